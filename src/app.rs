@@ -1,20 +1,52 @@
 use dioxus::prelude::*;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use tokio::sync::mpsc;
 
-use crate::gateway::{self, VoiceCommand, VoiceMessage};
-use crate::http::{self, ApiGuild, DiscordUser, DmChannel, GuildChannel, GuildMember, Relationship};
-use crate::state::{load_settings, load_token, login, logout, Message, PresenceStatus};
-use crate::ui::{Layout, LoginForm, MetricsOverlay, SettingsModal, ToastContainer, WelcomeModal};
+use crate::gateway::{self, GatewayEvent, VoiceCommand, VoiceEvent, VoiceMessage, VoiceStatus};
+use crate::http::{
+    self, ApiGuild, ApiMessage, ChannelType, DiscordUser, DmChannel, GroupDmEdit, GuildChannel, GuildMember,
+    Relationship, RelationshipAction, RelationshipType, Role,
+};
+use crate::member_list::{GuildSubscription, MemberList, MemberRequest, MEMBER_REQUEST_MAX_IDS};
+use crate::notifications::{self, DesktopNotification, NotifyContext};
+use crate::permissions::PermissionContext;
+use crate::rich_presence::{self, IpcClient, IpcEvent};
+use crate::state::{
+    is_snowflake, load_settings, load_token, login, logout, save_settings, snowflake_cmp, Activity, Message,
+    NotificationSettings, NotificationSettingsChange, OwnPresence, PresenceStatus, ReadState,
+};
+use crate::voice_state::VoiceStates;
+use crate::ui::{Layout, LoginForm, MetricsOverlay, ProfileCache, SettingsModal, ToastContainer, WelcomeModal};
 #[cfg(feature = "voice")]
-use crate::voice;
+use crate::push_to_talk::PushToTalk;
+#[cfg(feature = "voice")]
+use crate::state::VoiceInputMode;
+#[cfg(feature = "voice")]
+use crate::{voice, voice_audio};
 
 #[component]
 pub fn App() -> Element {
     let mut guilds = use_signal(|| Vec::<ApiGuild>::new());
     let mut selected_guild_id = use_signal(|| None::<String>);
     let mut guild_channels = use_signal(|| Vec::<GuildChannel>::new());
-    let mut guild_members = use_signal(|| Vec::<GuildMember>::new());
+    // Lazy member list of the selected guild (op 14) and the channel it was requested for.
+    let mut member_list = use_signal(MemberList::default);
+    let mut member_list_channel = use_signal(|| None::<String>);
+    let mut guild_roles = use_signal(|| Vec::<Role>::new());
+    let mut subscription_tx = use_signal(|| None::<mpsc::UnboundedSender<GuildSubscription>>);
+    // Guild ID -> user ID -> member, filled by op 8 for message authors and mentions.
+    let member_cache = use_signal(|| HashMap::<String, HashMap<String, GuildMember>>::new());
+    // (guild ID, user ID) already requested with op 8, found or not; never asked twice.
+    let mut requested_members = use_signal(|| HashSet::<(String, String)>::new());
+    let mut member_request_tx = use_signal(|| None::<mpsc::UnboundedSender<MemberRequest>>);
+    let mut user_profiles = use_signal(ProfileCache::new);
+    // Our roles in the selected guild; None until loaded (channels then fall back to 403 detection).
+    let mut guild_permissions = use_signal(|| None::<PermissionContext>);
+    let joined_threads = use_signal(|| HashSet::<String>::new());
+    // Parent channel ID -> archived threads, loaded on demand by the thread panel.
+    let mut archived_threads = use_signal(|| HashMap::<String, Vec<GuildChannel>>::new());
+    // Forum post ID -> starter message shown on its card (None while loading).
+    let mut forum_previews = use_signal(|| HashMap::<String, Option<Message>>::new());
     let mut token = use_signal(|| load_token());
     let mut current_user = use_signal(|| None::<DiscordUser>);
     let mut friends = use_signal(|| Vec::<Relationship>::new());
@@ -31,16 +63,170 @@ pub fn App() -> Element {
     let mut settings_open = use_signal(|| false);
     let mut toast_messages = use_signal(|| Vec::<(usize, String)>::new());
     let mut toast_counter = use_signal(|| 0usize);
-    let mut unread_counts = use_signal(|| HashMap::<String, u32>::new());
+    let read_states = use_signal(|| HashMap::<String, ReadState>::new());
+    // Read marker of the selected channel as it was when opened; drives the "New" divider.
+    let mut unread_marker = use_signal(|| None::<(String, String)>);
     let typing_users = use_signal(|| HashMap::<String, std::collections::HashMap<String, i64>>::new());
     let mut access_denied_channel_ids = use_signal(|| HashSet::<String>::new());
     let mut channel_error_display = use_signal(|| None::<(String, String)>);
-    // Channel to push presence updates (status) to the Gateway task.
-    let mut presence_tx = use_signal(|| None::<mpsc::UnboundedSender<PresenceStatus>>);
+    // Channel to push presence updates (status, custom status) to the Gateway task.
+    let mut presence_tx = use_signal(|| None::<mpsc::UnboundedSender<OwnPresence>>);
     let mut presence_map = use_signal(|| HashMap::<String, PresenceStatus>::new());
+    // Other users' activities (games, music, custom status) from PRESENCE_UPDATE.
+    let activity_map = use_signal(|| HashMap::<String, Vec<Activity>>::new());
+    // Applications publishing Rich Presence over the local IPC socket, by connection.
+    let mut ipc_clients = use_signal(BTreeMap::<u64, IpcClient>::new);
+    let ipc_activities = use_memo(move || {
+        ipc_clients()
+            .values()
+            .filter_map(IpcClient::presence_activity)
+            .collect::<Vec<Activity>>()
+    });
     let mut current_voice_channel_id = use_signal(|| None::<String>);
     let mut current_voice_guild_id = use_signal(|| None::<String>);
+    let mut self_mute = use_signal(|| false);
+    let mut self_deaf = use_signal(|| false);
+    // Participants of every voice channel, and who in our call is talking right now.
+    let mut voice_states = use_signal(VoiceStates::default);
+    let mut speaking_users = use_signal(HashSet::<String>::new);
+    let mut voice_status = use_signal(|| VoiceStatus::Connecting);
     let mut voice_cmd_tx = use_signal(|| None::<mpsc::UnboundedSender<VoiceCommand>>);
+    let notification_settings = use_signal(NotificationSettings::default);
+    // Channel names from READY, for notification titles (guild channels are otherwise only loaded per guild).
+    let channel_names = use_signal(|| HashMap::<String, String>::new());
+    let mut window_focused = use_signal(|| true);
+
+    // Track window focus so messages are only suppressed while the user is actually looking.
+    use_hook(move || {
+        spawn(async move {
+            let mut eval = document::eval(
+                r#"
+                window.addEventListener('focus', () => dioxus.send(true));
+                window.addEventListener('blur', () => dioxus.send(false));
+                dioxus.send(document.hasFocus());
+                await new Promise(() => {});
+                "#,
+            );
+            while let Ok(focused) = eval.recv::<bool>().await {
+                window_focused.set(focused);
+            }
+        });
+    });
+
+    // Clear the custom status when it expires. Keyed on the expiry alone: sliders write settings on
+    // every input event, and each re-arm cancels the previous timer.
+    let status_expires_at = use_memo(move || settings().custom_status.and_then(|c| c.expires_at));
+    let mut status_expiry_task = use_signal(|| None::<dioxus::core::Task>);
+    use_effect(move || {
+        let expires_at = status_expires_at();
+        if let Some(task) = status_expiry_task.write().take() {
+            task.cancel();
+        }
+        let Some(expires_at) = expires_at else {
+            return;
+        };
+        let task = spawn(async move {
+            let wait = (expires_at - chrono::Utc::now().timestamp()).max(0) as u64;
+            tokio::time::sleep(tokio::time::Duration::from_secs(wait)).await;
+            let mut s = settings();
+            if s.custom_status.as_ref().and_then(|c| c.expires_at) != Some(expires_at) {
+                return;
+            }
+            s.custom_status = None;
+            let mut settings = settings;
+            settings.set(s.clone());
+            let _ = save_settings(&s);
+            if let Some(tx) = presence_tx() {
+                let mut presence = s.own_presence();
+                presence.activities.extend(ipc_activities());
+                let _ = tx.send(presence);
+            }
+        });
+        status_expiry_task.set(Some(task));
+    });
+
+    // Local Rich Presence: track connected applications and look up their names.
+    use_hook(move || {
+        let (tx_ipc, mut rx_ipc) = mpsc::unbounded_channel::<IpcEvent>();
+        rich_presence::spawn_ipc_server(current_user, tx_ipc);
+        spawn(async move {
+            while let Some(event) = rx_ipc.recv().await {
+                match event {
+                    IpcEvent::Connected { conn, client_id } => {
+                        ipc_clients.write().insert(
+                            conn,
+                            IpcClient {
+                                client_id: client_id.clone(),
+                                app_name: None,
+                                app_icon: None,
+                                assets: None,
+                                activity: None,
+                            },
+                        );
+                        let Some(tok) = token() else { continue };
+                        spawn(async move {
+                            match http::get_rpc_application(&tok, &client_id).await {
+                                Ok(app) => {
+                                    if let Some(c) = ipc_clients.write().get_mut(&conn) {
+                                        c.app_name = Some(app.name);
+                                        c.app_icon = app.icon;
+                                    }
+                                }
+                                Err(e) => eprintln!("[rpc] application {}: {}", client_id, e),
+                            }
+                            match http::get_application_assets(&tok, &client_id).await {
+                                Ok(list) => {
+                                    if let Some(c) = ipc_clients.write().get_mut(&conn) {
+                                        c.assets = Some(list.into_iter().map(|a| (a.name.to_lowercase(), a.id)).collect());
+                                    }
+                                }
+                                Err(e) => eprintln!("[rpc] assets of {}: {}", client_id, e),
+                            }
+                        });
+                    }
+                    IpcEvent::SetActivity { conn, activity } => {
+                        if let Some(c) = ipc_clients.write().get_mut(&conn) {
+                            c.activity = activity.map(|a| *a);
+                        }
+                    }
+                    IpcEvent::Disconnected { conn } => {
+                        ipc_clients.write().remove(&conn);
+                    }
+                }
+            }
+        });
+    });
+
+    // Forward Rich Presence changes to the gateway (op 3).
+    use_effect(move || {
+        let activities = ipc_activities();
+        if let Some(tx) = presence_tx.peek().as_ref() {
+            let mut presence = settings.peek().own_presence();
+            presence.activities.extend(activities);
+            let _ = tx.send(presence);
+        }
+    });
+
+    // Input gate (voice activity / push-to-talk) follows the settings; the hotkey is rebound when it changes.
+    #[cfg(feature = "voice")]
+    {
+        let mut push_to_talk = use_signal(|| {
+            PushToTalk::new(voice_audio::shared_controls())
+                .map_err(|e| eprintln!("[voice] push-to-talk unavailable: {}", e))
+                .ok()
+        });
+        use_effect(move || {
+            let s = settings();
+            voice_audio::shared_controls().configure(&s);
+            let mut ptt = push_to_talk.write();
+            let Some(ptt) = ptt.as_mut() else { return };
+            let key = s.ptt_key.as_deref().filter(|_| s.voice_input_mode == VoiceInputMode::PushToTalk);
+            if let Err(e) = ptt.bind(key) {
+                eprintln!("[voice] {}", e);
+                show_toast(toast_messages, toast_counter, format!("Could not register push-to-talk key: {e}"));
+            }
+        });
+    }
 
     use_effect(move || {
         let tok = token();
@@ -79,21 +265,129 @@ pub fn App() -> Element {
         let gid = selected_guild_id();
         if tok.is_none() || gid.is_none() {
             guild_channels.set(Vec::new());
-            guild_members.set(Vec::new());
+            member_list.set(MemberList::default());
+            guild_roles.set(Vec::new());
+            guild_permissions.set(None);
             return;
         }
         let t = tok.unwrap();
         let gid = gid.unwrap();
+        guild_permissions.set(None);
+        guild_roles.set(Vec::new());
+        member_list.set(MemberList {
+            guild_id: gid.clone(),
+            ..Default::default()
+        });
+        archived_threads.set(HashMap::new());
+        forum_previews.set(HashMap::new());
         spawn(async move {
-            if let Ok(chs) = http::get_guild_channels(&t, &gid).await {
-                guild_channels.set(chs);
+            if let Ok(mut chs) = http::get_guild_channels(&t, &gid).await {
+                // Active threads live alongside the channels so lookups and permissions work for both.
+                match http::get_active_threads(&t, &gid).await {
+                    Ok(list) => {
+                        let mut joined = joined_threads;
+                        joined.write().extend(list.members.into_iter().filter_map(|m| m.id));
+                        chs.extend(list.threads);
+                    }
+                    Err(e) => eprintln!("[threads] could not load active threads for {}: {}", gid, e),
+                }
+                if selected_guild_id.peek().as_ref() == Some(&gid) {
+                    guild_channels.set(chs);
+                }
             }
-            if let Ok(mems) = http::get_guild_members(&t, &gid, 100).await {
-                guild_members.set(mems);
+            let uid = current_user.peek().as_ref().map(|u| u.id.clone()).unwrap_or_default();
+            let is_owner = guilds.peek().iter().any(|g| g.id == gid && g.owner);
+            match (http::get_guild_roles(&t, &gid).await, http::get_current_member(&t, &gid).await) {
+                // Ignore late replies after switching guilds.
+                (Ok(_), Ok(_)) if selected_guild_id.peek().as_ref() != Some(&gid) => {}
+                (Ok(roles), Ok(me)) => {
+                    guild_permissions.set(Some(PermissionContext::new(gid.clone(), uid, is_owner, &roles, me.roles)));
+                    guild_roles.set(roles);
+                }
+                (Err(e), _) | (_, Err(e)) => eprintln!("[permissions] could not load roles for {}: {}", gid, e),
             }
         });
     });
 
+    // Member list: subscribe to the open channel's list (threads use their parent's, other channels
+    // the first text channel). Lists only differ by who can view the channel, so this rarely re-syncs.
+    use_effect(move || {
+        let Some(gid) = selected_guild_id() else {
+            member_list_channel.set(None);
+            return;
+        };
+        let chs = guild_channels();
+        let selected = selected_channel_id().and_then(|sid| chs.iter().find(|c| c.id == sid).cloned());
+        let list_channel = match selected {
+            Some(c) if c.r#type.is_thread() => c.parent_id.clone(),
+            Some(c) if c.r#type.is_text_like() => Some(c.id.clone()),
+            _ => {
+                let mut text: Vec<&GuildChannel> = chs.iter().filter(|c| c.r#type == ChannelType::Text).collect();
+                text.sort_by_key(|c| c.position);
+                text.first().map(|c| c.id.clone())
+            }
+        };
+        let Some(cid) = list_channel else { return };
+        if member_list_channel.peek().as_ref() == Some(&cid) {
+            return;
+        }
+        member_list_channel.set(Some(cid.clone()));
+        if let Some(tx) = subscription_tx() {
+            let _ = tx.send(GuildSubscription::for_rows(gid, cid, 0, 0));
+        }
+    });
+
+    // Resolve authors and mentions of the loaded guild messages (op 8, answered by GUILD_MEMBERS_CHUNK).
+    use_effect(move || {
+        let list = messages();
+        let (Some(gid), Some(tx)) = (selected_guild_id(), member_request_tx()) else {
+            return;
+        };
+        let mut missing: Vec<String> = Vec::new();
+        {
+            let mut requested = requested_members.write();
+            for m in list.iter().filter(|m| !matches!(&m.guild_id, Some(g) if *g != gid)) {
+                for uid in std::iter::once(&m.author_id).chain(m.mention_ids.iter()) {
+                    if is_snowflake(uid) && requested.insert((gid.clone(), uid.clone())) {
+                        missing.push(uid.clone());
+                    }
+                }
+            }
+        }
+        for ids in missing.chunks(MEMBER_REQUEST_MAX_IDS) {
+            let _ = tx.send(MemberRequest {
+                guild_id: gid.clone(),
+                user_ids: ids.to_vec(),
+            });
+        }
+    });
+
+    // Resolve voice participants of the selected guild; READY voice states carry no member.
+    use_effect(move || {
+        let states = voice_states();
+        let (Some(gid), Some(tx)) = (selected_guild_id(), member_request_tx()) else {
+            return;
+        };
+        let mut missing: Vec<String> = Vec::new();
+        {
+            let cache = member_cache.peek();
+            let known = cache.get(&gid);
+            let mut requested = requested_members.write();
+            for vs in states.in_guild(&gid) {
+                let cached = known.is_some_and(|m| m.contains_key(&vs.user_id));
+                if !cached && vs.member.is_none() && requested.insert((gid.clone(), vs.user_id.clone())) {
+                    missing.push(vs.user_id.clone());
+                }
+            }
+        }
+        for ids in missing.chunks(MEMBER_REQUEST_MAX_IDS) {
+            let _ = tx.send(MemberRequest {
+                guild_id: gid.clone(),
+                user_ids: ids.to_vec(),
+            });
+        }
+    });
+
     // Gateway: spawn when logged in, receive real-time messages and typing.
     // Use signal to avoid re-spawning on every effect run (would create duplicate connections).
     let mut gateway_spawned = use_signal(|| None::<String>);
@@ -115,54 +409,345 @@ pub fn App() -> Element {
         }
         gateway_spawned.set(Some(uid.clone()));
         let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+        let (tx_events, mut rx_events) = mpsc::unbounded_channel::<GatewayEvent>();
         let (tx_typing, mut rx_typing) = mpsc::unbounded_channel::<(String, String)>();
-        let (tx_presence, rx_presence) = mpsc::unbounded_channel::<PresenceStatus>();
+        let (tx_presence, rx_presence) = mpsc::unbounded_channel::<OwnPresence>();
         let (tx_presence_updates, mut rx_presence_updates) =
-            mpsc::unbounded_channel::<(String, String)>();
+            mpsc::unbounded_channel::<(String, String, Vec<Activity>)>();
         presence_tx.set(Some(tx_presence.clone()));
         let (tx_voice_cmd, rx_voice_cmd) = mpsc::unbounded_channel::<VoiceCommand>();
+        let (tx_subscriptions, rx_subscriptions) = mpsc::unbounded_channel::<GuildSubscription>();
+        subscription_tx.set(Some(tx_subscriptions));
+        member_list_channel.set(None);
+        let (tx_member_requests, rx_member_requests) = mpsc::unbounded_channel::<MemberRequest>();
+        member_request_tx.set(Some(tx_member_requests));
+        requested_members.write().clear();
         let (tx_voice_message, mut rx_voice_message) = mpsc::unbounded_channel::<VoiceMessage>();
+        let (tx_voice_events, mut rx_voice_events) = mpsc::unbounded_channel::<VoiceEvent>();
+        speaking_users.write().clear();
         voice_cmd_tx.set(Some(tx_voice_cmd.clone()));
-        let current_user_id = Some(uid);
-        let initial_presence = settings().presence;
+        let (tx_notify, rx_notify) = mpsc::unbounded_channel::<DesktopNotification>();
+        let (tx_clicks, mut rx_clicks) = mpsc::unbounded_channel::<(Option<String>, String)>();
+        notifications::spawn_notifier(rx_notify, tx_clicks);
+        let current_user_id = Some(uid.clone());
+        let mut initial_presence = settings().own_presence();
+        initial_presence.activities.extend(ipc_activities.peek().iter().cloned());
         gateway::spawn_gateway(
             t.clone(),
             current_user_id,
-            tx,
-            Some(tx_typing),
-            Some(tx_presence_updates),
             initial_presence,
-            rx_presence,
-            rx_voice_cmd,
-            tx_voice_message,
+            gateway::GatewayChannels {
+                tx,
+                tx_events,
+                tx_typing: Some(tx_typing),
+                tx_presence_updates: Some(tx_presence_updates),
+                presence_rx: rx_presence,
+                rx_voice_cmd,
+                rx_subscriptions,
+                rx_member_requests,
+                tx_voice_message,
+            },
         );
         #[cfg(feature = "voice")]
-        voice::spawn_voice_task(rx_voice_message);
+        voice::spawn_voice_task(rx_voice_message, tx_voice_events);
         #[cfg(not(feature = "voice"))]
         spawn(async move {
+            drop(tx_voice_events);
             while rx_voice_message.recv().await.is_some() {}
         });
+        spawn(async move {
+            while let Some(event) = rx_voice_events.recv().await {
+                match event {
+                    VoiceEvent::Speaking { user_id, speaking } => {
+                        if speaking {
+                            speaking_users.write().insert(user_id);
+                        } else {
+                            speaking_users.write().remove(&user_id);
+                        }
+                    }
+                    VoiceEvent::Notice(message) => show_toast(toast_messages, toast_counter, message),
+                    VoiceEvent::Status(status) => voice_status.set(status),
+                    VoiceEvent::GaveUp => {
+                        if let Some(ref tx) = voice_cmd_tx() {
+                            let _ = tx.send(VoiceCommand::Leave);
+                        }
+                        current_voice_channel_id.set(None);
+                        current_voice_guild_id.set(None);
+                        speaking_users.write().clear();
+                    }
+                }
+            }
+        });
         let mut msgs_sig = messages;
         let sel_sig = selected_channel_id;
-        let mut unread_sig = unread_counts;
+        let mut read_sig = read_states;
         let mut typing_sig = typing_users;
+        let mut notif_sig = notification_settings;
+        let mut names_sig = channel_names;
+        let mut joined_sig = joined_threads;
+        let mut channels_sig = guild_channels;
+        let mut archived_sig = archived_threads;
+        let mut member_list_sig = member_list;
+        let mut member_cache_sig = member_cache;
+        let mut friends_sig = friends;
+        let mut dm_sig = dm_channels;
+        let mut sel_channel_sig = selected_channel_id;
+        let guild_sig = selected_guild_id;
+        let ack_token = t.clone();
         spawn(async move {
             while let Some(msg) = rx.recv().await {
                 let sel = sel_sig();
                 let is_current = sel.as_ref() == Some(&msg.channel_id);
+                let is_mine = msg.author_id == uid;
+                let s = settings();
+                let notify = notifications::should_notify(
+                    &msg,
+                    &NotifyContext {
+                        enabled: s.notifications_enabled,
+                        user_id: &uid,
+                        presence: s.presence,
+                        window_focused: window_focused(),
+                        selected_channel_id: sel.as_deref(),
+                        settings: &notif_sig(),
+                        keywords: &s.notification_keywords,
+                    },
+                );
+                if notify {
+                    let names = names_sig();
+                    let guild_name = msg
+                        .guild_id
+                        .as_ref()
+                        .and_then(|gid| guilds().iter().find(|g| &g.id == gid).map(|g| g.name.clone()));
+                    let channel_name = names.get(&msg.channel_id).map(|n| n.as_str());
+                    let _ = tx_notify.send(notifications::build_notification(&msg, channel_name, guild_name.as_deref()));
+                }
+                let mut map = read_sig();
+                let entry = map.entry(msg.channel_id.clone()).or_default();
+                if msg.guild_id.is_some() {
+                    entry.guild_id = msg.guild_id.clone();
+                }
+                entry.last_message_id = Some(msg.id.clone());
+                let suppress_everyone = notif_sig()
+                    .for_guild(msg.guild_id.as_deref())
+                    .map(|g| g.suppress_everyone)
+                    .unwrap_or(false);
+                if !is_current && !is_mine && is_mention_for(&msg, &uid, suppress_everyone) {
+                    entry.mention_count += 1;
+                }
+                read_sig.set(map);
                 if is_current {
                     let mut list = msgs_sig();
                     if !list.iter().any(|m| m.id == msg.id) {
                         list.push(msg.clone());
                         msgs_sig.set(list);
                     }
-                } else {
-                    let mut counts = unread_sig();
-                    *counts.entry(msg.channel_id).or_insert(0) += 1;
-                    unread_sig.set(counts);
+                }
+                // Our own messages are acked by the server; the open channel is acked by us.
+                if is_mine {
+                    advance_read_state(&mut read_sig, &msg.channel_id, &msg.id);
+                } else if is_current && advance_read_state(&mut read_sig, &msg.channel_id, &msg.id) {
+                    schedule_ack(ack_token.clone(), read_sig, msg.channel_id.clone(), msg.id.clone());
                 }
             }
         });
+        spawn(async move {
+            while let Some(event) = rx_events.recv().await {
+                match event {
+                    GatewayEvent::Ready(ready) => {
+                        let mut notif = NotificationSettings::default();
+                        for gs in ready.guild_settings {
+                            notif.insert(gs);
+                        }
+                        notif.guild_defaults = ready.guild_notification_defaults.into_iter().collect();
+                        notif_sig.set(notif);
+                        let mut names = names_sig();
+                        let mut map = read_sig();
+                        for ch in ready.channels {
+                            if let Some(name) = ch.name {
+                                names.insert(ch.channel_id.clone(), name);
+                            }
+                            let entry = map.entry(ch.channel_id).or_default();
+                            entry.guild_id = ch.guild_id;
+                            entry.last_message_id = ch.last_message_id;
+                        }
+                        for rs in ready.read_states {
+                            let entry = map.entry(rs.id).or_default();
+                            entry.last_read_id = rs.last_message_id;
+                            entry.mention_count = rs.mention_count;
+                        }
+                        // No read state yet means we never opened it; start from "read" so only new messages count.
+                        for entry in map.values_mut() {
+                            if entry.last_read_id.is_none() && entry.mention_count == 0 {
+                                entry.last_read_id = entry.last_message_id.clone();
+                            }
+                        }
+                        read_sig.set(map);
+                        names_sig.set(names);
+                        joined_sig.set(ready.joined_threads.into_iter().collect());
+                        voice_states.write().reset(ready.voice_states);
+                    }
+                    GatewayEvent::MessageAck { channel_id, message_id } => {
+                        advance_read_state(&mut read_sig, &channel_id, &message_id);
+                    }
+                    GatewayEvent::GuildSettingsUpdate(gs) => {
+                        let mut notif = notif_sig();
+                        notif.insert(gs);
+                        notif_sig.set(notif);
+                    }
+                    // Only the open guild's threads are kept; others are fetched when it is opened.
+                    GatewayEvent::ThreadUpsert(thread) => {
+                        if thread.guild_id.is_some() && thread.guild_id != *guild_sig.peek() {
+                            continue;
+                        }
+                        if let Some(pid) = thread.parent_id.clone() {
+                            let mut archived = archived_sig.write();
+                            if let Some(list) = archived.get_mut(&pid) {
+                                list.retain(|t| t.id != thread.id);
+                                if thread.is_archived() {
+                                    list.insert(0, thread.clone());
+                                }
+                            }
+                        }
+                        let mut chs = channels_sig.write();
+                        match chs.iter_mut().find(|c| c.id == thread.id) {
+                            Some(existing) => *existing = thread,
+                            None => chs.push(thread),
+                        }
+                    }
+                    GatewayEvent::ThreadDelete { id, guild_id } => {
+                        if guild_id.is_some() && guild_id != *guild_sig.peek() {
+                            continue;
+                        }
+                        channels_sig.write().retain(|c| c.id != id);
+                        for list in archived_sig.write().values_mut() {
+                            list.retain(|t| t.id != id);
+                        }
+                        joined_sig.write().remove(&id);
+                        if sel_channel_sig.peek().as_ref() == Some(&id) {
+                            sel_channel_sig.set(None);
+                        }
+                    }
+                    GatewayEvent::ThreadListSync { guild_id, channel_ids, threads, joined } => {
+                        joined_sig.write().extend(joined);
+                        if guild_sig.peek().as_ref() != Some(&guild_id) {
+                            continue;
+                        }
+                        let mut chs = channels_sig.write();
+                        // The open thread stays even if it dropped out of the active list.
+                        let open = sel_channel_sig.peek().clone();
+                        chs.retain(|c| {
+                            !c.r#type.is_thread()
+                                || open.as_ref() == Some(&c.id)
+                                || channel_ids
+                                    .as_ref()
+                                    .map(|ids| !c.parent_id.as_ref().map(|p| ids.contains(p)).unwrap_or(false))
+                                    .unwrap_or(false)
+                        });
+                        for t in threads {
+                            if !chs.iter().any(|c| c.id == t.id) {
+                                chs.push(t);
+                            }
+                        }
+                    }
+                    GatewayEvent::ThreadMembership { thread_id, joined } => {
+                        if joined {
+                            joined_sig.write().insert(thread_id);
+                        } else {
+                            joined_sig.write().remove(&thread_id);
+                        }
+                    }
+                    GatewayEvent::ThreadMemberCount { thread_id, member_count } => {
+                        if let Some(t) = channels_sig.write().iter_mut().find(|c| c.id == thread_id) {
+                            t.member_count = Some(member_count);
+                        }
+                    }
+                    GatewayEvent::MemberListUpdate(update) => {
+                        if guild_sig.peek().as_ref() == Some(&update.guild_id) {
+                            member_list_sig.write().apply(update);
+                        }
+                    }
+                    GatewayEvent::PrivateChannelUpsert(ch) => upsert_dm_channel(&mut dm_sig.write(), ch),
+                    GatewayEvent::PrivateChannelDelete { id } => {
+                        dm_sig.write().retain(|c| c.id != id);
+                        if sel_channel_sig.peek().as_ref() == Some(&id) {
+                            sel_channel_sig.set(None);
+                        }
+                    }
+                    GatewayEvent::GroupRecipient { channel_id, user, added } => {
+                        if let Some(ch) = dm_sig.write().iter_mut().find(|c| c.id == channel_id) {
+                            ch.recipients.retain(|u| u.id != user.id);
+                            if added {
+                                ch.recipients.push(user);
+                            }
+                        }
+                    }
+                    GatewayEvent::VoiceStateUpdate(vs) => {
+                        // Guild voice state updates carry the member; keep it for names and avatars.
+                        if let (Some(gid), Some(member)) = (vs.guild_id.clone(), vs.member.clone()) {
+                            if let Some(uid) = member.user.as_ref().map(|u| u.id.clone()) {
+                                member_cache_sig.write().entry(gid).or_default().insert(uid, member);
+                            }
+                        }
+                        voice_states.write().apply(vs);
+                    }
+                    GatewayEvent::VoiceChannelChanged { guild_id, channel_id } => match channel_id {
+                        Some(channel_id) => {
+                            let name = names_sig().get(&channel_id).cloned();
+                            current_voice_guild_id.set(guild_id);
+                            current_voice_channel_id.set(Some(channel_id));
+                            if let Some(name) = name {
+                                show_toast(toast_messages, toast_counter, format!("Moved to {name}"));
+                            }
+                        }
+                        None => {
+                            current_voice_channel_id.set(None);
+                            current_voice_guild_id.set(None);
+                            speaking_users.write().clear();
+                            show_toast(toast_messages, toast_counter, "Disconnected from voice".to_string());
+                        }
+                    },
+                    GatewayEvent::RelationshipUpsert { id, kind, user } => {
+                        upsert_relationship(&mut friends_sig.write(), id, kind, user);
+                    }
+                    GatewayEvent::RelationshipRemove { id } => {
+                        friends_sig.write().retain(|r| r.id != id);
+                    }
+                    GatewayEvent::MembersChunk { guild_id, members, not_found } => {
+                        if !not_found.is_empty() {
+                            eprintln!("[members] {} of the requested users are not in guild {}", not_found.len(), guild_id);
+                        }
+                        let mut cache = member_cache_sig.write();
+                        let guild = cache.entry(guild_id).or_default();
+                        for member in members {
+                            if let Some(uid) = member.user.as_ref().map(|u| u.id.clone()) {
+                                guild.insert(uid, member);
+                            }
+                        }
+                    }
+                }
+            }
+        });
+        // Clicked notification: open its channel and bring the window forward.
+        let mut guild_sel = selected_guild_id;
+        let mut channel_sel = selected_channel_id;
+        let mut marker_sig = unread_marker;
+        let click_token = t.clone();
+        spawn(async move {
+            while let Some((guild_id, channel_id)) = rx_clicks.recv().await {
+                #[cfg(feature = "desktop")]
+                {
+                    let window = dioxus_desktop::window();
+                    window.window.set_minimized(false);
+                    window.window.set_focus();
+                }
+                if guild_sel() != guild_id {
+                    guild_sel.set(guild_id);
+                }
+                marker_sig.set(unread_marker_for(&read_sig(), &channel_id));
+                mark_channel_read(Some(click_token.clone()), read_sig, &channel_id);
+                channel_sel.set(Some(channel_id));
+            }
+        });
         spawn(async move {
             while let Some((channel_id, user_id)) = rx_typing.recv().await {
                 let now = chrono::Utc::now().timestamp();
@@ -174,8 +759,9 @@ pub fn App() -> Element {
             }
         });
         let mut presence_sig = presence_map;
+        let mut activity_sig = activity_map;
         spawn(async move {
-            while let Some((user_id, status_str)) = rx_presence_updates.recv().await {
+            while let Some((user_id, status_str, activities)) = rx_presence_updates.recv().await {
                 let status = match status_str.as_str() {
                     "online" => PresenceStatus::Online,
                     "idle" => PresenceStatus::Idle,
@@ -183,6 +769,11 @@ pub fn App() -> Element {
                     _ => PresenceStatus::Invisible,
                 };
                 eprintln!("[presence app] user_id={} status={} -> {:?}", user_id, status_str, status);
+                if activities.is_empty() {
+                    activity_sig.write().remove(&user_id);
+                } else {
+                    activity_sig.write().insert(user_id.clone(), activities);
+                }
                 let mut map = presence_sig();
                 map.insert(user_id, status);
                 presence_sig.set(map);
@@ -200,6 +791,7 @@ pub fn App() -> Element {
         let mut counter = toast_counter;
         let mut access_denied = access_denied_channel_ids;
         let mut channel_error = channel_error_display;
+        let mut read_sig = read_states;
         if let (Some(t), Some(cid)) = (tok, ch_id) {
             messages.set(Vec::new());
             has_more_older.set(false);
@@ -212,22 +804,13 @@ pub fn App() -> Element {
                         den.remove(&cid);
                         access_denied.set(den);
                         channel_error.set(None);
-                        let msgs: Vec<Message> = api_msgs
-                            .into_iter()
-                            .map(|m| Message {
-                                id: m.id,
-                                channel_id: m.channel_id,
-                                author_id: m.author.as_ref().map(|a| a.id.clone()).unwrap_or_default(),
-                                author_username: m
-                                    .author
-                                    .as_ref()
-                                    .map(|a| a.global_name.clone().or(Some(a.username.clone())).unwrap()),
-                                content: m.content,
-                                timestamp: m.timestamp,
-                                sending: false,
-                            })
-                            .collect();
+                        let msgs: Vec<Message> = api_msgs.into_iter().map(message_from_api).collect();
                         has_more.set(msgs.len() == 50);
+                        if let Some(newest) = msgs.first() {
+                            if advance_read_state(&mut read_sig, &cid, &newest.id) {
+                                schedule_ack(t.clone(), read_sig, cid.clone(), newest.id.clone());
+                            }
+                        }
                         msgs_signal.set(msgs.into_iter().rev().collect::<Vec<_>>());
                     }
                     Err(e) => {
@@ -269,7 +852,10 @@ pub fn App() -> Element {
                 guilds,
                 selected_guild_id,
                 guild_channels,
-                guild_members,
+                member_list,
+                member_cache,
+                guild_roles,
+                user_profiles,
                 friends,
                 dm_channels,
                 messages,
@@ -279,38 +865,224 @@ pub fn App() -> Element {
                 loading_older,
                 loading_messages,
                 settings,
-                unread_counts,
+                read_states,
+                notification_settings,
+                guild_permissions,
+                joined_threads,
+                archived_threads,
+                forum_previews,
                 typing_users,
+                unread_marker,
                 access_denied_channel_ids,
                 channel_error_display,
                 presence_map,
+                activity_map,
                 current_voice_channel_id,
                 current_voice_guild_id,
+                voice_states,
+                speaking_users,
+                voice_status,
+                self_mute,
+                self_deaf,
                 on_select_guild: move |id: Option<String>| {
                     selected_guild_id.set(id);
                     selected_channel_id.set(None);
                 },
                 on_select_channel: move |id: Option<String>| {
                     if let Some(ref cid) = id {
-                        let mut counts = unread_counts();
-                        counts.insert(cid.clone(), 0);
-                        unread_counts.set(counts);
+                        unread_marker.set(unread_marker_for(&read_states(), cid));
+                        mark_channel_read(token(), read_states, cid);
                     }
                     selected_channel_id.set(id);
                 },
+                on_open_thread: move |tid: String| {
+                    // Archived threads are not in the channel list until opened.
+                    let archived = archived_threads().values().flatten().find(|t| t.id == tid).cloned();
+                    if let Some(t) = archived {
+                        if !guild_channels.peek().iter().any(|c| c.id == tid) {
+                            guild_channels.write().push(t);
+                        }
+                    }
+                    unread_marker.set(unread_marker_for(&read_states(), &tid));
+                    mark_channel_read(token(), read_states, &tid);
+                    selected_channel_id.set(Some(tid));
+                },
+                on_follow_thread: move |(tid, join): (String, bool)| {
+                    let Some(tok) = token() else { return };
+                    let mut joined = joined_threads;
+                    let was_joined = joined.peek().contains(&tid);
+                    if join {
+                        joined.write().insert(tid.clone());
+                    } else {
+                        joined.write().remove(&tid);
+                    }
+                    let toast = toast_messages;
+                    let counter = toast_counter;
+                    spawn(async move {
+                        if let Err(e) = http::set_thread_membership(&tok, &tid, join).await {
+                            if was_joined {
+                                joined.write().insert(tid.clone());
+                            } else {
+                                joined.write().remove(&tid);
+                            }
+                            let action = if join { "follow" } else { "leave" };
+                            show_toast(toast, counter, format!("Could not {action} thread: {e}"));
+                        }
+                    });
+                },
+                on_load_archived_threads: move |cid: String| {
+                    let Some(tok) = token() else { return };
+                    let toast = toast_messages;
+                    let counter = toast_counter;
+                    spawn(async move {
+                        let public = http::get_archived_threads(&tok, &cid, http::ArchivedThreads::Public, None).await;
+                        let private = http::get_archived_threads(&tok, &cid, http::ArchivedThreads::JoinedPrivate, None).await;
+                        let mut list = match public {
+                            Ok(l) => l.threads,
+                            Err(e) => {
+                                show_toast(toast, counter, format!("Could not load archived threads: {e}"));
+                                return;
+                            }
+                        };
+                        // Private threads we are not in are simply not listed.
+                        if let Ok(l) = private {
+                            list.extend(l.threads);
+                        }
+                        let archive_time = |t: &GuildChannel| {
+                            t.thread_metadata.as_ref().and_then(|m| m.archive_timestamp.clone()).unwrap_or_default()
+                        };
+                        list.sort_by_key(|t| std::cmp::Reverse(archive_time(t)));
+                        archived_threads.write().insert(cid, list);
+                    });
+                },
+                on_create_thread: move |(cid, message_id, name, private): (String, Option<String>, String, bool)| {
+                    let Some(tok) = token() else { return };
+                    let toast = toast_messages;
+                    let counter = toast_counter;
+                    let mut joined = joined_threads;
+                    spawn(async move {
+                        match http::start_thread(&tok, &cid, message_id.as_deref(), &name, private).await {
+                            Ok(thread) => {
+                                let tid = thread.id.clone();
+                                joined.write().insert(tid.clone());
+                                if !guild_channels.peek().iter().any(|c| c.id == tid) {
+                                    guild_channels.write().push(thread);
+                                }
+                                unread_marker.set(None);
+                                selected_channel_id.set(Some(tid));
+                            }
+                            Err(e) => show_toast(toast, counter, format!("Could not create thread: {e}")),
+                        }
+                    });
+                },
+                on_load_forum_previews: move |ids: Vec<String>| {
+                    let Some(tok) = token() else { return };
+                    {
+                        let mut previews = forum_previews.write();
+                        for id in ids.iter() {
+                            previews.entry(id.clone()).or_insert(None);
+                        }
+                    }
+                    spawn(async move {
+                        for id in ids {
+                            // The starter message shares the post's ID.
+                            match http::get_message(&tok, &id, &id).await {
+                                Ok(m) => {
+                                    forum_previews.write().insert(id, Some(message_from_api(m)));
+                                }
+                                Err(e) => eprintln!("[forum] no starter message for {}: {}", id, e),
+                            }
+                        }
+                    });
+                },
+                on_create_forum_post: move |(fid, title, content, tags): (String, String, String, Vec<String>)| {
+                    let Some(tok) = token() else { return };
+                    let toast = toast_messages;
+                    let counter = toast_counter;
+                    let mut joined = joined_threads;
+                    spawn(async move {
+                        match http::create_forum_post(&tok, &fid, &title, &content, &tags).await {
+                            Ok(post) => {
+                                let pid = post.id.clone();
+                                joined.write().insert(pid.clone());
+                                if !guild_channels.peek().iter().any(|c| c.id == pid) {
+                                    guild_channels.write().push(post);
+                                }
+                                unread_marker.set(None);
+                                selected_channel_id.set(Some(pid));
+                            }
+                            Err(e) => show_toast(toast, counter, format!("Could not create post: {e}")),
+                        }
+                    });
+                },
+                on_member_list_range: move |(first, last): (u32, u32)| {
+                    let (Some(gid), Some(cid)) = (selected_guild_id(), member_list_channel()) else { return };
+                    if let Some(tx) = subscription_tx() {
+                        let _ = tx.send(GuildSubscription::for_rows(gid, cid, first, last));
+                    }
+                },
+                on_mark_read: move |cid: String| {
+                    mark_channel_read(token(), read_states, &cid);
+                },
+                on_mark_guild_read: move |gid: String| {
+                    let mut read_sig = read_states;
+                    let unread: Vec<(String, String)> = read_states()
+                        .iter()
+                        .filter(|(_, r)| r.guild_id.as_deref() == Some(gid.as_str()))
+                        .filter(|(_, r)| r.is_unread() || r.mention_count > 0)
+                        .filter_map(|(cid, r)| r.last_message_id.clone().map(|mid| (cid.clone(), mid)))
+                        .collect();
+                    let acks: Vec<(String, String)> = unread
+                        .into_iter()
+                        .filter(|(cid, mid)| advance_read_state(&mut read_sig, cid, mid))
+                        .collect();
+                    let Some(tok) = token() else { return };
+                    if acks.is_empty() {
+                        return;
+                    }
+                    spawn(async move {
+                        if let Err(e) = http::ack_bulk(&tok, &acks).await {
+                            eprintln!("[read state] bulk ack failed for guild {}: {}", gid, e);
+                        }
+                    });
+                },
+                on_update_notifications: move |change: NotificationSettingsChange| {
+                    let Some(tok) = token() else { return };
+                    let mut notif_sig = notification_settings;
+                    let previous = notif_sig();
+                    let mut updated = previous.clone();
+                    updated.apply(&change);
+                    notif_sig.set(updated);
+                    let toast = toast_messages;
+                    let counter = toast_counter;
+                    spawn(async move {
+                        match http::update_guild_settings(&tok, change.guild_id.as_deref(), &change.to_patch()).await {
+                            Ok(gs) => {
+                                let mut notif = notif_sig();
+                                notif.insert(gs);
+                                notif_sig.set(notif);
+                            }
+                            Err(e) => {
+                                notif_sig.set(previous);
+                                show_toast(toast, counter, format!("Could not update notification settings: {e}"));
+                            }
+                        }
+                    });
+                },
                 on_join_voice: move |(guild_id, channel_id): (Option<String>, String)| {
                     if let Some(ref tx) = voice_cmd_tx() {
                         let s = settings();
                         let _ = tx.send(VoiceCommand::Join {
                             guild_id: guild_id.clone(),
                             channel_id: channel_id.clone(),
-                            self_mute: false,
-                            self_deaf: false,
+                            self_mute: self_mute(),
+                            self_deaf: self_deaf(),
                             input_device: s.voice_input_device.clone(),
                             output_device: s.voice_output_device.clone(),
                         });
                         current_voice_guild_id.set(guild_id);
                         current_voice_channel_id.set(Some(channel_id));
+                        voice_status.set(VoiceStatus::Connecting);
                     }
                 },
                 on_leave_voice: move |_| {
@@ -318,6 +1090,14 @@ pub fn App() -> Element {
                         let _ = tx.send(VoiceCommand::Leave);
                         current_voice_channel_id.set(None);
                         current_voice_guild_id.set(None);
+                        speaking_users.write().clear();
+                    }
+                },
+                on_set_self_voice: move |(mute, deaf): (bool, bool)| {
+                    self_mute.set(mute);
+                    self_deaf.set(deaf);
+                    if let Some(ref tx) = voice_cmd_tx() {
+                        let _ = tx.send(VoiceCommand::SetSelfState { self_mute: mute, self_deaf: deaf });
                     }
                 },
                 on_send_message: move |arg: (String, String)| {
@@ -337,9 +1117,10 @@ pub fn App() -> Element {
                         channel_id: channel_id.clone(),
                         author_id: current_uid.clone().unwrap_or_default(),
                         author_username: current_user().as_ref().map(|u| u.global_name.clone().or(Some(u.username.clone())).unwrap()),
+                        author_avatar: current_user().as_ref().and_then(|u| u.avatar.clone()),
                         content: trimmed.clone(),
-                        timestamp: None,
                         sending: true,
+                        ..Default::default()
                     };
                     let mut msgs = messages;
                     let mut list = msgs();
@@ -347,24 +1128,15 @@ pub fn App() -> Element {
                     msgs.set(list);
                     let mut toast = toast_messages;
                     let mut counter = toast_counter;
+                    let mut read_sig = read_states;
                     spawn(async move {
                         match http::send_message(&tok, &channel_id, &trimmed).await {
                             Ok(api_msg) => {
-                                let real = Message {
-                                    id: api_msg.id.clone(),
-                                    channel_id: api_msg.channel_id,
-                                    author_id: api_msg.author.as_ref().map(|a| a.id.clone()).unwrap_or_default(),
-                                    author_username: api_msg
-                                        .author
-                                        .as_ref()
-                                        .map(|a| a.global_name.clone().or(Some(a.username.clone())).unwrap()),
-                                    content: api_msg.content,
-                                    timestamp: api_msg.timestamp,
-                                    sending: false,
-                                };
+                                advance_read_state(&mut read_sig, &api_msg.channel_id, &api_msg.id);
+                                let real = message_from_api(api_msg);
                                 let mut list = msgs();
                                 list.retain(|m| m.id != temp_id);
-                                if !list.iter().any(|m| m.id == api_msg.id) {
+                                if !list.iter().any(|m| m.id == real.id) {
                                     list.push(real);
                                 }
                                 msgs.set(list);
@@ -404,21 +1176,7 @@ pub fn App() -> Element {
                     let mut loading = loading_older;
                     spawn(async move {
                         if let Ok(api_msgs) = http::fetch_channel_messages_before(&tok, &channel_id, &before_message_id, 50).await {
-                            let older: Vec<Message> = api_msgs
-                                .into_iter()
-                                .map(|m| Message {
-                                    id: m.id,
-                                    channel_id: m.channel_id,
-                                    author_id: m.author.as_ref().map(|a| a.id.clone()).unwrap_or_default(),
-                                    author_username: m
-                                        .author
-                                        .as_ref()
-                                        .map(|a| a.global_name.clone().or(Some(a.username.clone())).unwrap()),
-                                    content: m.content,
-                                    timestamp: m.timestamp,
-                                    sending: false,
-                                })
-                                .collect();
+                            let older: Vec<Message> = api_msgs.into_iter().map(message_from_api).collect();
                             has_more.set(older.len() == 50);
                             let mut list = msgs();
                             list.splice(0..0, older.into_iter().rev());
@@ -458,10 +1216,116 @@ pub fn App() -> Element {
                                 list.insert(0, ch.clone());
                                 chs.set(list);
                             }
+                            // Also reached from profile popouts inside a guild; DMs live on the home view.
+                            selected_guild_id.set(None);
                             sel.set(Some(ch.id));
                         }
                     });
                 },
+                on_relationship_action: move |(user_id, action): (String, RelationshipAction)| {
+                    let Some(tok) = token() else { return };
+                    let toast = toast_messages;
+                    let counter = toast_counter;
+                    spawn(async move {
+                        match http::update_relationship(&tok, &user_id, action).await {
+                            // RELATIONSHIP_* events follow; apply now so the page does not lag behind.
+                            Ok(()) => match action {
+                                RelationshipAction::Accept => {
+                                    upsert_relationship(&mut friends.write(), user_id, RelationshipType::Friend, None)
+                                }
+                                RelationshipAction::Block => {
+                                    upsert_relationship(&mut friends.write(), user_id, RelationshipType::Blocked, None)
+                                }
+                                RelationshipAction::Remove => friends.write().retain(|r| r.id != user_id),
+                            },
+                            Err(e) => show_toast(toast, counter, format!("Could not update relationship: {e}")),
+                        }
+                    });
+                },
+                on_send_friend_request: move |username: String| {
+                    let Some(tok) = token() else { return };
+                    let toast = toast_messages;
+                    let counter = toast_counter;
+                    spawn(async move {
+                        match http::send_friend_request(&tok, &username).await {
+                            Ok(()) => show_toast(toast, counter, format!("Friend request sent to {username}")),
+                            Err(e) => show_toast(toast, counter, format!("Could not send friend request: {e}")),
+                        }
+                    });
+                },
+                on_create_group_dm: move |user_ids: Vec<String>| {
+                    let Some(tok) = token() else { return };
+                    let toast = toast_messages;
+                    let counter = toast_counter;
+                    spawn(async move {
+                        match http::create_group_dm(&tok, &user_ids).await {
+                            Ok(ch) => {
+                                let id = ch.id.clone();
+                                upsert_dm_channel(&mut dm_channels.write(), ch);
+                                selected_guild_id.set(None);
+                                unread_marker.set(None);
+                                selected_channel_id.set(Some(id));
+                            }
+                            Err(e) => show_toast(toast, counter, format!("Could not create group DM: {e}")),
+                        }
+                    });
+                },
+                on_group_recipient: move |(channel_id, user_id, add): (String, String, bool)| {
+                    let Some(tok) = token() else { return };
+                    let toast = toast_messages;
+                    let counter = toast_counter;
+                    spawn(async move {
+                        // CHANNEL_RECIPIENT_ADD / REMOVE updates the member list.
+                        if let Err(e) = http::set_group_recipient(&tok, &channel_id, &user_id, add).await {
+                            let verb = if add { "add" } else { "remove" };
+                            show_toast(toast, counter, format!("Could not {verb} member: {e}"));
+                        }
+                    });
+                },
+                on_edit_group_dm: move |(channel_id, edit): (String, GroupDmEdit)| {
+                    let Some(tok) = token() else { return };
+                    let toast = toast_messages;
+                    let counter = toast_counter;
+                    spawn(async move {
+                        match http::edit_group_dm(&tok, &channel_id, &edit).await {
+                            Ok(ch) => upsert_dm_channel(&mut dm_channels.write(), ch),
+                            Err(e) => show_toast(toast, counter, format!("Could not update group DM: {e}")),
+                        }
+                    });
+                },
+                on_leave_group_dm: move |channel_id: String| {
+                    let Some(tok) = token() else { return };
+                    let toast = toast_messages;
+                    let counter = toast_counter;
+                    spawn(async move {
+                        match http::close_channel(&tok, &channel_id).await {
+                            Ok(()) => {
+                                dm_channels.write().retain(|c| c.id != channel_id);
+                                if selected_channel_id.peek().as_ref() == Some(&channel_id) {
+                                    selected_channel_id.set(None);
+                                }
+                            }
+                            Err(e) => show_toast(toast, counter, format!("Could not leave group DM: {e}")),
+                        }
+                    });
+                },
+                on_group_dm_error: move |message: String| show_toast(toast_messages, toast_counter, message),
+                on_load_profile: move |(user_id, guild_id): (String, Option<String>)| {
+                    let Some(tok) = token() else { return };
+                    user_profiles.write().insert((user_id.clone(), guild_id.clone()), None);
+                    spawn(async move {
+                        match http::get_user_profile(&tok, &user_id, guild_id.as_deref()).await {
+                            Ok(profile) => {
+                                user_profiles.write().insert((user_id, guild_id), Some(profile));
+                            }
+                            Err(e) => {
+                                eprintln!("[profile] could not load {}: {}", user_id, e);
+                                // Drop the placeholder so the next time the card opens it fetches again.
+                                user_profiles.write().remove(&(user_id, guild_id));
+                            }
+                        }
+                    });
+                },
                 on_logout: move |_| {
                     let _ = logout();
                     token.set(None);
@@ -471,7 +1335,7 @@ pub fn App() -> Element {
                     guilds.set(Vec::new());
                     selected_guild_id.set(None);
                     guild_channels.set(Vec::new());
-                    guild_members.set(Vec::new());
+                    member_list.set(MemberList::default());
                     selected_channel_id.set(None);
                 },
                 on_open_settings: move |_| settings_open.set(true),
@@ -560,6 +1424,7 @@ pub fn App() -> Element {
                 open: settings_open,
                 settings,
                 current_user,
+                ipc_clients,
                 on_close: move |_| settings_open.set(false),
                 on_show_toast: move |msg: String| {
                     let id = toast_counter() + 1;
@@ -575,9 +1440,10 @@ pub fn App() -> Element {
                         toast.set(t);
                     });
                 },
-                on_change_presence: move |status: PresenceStatus| {
+                on_change_presence: move |mut presence: OwnPresence| {
+                    presence.activities.extend(ipc_activities());
                     if let Some(tx) = presence_tx() {
-                        let _ = tx.send(status);
+                        let _ = tx.send(presence);
                     }
                 },
             }
@@ -586,3 +1452,132 @@ pub fn App() -> Element {
         }
     }
 }
+
+/// Replace a DM channel in place (keeping list order) or add it at the top.
+fn upsert_dm_channel(list: &mut Vec<DmChannel>, ch: DmChannel) {
+    match list.iter_mut().find(|c| c.id == ch.id) {
+        Some(existing) => *existing = ch,
+        None => list.insert(0, ch),
+    }
+}
+
+/// Add or retype a relationship. Without `user` (RELATIONSHIP_UPDATE, local actions) unknown IDs are skipped.
+fn upsert_relationship(list: &mut Vec<Relationship>, id: String, kind: RelationshipType, user: Option<DiscordUser>) {
+    if let Some(existing) = list.iter_mut().find(|r| r.id == id) {
+        existing.r#type = kind;
+        if let Some(user) = user {
+            existing.user = user;
+        }
+    } else if let Some(user) = user {
+        list.push(Relationship { id, r#type: kind, user });
+    }
+}
+
+/// Show a toast for four seconds.
+fn show_toast(mut toast_messages: Signal<Vec<(usize, String)>>, mut toast_counter: Signal<usize>, msg: String) {
+    let id = toast_counter() + 1;
+    toast_counter.set(id);
+    let mut list = toast_messages();
+    list.push((id, msg));
+    toast_messages.set(list);
+    spawn(async move {
+        tokio::time::sleep(tokio::time::Duration::from_secs(4)).await;
+        let mut list = toast_messages();
+        list.retain(|(i, _)| *i != id);
+        toast_messages.set(list);
+    });
+}
+
+fn message_from_api(m: ApiMessage) -> Message {
+    Message {
+        id: m.id,
+        channel_id: m.channel_id,
+        guild_id: m.guild_id,
+        author_id: m.author.as_ref().map(|a| a.id.clone()).unwrap_or_default(),
+        author_username: m
+            .author
+            .as_ref()
+            .map(|a| a.global_name.clone().unwrap_or_else(|| a.username.clone())),
+        author_avatar: m.author.as_ref().and_then(|a| a.avatar.clone()),
+        content: m.content,
+        timestamp: m.timestamp,
+        mention_ids: m.mentions.into_iter().map(|u| u.id).collect(),
+        mention_everyone: m.mention_everyone,
+        sending: false,
+    }
+}
+
+/// DMs count every message as a mention; in guilds only direct and (unless suppressed) @everyone mentions do.
+fn is_mention_for(msg: &Message, user_id: &str, suppress_everyone: bool) -> bool {
+    msg.guild_id.is_none()
+        || (msg.mention_everyone && !suppress_everyone)
+        || msg.mention_ids.iter().any(|id| id == user_id)
+}
+
+/// Read marker to show the "New" divider at when opening a channel; None if it has nothing unread.
+fn unread_marker_for(read_states: &HashMap<String, ReadState>, channel_id: &str) -> Option<(String, String)> {
+    let state = read_states.get(channel_id)?;
+    if !state.is_unread() {
+        return None;
+    }
+    state.last_read_id.clone().map(|mid| (channel_id.to_string(), mid))
+}
+
+/// Move a channel's read marker forward to `message_id` (never backwards) and clear its mentions.
+/// Returns true when the marker moved, i.e. the server should be told.
+fn advance_read_state(read_states: &mut Signal<HashMap<String, ReadState>>, channel_id: &str, message_id: &str) -> bool {
+    if !is_snowflake(message_id) {
+        return false;
+    }
+    let mut map = read_states();
+    let entry = map.entry(channel_id.to_string()).or_default();
+    let is_newer = entry
+        .last_read_id
+        .as_deref()
+        .map(|prev| snowflake_cmp(message_id, prev).is_gt())
+        .unwrap_or(true);
+    let had_mentions = entry.mention_count > 0;
+    if !is_newer && !had_mentions {
+        return false;
+    }
+    if is_newer {
+        entry.last_read_id = Some(message_id.to_string());
+        let behind = entry
+            .last_message_id
+            .as_deref()
+            .map(|last| snowflake_cmp(message_id, last).is_gt())
+            .unwrap_or(true);
+        if behind {
+            entry.last_message_id = Some(message_id.to_string());
+        }
+    }
+    entry.mention_count = 0;
+    read_states.set(map);
+    true
+}
+
+/// Mark everything in a channel as read locally and ack it on the server.
+fn mark_channel_read(token: Option<String>, mut read_states: Signal<HashMap<String, ReadState>>, channel_id: &str) {
+    let Some(last) = read_states().get(channel_id).and_then(|r| r.last_message_id.clone()) else {
+        return;
+    };
+    if advance_read_state(&mut read_states, channel_id, &last) {
+        if let Some(tok) = token {
+            schedule_ack(tok, read_states, channel_id.to_string(), last);
+        }
+    }
+}
+
+/// Ack after a short delay so a burst of messages in the open channel sends one request.
+fn schedule_ack(token: String, read_states: Signal<HashMap<String, ReadState>>, channel_id: String, message_id: String) {
+    spawn(async move {
+        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+        let latest = read_states().get(&channel_id).and_then(|r| r.last_read_id.clone());
+        if latest.as_deref() != Some(message_id.as_str()) {
+            return;
+        }
+        if let Err(e) = http::ack_message(&token, &channel_id, &message_id).await {
+            eprintln!("[read state] ack failed for {}: {}", channel_id, e);
+        }
+    });
+}
@@ -1,6 +1,15 @@
 //! Discord Gateway WebSocket: real-time message updates.
 
-use crate::state::{Message, PresenceStatus};
+mod voice_session;
+
+use crate::http::{DiscordUser, DmChannel, GuildChannel, GuildMember, RelationshipType};
+use crate::member_list::{GuildSubscription, MemberListUpdate, MemberRequest};
+use crate::voice_state::VoiceState;
+use voice_session::{VoiceAction, VoiceServerUpdateData, VoiceSession};
+use crate::state::{
+    deserialize_activities, Activity, GuildNotificationSettings, Message, NotificationLevel, OwnPresence,
+    PresenceStatus,
+};
 use dioxus::prelude::spawn;
 use tokio::sync::mpsc::UnboundedSender;
 use futures_util::{SinkExt, StreamExt};
@@ -44,13 +53,13 @@ struct IdentifyProperties {
 #[derive(Serialize)]
 struct PresenceData {
     since: Option<u64>,
-    activities: Vec<serde_json::Value>,
+    activities: Vec<Activity>,
     status: String,
     afk: bool,
 }
 
-fn presence_to_payload(status: PresenceStatus) -> PresenceData {
-    let (status_str, afk) = match status {
+fn presence_to_payload(presence: &OwnPresence) -> PresenceData {
+    let (status_str, afk) = match presence.status {
         PresenceStatus::Online => ("online", false),
         PresenceStatus::Idle => ("idle", true),
         PresenceStatus::DoNotDisturb => ("dnd", false),
@@ -58,7 +67,7 @@ fn presence_to_payload(status: PresenceStatus) -> PresenceData {
     };
     PresenceData {
         since: None,
-        activities: Vec::new(),
+        activities: presence.activities.clone(),
         status: status_str.to_string(),
         afk,
     }
@@ -73,6 +82,12 @@ struct GatewayMessage {
     timestamp: Option<String>,
     #[serde(default)]
     author: Option<GatewayAuthor>,
+    #[serde(default)]
+    guild_id: Option<String>,
+    #[serde(default)]
+    mentions: Vec<GatewayAuthor>,
+    #[serde(default)]
+    mention_everyone: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -81,6 +96,8 @@ struct GatewayAuthor {
     username: String,
     #[serde(default)]
     global_name: Option<String>,
+    #[serde(default)]
+    avatar: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -92,7 +109,7 @@ struct TypingStartData {
 }
 
 /// Discord sends user.id as snowflake (number or string in JSON).
-fn deserialize_snowflake_string<'de, D>(deserializer: D) -> Result<String, D::Error>
+pub(crate) fn deserialize_snowflake_string<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
@@ -116,6 +133,297 @@ struct PresenceUpdateData {
     user: PresenceUpdateUser,
     #[serde(default)]
     status: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_activities")]
+    activities: Vec<Activity>,
+}
+
+/// Channel entry from READY `guilds[].channels` / `private_channels`.
+#[derive(Debug, Deserialize)]
+struct ReadyChannel {
+    #[serde(deserialize_with = "deserialize_snowflake_string")]
+    id: String,
+    #[serde(default, deserialize_with = "deserialize_snowflake_string_opt")]
+    last_message_id: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReadyGuild {
+    #[serde(deserialize_with = "deserialize_snowflake_string")]
+    id: String,
+    #[serde(default)]
+    channels: Vec<ReadyChannel>,
+    /// Active threads; only the ones we are a member of carry `member`.
+    #[serde(default)]
+    threads: Vec<ReadyThread>,
+    #[serde(default)]
+    default_message_notifications: Option<i64>,
+    /// Who is in the guild's voice channels; entries carry no `guild_id`.
+    #[serde(default)]
+    voice_states: Vec<VoiceState>,
+    /// Newer user READY payloads move guild fields into `properties`.
+    #[serde(default)]
+    properties: Option<ReadyGuildProperties>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReadyThread {
+    #[serde(deserialize_with = "deserialize_snowflake_string")]
+    id: String,
+    #[serde(default)]
+    member: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReadyGuildProperties {
+    #[serde(default)]
+    default_message_notifications: Option<i64>,
+}
+
+/// The parts of READY we use. User-account READY payloads are large; everything else is skipped.
+#[derive(Debug, Deserialize)]
+struct ReadyPayload {
+    /// Either a plain array (legacy) or `{ "entries": [...], "partial": bool, "version": n }`.
+    #[serde(default)]
+    read_state: Option<serde_json::Value>,
+    /// Same shape as `read_state`.
+    #[serde(default)]
+    user_guild_settings: Option<serde_json::Value>,
+    #[serde(default)]
+    guilds: Vec<ReadyGuild>,
+    #[serde(default)]
+    private_channels: Vec<ReadyChannel>,
+}
+
+/// Read state of one channel from READY: the last acked message and pending mentions.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReadStateEntry {
+    /// Channel ID.
+    #[serde(deserialize_with = "deserialize_snowflake_string")]
+    pub id: String,
+    #[serde(default, deserialize_with = "deserialize_snowflake_string_opt")]
+    pub last_message_id: Option<String>,
+    #[serde(default)]
+    pub mention_count: u32,
+    /// 0 = channel; other types (guild events, notification center) are not channels.
+    #[serde(default)]
+    read_state_type: u8,
+}
+
+/// Newest message known for a channel at READY time.
+#[derive(Clone, Debug)]
+pub struct ChannelLastMessage {
+    pub channel_id: String,
+    pub guild_id: Option<String>,
+    pub last_message_id: Option<String>,
+    /// None for DMs.
+    pub name: Option<String>,
+}
+
+/// Read-state and notification-settings snapshot sent once per session.
+#[derive(Clone, Debug)]
+pub struct ReadyData {
+    pub read_states: Vec<ReadStateEntry>,
+    pub channels: Vec<ChannelLastMessage>,
+    pub guild_settings: Vec<GuildNotificationSettings>,
+    /// Guild ID -> server default notification level.
+    pub guild_notification_defaults: Vec<(String, NotificationLevel)>,
+    /// Active threads we are a member of.
+    pub joined_threads: Vec<String>,
+    pub voice_states: Vec<VoiceState>,
+}
+
+/// Dispatch events forwarded to the app (messages, typing and presence have their own channels).
+#[derive(Clone, Debug)]
+pub enum GatewayEvent {
+    Ready(ReadyData),
+    /// A message was acked, possibly from another client.
+    MessageAck { channel_id: String, message_id: String },
+    /// Notification settings of one guild (or DMs) changed.
+    GuildSettingsUpdate(GuildNotificationSettings),
+    /// A thread was created or changed (renamed, archived, locked...).
+    ThreadUpsert(GuildChannel),
+    ThreadDelete { id: String, guild_id: Option<String> },
+    /// Active threads re-sent for a guild. `channel_ids` None means the whole guild, otherwise only
+    /// threads under those parents are replaced.
+    ThreadListSync {
+        guild_id: String,
+        channel_ids: Option<Vec<String>>,
+        threads: Vec<GuildChannel>,
+        joined: Vec<String>,
+    },
+    /// We joined or left a thread (possibly from another client).
+    ThreadMembership { thread_id: String, joined: bool },
+    ThreadMemberCount { thread_id: String, member_count: u32 },
+    /// Ops on the lazily loaded member list we subscribed to with op 14.
+    MemberListUpdate(MemberListUpdate),
+    /// Members we asked for with op 8; `not_found` are requested IDs that are not in the guild.
+    MembersChunk {
+        guild_id: String,
+        members: Vec<GuildMember>,
+        not_found: Vec<String>,
+    },
+    /// A relationship was added or changed type (request accepted, user blocked...).
+    /// `user` is only sent with RELATIONSHIP_ADD.
+    RelationshipUpsert {
+        id: String,
+        kind: RelationshipType,
+        user: Option<DiscordUser>,
+    },
+    RelationshipRemove { id: String },
+    /// A DM or group DM was created or changed (name, icon, owner).
+    PrivateChannelUpsert(DmChannel),
+    /// We left or closed a DM or group DM.
+    PrivateChannelDelete { id: String },
+    /// Someone joined (`added`) or left a group DM.
+    GroupRecipient {
+        channel_id: String,
+        user: DiscordUser,
+        added: bool,
+    },
+    /// Someone joined, left, moved or changed mute / deafen / stream in a voice channel (including us).
+    VoiceStateUpdate(VoiceState),
+    /// Our call moved to another channel without us asking (a moderator moved us), or ended when
+    /// `channel_id` is None (disconnected by a moderator or from another client).
+    VoiceChannelChanged { guild_id: Option<String>, channel_id: Option<String> },
+}
+
+#[derive(Debug, Deserialize)]
+struct RecipientData {
+    #[serde(deserialize_with = "deserialize_snowflake_string")]
+    channel_id: String,
+    user: DiscordUser,
+}
+
+#[derive(Debug, Deserialize)]
+struct RelationshipData {
+    #[serde(deserialize_with = "deserialize_snowflake_string")]
+    id: String,
+    #[serde(default)]
+    r#type: RelationshipType,
+    #[serde(default)]
+    user: Option<DiscordUser>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MembersChunkData {
+    #[serde(deserialize_with = "deserialize_snowflake_string")]
+    guild_id: String,
+    #[serde(default)]
+    members: Vec<GuildMember>,
+    #[serde(default)]
+    not_found: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ThreadDeleteData {
+    #[serde(deserialize_with = "deserialize_snowflake_string")]
+    id: String,
+    #[serde(default, deserialize_with = "deserialize_snowflake_string_opt")]
+    guild_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ThreadListSyncData {
+    #[serde(deserialize_with = "deserialize_snowflake_string")]
+    guild_id: String,
+    #[serde(default)]
+    channel_ids: Option<Vec<String>>,
+    #[serde(default)]
+    threads: Vec<GuildChannel>,
+    /// Our memberships among `threads`.
+    #[serde(default)]
+    members: Vec<ThreadMemberData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ThreadMemberData {
+    /// Thread ID.
+    #[serde(default, deserialize_with = "deserialize_snowflake_string_opt")]
+    id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_snowflake_string_opt")]
+    user_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ThreadMembersUpdateData {
+    #[serde(deserialize_with = "deserialize_snowflake_string")]
+    id: String,
+    #[serde(default)]
+    member_count: u32,
+    #[serde(default)]
+    added_members: Vec<ThreadMemberData>,
+    #[serde(default)]
+    removed_member_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageAckData {
+    #[serde(deserialize_with = "deserialize_snowflake_string")]
+    channel_id: String,
+    #[serde(deserialize_with = "deserialize_snowflake_string")]
+    message_id: String,
+}
+
+/// READY lists are either a plain array or a versioned `{ "entries": [...] }` object.
+fn versioned_entries<T: serde::de::DeserializeOwned>(v: Option<serde_json::Value>) -> Result<Vec<T>, String> {
+    let entries = match v {
+        Some(serde_json::Value::Array(list)) => serde_json::Value::Array(list),
+        Some(serde_json::Value::Object(mut obj)) => obj.remove("entries").unwrap_or_default(),
+        _ => serde_json::Value::Null,
+    };
+    serde_json::from_value::<Option<Vec<T>>>(entries)
+        .map(|list| list.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+fn parse_ready(d: serde_json::Value) -> Result<ReadyData, String> {
+    let ready: ReadyPayload = serde_json::from_value(d).map_err(|e| e.to_string())?;
+    let read_states: Vec<ReadStateEntry> = versioned_entries::<ReadStateEntry>(ready.read_state)?
+        .into_iter()
+        .filter(|r| r.read_state_type == 0)
+        .collect();
+    let guild_settings = versioned_entries::<GuildNotificationSettings>(ready.user_guild_settings)?;
+    let mut channels = Vec::new();
+    let mut guild_notification_defaults = Vec::new();
+    let mut joined_threads = Vec::new();
+    let mut voice_states = Vec::new();
+    for g in ready.guilds {
+        voice_states.extend(g.voice_states.into_iter().map(|mut vs| {
+            vs.guild_id = Some(g.id.clone());
+            vs
+        }));
+        joined_threads.extend(g.threads.into_iter().filter(|t| t.member.is_some()).map(|t| t.id));
+        let default_level = g
+            .default_message_notifications
+            .or_else(|| g.properties.as_ref().and_then(|p| p.default_message_notifications));
+        guild_notification_defaults.push((g.id.clone(), NotificationLevel::from(default_level)));
+        for c in g.channels {
+            channels.push(ChannelLastMessage {
+                channel_id: c.id,
+                guild_id: Some(g.id.clone()),
+                last_message_id: c.last_message_id,
+                name: c.name,
+            });
+        }
+    }
+    for c in ready.private_channels {
+        channels.push(ChannelLastMessage {
+            channel_id: c.id,
+            guild_id: None,
+            last_message_id: c.last_message_id,
+            name: None,
+        });
+    }
+    Ok(ReadyData {
+        read_states,
+        channels,
+        guild_settings,
+        guild_notification_defaults,
+        joined_threads,
+        voice_states,
+    })
 }
 
 /// Data needed to connect the voice driver (from VOICE_STATE_UPDATE + VOICE_SERVER_UPDATE).
@@ -140,40 +448,50 @@ pub enum VoiceCommand {
         input_device: Option<String>,
         output_device: Option<String>,
     },
+    /// Change self mute / deafen in the current call (op 4 with the same channel).
+    SetSelfState { self_mute: bool, self_deaf: bool },
     Leave,
 }
 
-/// Message to the voice task: connect with info and optional device names, or leave.
+/// Message to the voice task: connect with info and optional device names, change mute / deafen, or leave.
 #[derive(Clone, Debug)]
 pub enum VoiceMessage {
     Connect {
         info: VoiceConnectionInfo,
         input_device: Option<String>,
         output_device: Option<String>,
+        self_mute: bool,
+        self_deaf: bool,
     },
+    SetSelfState { self_mute: bool, self_deaf: bool },
     Leave,
 }
 
-#[derive(Debug, Deserialize)]
-struct VoiceStateUpdateData {
-    #[serde(default, deserialize_with = "deserialize_snowflake_string_opt")]
-    pub guild_id: Option<String>,
-    #[serde(default, deserialize_with = "deserialize_snowflake_string_opt")]
-    pub channel_id: Option<String>,
-    #[serde(deserialize_with = "deserialize_snowflake_string")]
-    pub user_id: String,
-    pub session_id: String,
+/// Events from the voice task to the app.
+#[derive(Clone, Debug)]
+pub enum VoiceEvent {
+    /// A user in our call (or we) started or stopped speaking.
+    Speaking { user_id: String, speaking: bool },
+    /// Something the user should know about the call's audio (device lost, fallback, restored).
+    Notice(String),
+    Status(VoiceStatus),
+    /// The voice connection could not be (re)established and the call was torn down; the channel
+    /// should be left on the gateway too.
+    GaveUp,
 }
 
-#[derive(Debug, Deserialize)]
-struct VoiceServerUpdateData {
-    pub token: String,
-    #[serde(default, deserialize_with = "deserialize_snowflake_string_opt")]
-    pub guild_id: Option<String>,
-    pub endpoint: Option<String>,
+/// Connection state of our call, shown next to the voice controls.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VoiceStatus {
+    Connecting,
+    /// `latency_ms`: TCP connect time to the voice server's host, once measured (not the RTC ping,
+    /// which the voice driver does not expose).
+    Connected { latency_ms: Option<u32> },
+    /// Lost and not coming back without rejoining.
+    Disconnected,
 }
 
-fn deserialize_snowflake_string_opt<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+pub(crate) fn deserialize_snowflake_string_opt<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
@@ -186,61 +504,83 @@ where
     }
 }
 
-/// Spawn Gateway task. Sends new messages, typing events, presence and voice.
-pub fn spawn_gateway(
-    token: String,
-    current_user_id: Option<String>,
-    tx: mpsc::UnboundedSender<Message>,
-    tx_typing: Option<mpsc::UnboundedSender<(String, String)>>,
-    tx_presence_updates: Option<mpsc::UnboundedSender<(String, String)>>,
-    presence: PresenceStatus,
-    presence_rx: mpsc::UnboundedReceiver<PresenceStatus>,
-    rx_voice_cmd: mpsc::UnboundedReceiver<VoiceCommand>,
-    tx_voice_message: UnboundedSender<VoiceMessage>,
-) {
+/// Channels between the Gateway task and the app.
+pub struct GatewayChannels {
+    /// New messages (MESSAGE_CREATE).
+    pub tx: mpsc::UnboundedSender<Message>,
+    /// All other dispatch events the app handles.
+    pub tx_events: mpsc::UnboundedSender<GatewayEvent>,
+    /// (channel_id, user_id) from TYPING_START.
+    pub tx_typing: Option<mpsc::UnboundedSender<(String, String)>>,
+    /// (user_id, status, activities) from PRESENCE_UPDATE.
+    pub tx_presence_updates: Option<mpsc::UnboundedSender<(String, String, Vec<Activity>)>>,
+    /// Our own presence changes, sent as op 3.
+    pub presence_rx: mpsc::UnboundedReceiver<OwnPresence>,
+    /// Voice join, leave and self mute / deafen, sent as op 4.
+    pub rx_voice_cmd: mpsc::UnboundedReceiver<VoiceCommand>,
+    /// Member list subscriptions, sent as op 14.
+    pub rx_subscriptions: mpsc::UnboundedReceiver<GuildSubscription>,
+    /// Member requests, sent as op 8.
+    pub rx_member_requests: mpsc::UnboundedReceiver<MemberRequest>,
+    /// Connection info and leave for the voice task.
+    pub tx_voice_message: UnboundedSender<VoiceMessage>,
+}
+
+/// Spawn Gateway task. Sends new messages, typing events, presence, voice and other dispatch events.
+pub fn spawn_gateway(token: String, current_user_id: Option<String>, presence: OwnPresence, channels: GatewayChannels) {
     spawn(async move {
-        if let Err(e) = run_gateway_loop(
-            token,
-            current_user_id,
-            tx,
-            tx_typing,
-            tx_presence_updates,
-            presence,
-            presence_rx,
-            rx_voice_cmd,
-            tx_voice_message,
-        )
-        .await
-        {
+        if let Err(e) = run_gateway_loop(token, current_user_id, presence, channels).await {
             eprintln!("Gateway error: {}", e);
         }
     });
 }
 
+fn apply_voice_action(
+    action: VoiceAction,
+    tx_voice_message: &UnboundedSender<VoiceMessage>,
+    tx_events: &mpsc::UnboundedSender<GatewayEvent>,
+) {
+    match action {
+        VoiceAction::Voice(message) => {
+            let _ = tx_voice_message.send(message);
+        }
+        VoiceAction::ChannelChanged { guild_id, channel_id } => {
+            let _ = tx_events.send(GatewayEvent::VoiceChannelChanged { guild_id, channel_id });
+        }
+    }
+}
+
 async fn run_gateway_loop(
     token: String,
     current_user_id: Option<String>,
-    tx: mpsc::UnboundedSender<Message>,
-    tx_typing: Option<mpsc::UnboundedSender<(String, String)>>,
-    tx_presence_updates: Option<mpsc::UnboundedSender<(String, String)>>,
-    presence: PresenceStatus,
-    mut presence_rx: mpsc::UnboundedReceiver<PresenceStatus>,
-    mut rx_voice_cmd: mpsc::UnboundedReceiver<VoiceCommand>,
-    tx_voice_message: UnboundedSender<VoiceMessage>,
+    presence: OwnPresence,
+    channels: GatewayChannels,
 ) -> Result<(), String> {
+    let GatewayChannels {
+        tx,
+        tx_events,
+        tx_typing,
+        tx_presence_updates,
+        mut presence_rx,
+        mut rx_voice_cmd,
+        mut rx_subscriptions,
+        mut rx_member_requests,
+        tx_voice_message,
+    } = channels;
     let (ws_stream, _) = connect_async(GATEWAY_URL).await.map_err(|e| e.to_string())?;
     let (mut write, mut read) = ws_stream.split();
     let mut last_seq: u64 = 0;
     let mut heartbeat_interval: Option<u64> = None;
     let mut identified = false;
     let mut current_presence = presence;
+    // op 8 / op 14 before READY are dropped by the gateway; hold them until then.
+    let mut ready_received = false;
+    let mut pending_subscription: Option<GuildSubscription> = None;
+    let mut pending_member_requests: Vec<MemberRequest> = Vec::new();
     let mut heartbeat = tokio::time::interval(tokio::time::Duration::from_millis(100));
     heartbeat.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
-    // Voice: we send op 4 on Join/Leave; when we get our VOICE_STATE_UPDATE + VOICE_SERVER_UPDATE we send ConnectionInfo.
-    let mut current_voice: Option<(Option<String>, String, bool, bool, Option<String>, Option<String>)> = None; // guild_id, channel_id, mute, deaf, input_device, output_device
-    let mut my_voice_session: Option<(String, String)> = None; // (session_id, channel_id)
-    let mut last_voice_server: Option<(String, Option<String>, Option<String>)> = None; // (token, endpoint, guild_id)
+    let mut voice_session = VoiceSession::default();
 
     loop {
         tokio::select! {
@@ -272,13 +612,13 @@ async fn run_gateway_loop(
                         if !identified {
                             let identify = IdentifyPayload {
                                 token: token.clone(),
-                                intents: 4096 | 16384 | 256 | 128 | 2, // DM | DM_TYPING | GUILD_VOICE_STATES | GUILD_MEMBERS | ...
+                                intents: 4096 | 16384 | 256 | 128 | 2 | 1, // DM | DM_TYPING | GUILD_VOICE_STATES | GUILD_MEMBERS | ... | GUILDS (threads)
                                 properties: IdentifyProperties {
                                     os: std::env::consts::OS.to_string(),
                                     browser: "Velocity".to_string(),
                                     device: "Velocity".to_string(),
                                 },
-                                presence: presence_to_payload(current_presence),
+                                presence: presence_to_payload(&current_presence),
                             };
                             let payload = serde_json::json!({"op": 2, "d": identify});
                             write
@@ -289,16 +629,184 @@ async fn run_gateway_loop(
                         }
                     }
                     0 => {
-                        if frame.t.as_deref() == Some("MESSAGE_CREATE") {
+                        if frame.t.as_deref() == Some("READY") {
+                            if let Some(d) = frame.d {
+                                match parse_ready(d) {
+                                    Ok(ready) => {
+                                        let _ = tx_events.send(GatewayEvent::Ready(ready));
+                                    }
+                                    Err(e) => eprintln!("[gateway] READY parse error: {}", e),
+                                }
+                            }
+                            ready_received = true;
+                            if let Some(sub) = pending_subscription.take() {
+                                let _ = write.send(WsMessage::Text(sub.to_payload().to_string())).await;
+                            }
+                            for req in pending_member_requests.drain(..) {
+                                let _ = write.send(WsMessage::Text(req.to_payload().to_string())).await;
+                            }
+                        } else if frame.t.as_deref() == Some("GUILD_MEMBERS_CHUNK") {
+                            if let Some(d) = frame.d {
+                                match serde_json::from_value::<MembersChunkData>(d) {
+                                    Ok(chunk) => {
+                                        let _ = tx_events.send(GatewayEvent::MembersChunk {
+                                            guild_id: chunk.guild_id,
+                                            members: chunk.members,
+                                            not_found: chunk.not_found,
+                                        });
+                                    }
+                                    Err(e) => eprintln!("[gateway] GUILD_MEMBERS_CHUNK parse error: {}", e),
+                                }
+                            }
+                        } else if frame.t.as_deref() == Some("GUILD_MEMBER_LIST_UPDATE") {
+                            if let Some(d) = frame.d {
+                                match serde_json::from_value::<MemberListUpdate>(d) {
+                                    Ok(update) => {
+                                        let _ = tx_events.send(GatewayEvent::MemberListUpdate(update));
+                                    }
+                                    Err(e) => eprintln!("[gateway] GUILD_MEMBER_LIST_UPDATE parse error: {}", e),
+                                }
+                            }
+                        } else if frame.t.as_deref() == Some("MESSAGE_ACK") {
+                            if let Some(d) = frame.d {
+                                if let Ok(ack) = serde_json::from_value::<MessageAckData>(d) {
+                                    let _ = tx_events.send(GatewayEvent::MessageAck {
+                                        channel_id: ack.channel_id,
+                                        message_id: ack.message_id,
+                                    });
+                                }
+                            }
+                        } else if frame.t.as_deref() == Some("USER_GUILD_SETTINGS_UPDATE") {
+                            if let Some(d) = frame.d {
+                                match serde_json::from_value::<GuildNotificationSettings>(d) {
+                                    Ok(gs) => {
+                                        let _ = tx_events.send(GatewayEvent::GuildSettingsUpdate(gs));
+                                    }
+                                    Err(e) => eprintln!("[gateway] USER_GUILD_SETTINGS_UPDATE parse error: {}", e),
+                                }
+                            }
+                        } else if matches!(frame.t.as_deref(), Some("THREAD_CREATE") | Some("THREAD_UPDATE")) {
+                            if let Some(d) = frame.d {
+                                // THREAD_CREATE carries `member` when we are in the thread (e.g. we started it).
+                                let joined = d.get("member").map(|m| !m.is_null()).unwrap_or(false);
+                                match serde_json::from_value::<GuildChannel>(d) {
+                                    Ok(thread) => {
+                                        if joined {
+                                            let _ = tx_events.send(GatewayEvent::ThreadMembership {
+                                                thread_id: thread.id.clone(),
+                                                joined: true,
+                                            });
+                                        }
+                                        let _ = tx_events.send(GatewayEvent::ThreadUpsert(thread));
+                                    }
+                                    Err(e) => eprintln!("[gateway] {:?} parse error: {}", frame.t, e),
+                                }
+                            }
+                        } else if matches!(frame.t.as_deref(), Some("RELATIONSHIP_ADD") | Some("RELATIONSHIP_UPDATE")) {
+                            if let Some(d) = frame.d {
+                                match serde_json::from_value::<RelationshipData>(d) {
+                                    Ok(rel) => {
+                                        let _ = tx_events.send(GatewayEvent::RelationshipUpsert {
+                                            id: rel.id,
+                                            kind: rel.r#type,
+                                            user: rel.user,
+                                        });
+                                    }
+                                    Err(e) => eprintln!("[gateway] relationship parse error: {}", e),
+                                }
+                            }
+                        } else if frame.t.as_deref() == Some("RELATIONSHIP_REMOVE") {
+                            if let Some(d) = frame.d {
+                                if let Ok(rel) = serde_json::from_value::<RelationshipData>(d) {
+                                    let _ = tx_events.send(GatewayEvent::RelationshipRemove { id: rel.id });
+                                }
+                            }
+                        } else if matches!(
+                            frame.t.as_deref(),
+                            Some("CHANNEL_CREATE") | Some("CHANNEL_UPDATE") | Some("CHANNEL_DELETE")
+                        ) {
+                            // Guild channels are loaded per guild; only DMs and group DMs are tracked live here.
+                            if let Some(d) = frame.d.filter(|d| !matches!(d.get("guild_id"), Some(g) if !g.is_null())) {
+                                match serde_json::from_value::<DmChannel>(d) {
+                                    Ok(ch) if frame.t.as_deref() == Some("CHANNEL_DELETE") => {
+                                        let _ = tx_events.send(GatewayEvent::PrivateChannelDelete { id: ch.id });
+                                    }
+                                    Ok(ch) => {
+                                        let _ = tx_events.send(GatewayEvent::PrivateChannelUpsert(ch));
+                                    }
+                                    Err(e) => eprintln!("[gateway] private channel parse error: {}", e),
+                                }
+                            }
+                        } else if matches!(frame.t.as_deref(), Some("CHANNEL_RECIPIENT_ADD") | Some("CHANNEL_RECIPIENT_REMOVE")) {
+                            let added = frame.t.as_deref() == Some("CHANNEL_RECIPIENT_ADD");
+                            if let Some(d) = frame.d {
+                                if let Ok(r) = serde_json::from_value::<RecipientData>(d) {
+                                    let _ = tx_events.send(GatewayEvent::GroupRecipient {
+                                        channel_id: r.channel_id,
+                                        user: r.user,
+                                        added,
+                                    });
+                                }
+                            }
+                        } else if frame.t.as_deref() == Some("THREAD_DELETE") {
+                            if let Some(d) = frame.d {
+                                if let Ok(td) = serde_json::from_value::<ThreadDeleteData>(d) {
+                                    let _ = tx_events.send(GatewayEvent::ThreadDelete { id: td.id, guild_id: td.guild_id });
+                                }
+                            }
+                        } else if frame.t.as_deref() == Some("THREAD_LIST_SYNC") {
+                            if let Some(d) = frame.d {
+                                match serde_json::from_value::<ThreadListSyncData>(d) {
+                                    Ok(sync) => {
+                                        let _ = tx_events.send(GatewayEvent::ThreadListSync {
+                                            guild_id: sync.guild_id,
+                                            channel_ids: sync.channel_ids,
+                                            threads: sync.threads,
+                                            joined: sync.members.into_iter().filter_map(|m| m.id).collect(),
+                                        });
+                                    }
+                                    Err(e) => eprintln!("[gateway] THREAD_LIST_SYNC parse error: {}", e),
+                                }
+                            }
+                        } else if frame.t.as_deref() == Some("THREAD_MEMBER_UPDATE") {
+                            // Only ever sent for the current user.
+                            if let Some(d) = frame.d {
+                                if let Ok(ThreadMemberData { id: Some(thread_id), .. }) = serde_json::from_value::<ThreadMemberData>(d) {
+                                    let _ = tx_events.send(GatewayEvent::ThreadMembership { thread_id, joined: true });
+                                }
+                            }
+                        } else if frame.t.as_deref() == Some("THREAD_MEMBERS_UPDATE") {
+                            if let Some(d) = frame.d {
+                                if let Ok(tm) = serde_json::from_value::<ThreadMembersUpdateData>(d) {
+                                    let me = current_user_id.as_deref();
+                                    let added = tm.added_members.iter().any(|m| m.user_id.as_deref() == me);
+                                    let removed = tm.removed_member_ids.iter().any(|id| Some(id.as_str()) == me);
+                                    if added || removed {
+                                        let _ = tx_events.send(GatewayEvent::ThreadMembership {
+                                            thread_id: tm.id.clone(),
+                                            joined: added,
+                                        });
+                                    }
+                                    let _ = tx_events.send(GatewayEvent::ThreadMemberCount {
+                                        thread_id: tm.id,
+                                        member_count: tm.member_count,
+                                    });
+                                }
+                            }
+                        } else if frame.t.as_deref() == Some("MESSAGE_CREATE") {
                             if let Some(d) = frame.d {
                                 if let Ok(gm) = serde_json::from_value::<GatewayMessage>(d) {
                                     let msg = Message {
                                         id: gm.id,
                                         channel_id: gm.channel_id,
+                                        guild_id: gm.guild_id,
                                         author_id: gm.author.as_ref().map(|a| a.id.clone()).unwrap_or_default(),
+                                        author_avatar: gm.author.as_ref().and_then(|a| a.avatar.clone()),
                                         author_username: gm.author.map(|a| a.global_name.unwrap_or(a.username)),
                                         content: gm.content,
                                         timestamp: gm.timestamp,
+                                        mention_ids: gm.mentions.into_iter().map(|u| u.id).collect(),
+                                        mention_everyone: gm.mention_everyone,
                                         sending: false,
                                     };
                                     let _ = tx.send(msg);
@@ -317,7 +825,7 @@ async fn run_gateway_loop(
                                     Ok(pu) => {
                                         let status = pu.status.unwrap_or_else(|| "offline".to_string());
                                         eprintln!("[presence] user_id={} status={}", pu.user.id, status);
-                                        let _ = tx_pres.send((pu.user.id, status));
+                                        let _ = tx_pres.send((pu.user.id, status, pu.activities));
                                     }
                                     Err(e) => {
                                         let preview = if raw_str.len() > 400 {
@@ -332,43 +840,15 @@ async fn run_gateway_loop(
                             }
                         } else if frame.t.as_deref() == Some("VOICE_STATE_UPDATE") {
                             if let Some(d) = &frame.d {
-                                match serde_json::from_value::<VoiceStateUpdateData>(d.clone()) {
+                                match serde_json::from_value::<VoiceState>(d.clone()) {
                                     Ok(vs) => {
+                                        let _ = tx_events.send(GatewayEvent::VoiceStateUpdate(vs.clone()));
                                         let is_self = current_user_id.as_ref().map(|my_id| vs.user_id == *my_id).unwrap_or(false);
                                         eprintln!("[voice gateway] VOICE_STATE_UPDATE user_id={} channel_id={:?} is_self={}",
                                             vs.user_id, vs.channel_id, is_self);
-                                        if is_self {
-                                            let my_id = current_user_id.as_ref().unwrap();
-                                            let ch = vs.channel_id.unwrap_or_default();
-                                            my_voice_session = Some((vs.session_id.clone(), ch.clone()));
-                                            eprintln!("[voice gateway] VOICE_STATE_UPDATE self session_id={} channel_id={} last_voice_server={}",
-                                                vs.session_id, ch, last_voice_server.is_some());
-                                            if let (Some(ref tx_v), Some((token, endpoint, gid)), Some((session_id, channel_id))) =
-                                                (Some(&tx_voice_message), last_voice_server.clone(), my_voice_session.clone())
-                                            {
-                                                if !channel_id.is_empty() {
-                                                    if let Some(ep) = endpoint {
-                                                        let info = VoiceConnectionInfo {
-                                                            guild_id: gid.clone(),
-                                                            channel_id: channel_id.clone(),
-                                                            endpoint: ep,
-                                                            token: token.clone(),
-                                                            session_id: session_id.clone(),
-                                                            user_id: my_id.clone(),
-                                                        };
-                                                    let (in_dev, out_dev) = current_voice.as_ref()
-                                                        .map(|(_, _, _, _, i, o)| (i.clone(), o.clone()))
-                                                        .unwrap_or((None, None));
-                                                    eprintln!("[voice gateway] sending Connect (from VOICE_STATE_UPDATE) channel={}", info.channel_id);
-                                                    let _ = tx_v.send(VoiceMessage::Connect {
-                                                        info,
-                                                        input_device: in_dev,
-                                                        output_device: out_dev,
-                                                    });
-                                                    last_voice_server = None;
-                                                    my_voice_session = None;
-                                                    }
-                                                }
+                                        if let Some(my_id) = current_user_id.as_ref().filter(|_| is_self) {
+                                            for action in voice_session.on_voice_state(my_id, &vs) {
+                                                apply_voice_action(action, &tx_voice_message, &tx_events);
                                             }
                                         }
                                     }
@@ -381,39 +861,13 @@ async fn run_gateway_loop(
                             if let Some(d) = frame.d {
                                 match serde_json::from_value::<VoiceServerUpdateData>(d.clone()) {
                                     Ok(vs) => {
-                                        eprintln!("[voice gateway] VOICE_SERVER_UPDATE endpoint={:?} my_voice_session={}",
-                                            vs.endpoint, my_voice_session.is_some());
-                                        last_voice_server = Some((vs.token.clone(), vs.endpoint.clone(), vs.guild_id.clone()));
-                                    if let (Some(ref tx_v), Some((token, endpoint, gid)), Some((session_id, channel_id))) =
-                                        (Some(&tx_voice_message), last_voice_server.clone(), my_voice_session.clone())
-                                    {
-                                        if !channel_id.is_empty() {
-                                            if let Some(ep) = endpoint {
-                                                if let Some(ref my_id) = current_user_id {
-                                                    let info = VoiceConnectionInfo {
-                                                        guild_id: gid.clone(),
-                                                        channel_id: channel_id.clone(),
-                                                        endpoint: ep,
-                                                        token: token.clone(),
-                                                        session_id: session_id.clone(),
-                                                        user_id: my_id.clone(),
-                                                    };
-                                                    let (in_dev, out_dev) = current_voice.as_ref()
-                                                        .map(|(_, _, _, _, i, o)| (i.clone(), o.clone()))
-                                                        .unwrap_or((None, None));
-                                                    eprintln!("[voice gateway] sending Connect (from VOICE_SERVER_UPDATE) channel={}", info.channel_id);
-                                                    let _ = tx_v.send(VoiceMessage::Connect {
-                                                        info,
-                                                        input_device: in_dev,
-                                                        output_device: out_dev,
-                                                    });
-                                                    last_voice_server = None;
-                                                    my_voice_session = None;
-                                                }
+                                        eprintln!("[voice gateway] VOICE_SERVER_UPDATE endpoint={:?}", vs.endpoint);
+                                        if let Some(ref my_id) = current_user_id {
+                                            for action in voice_session.on_voice_server(my_id, vs) {
+                                                apply_voice_action(action, &tx_voice_message, &tx_events);
                                             }
                                         }
                                     }
-                                    }
                                     Err(e) => {
                                         eprintln!("[voice gateway] VOICE_SERVER_UPDATE parse error: {}", e);
                                     }
@@ -433,16 +887,32 @@ async fn run_gateway_loop(
                 }
             }
             // Presence updates from the UI
-            Some(new_status) = presence_rx.recv() => {
-                current_presence = new_status;
+            Some(new_presence) = presence_rx.recv() => {
+                current_presence = new_presence;
                 if identified {
                     let payload = serde_json::json!({
                         "op": 3,
-                        "d": presence_to_payload(current_presence),
+                        "d": presence_to_payload(&current_presence),
                     });
                     let _ = write.send(WsMessage::Text(payload.to_string())).await;
                 }
             }
+            // Member list ranges for the open guild
+            Some(sub) = rx_subscriptions.recv() => {
+                if ready_received {
+                    let _ = write.send(WsMessage::Text(sub.to_payload().to_string())).await;
+                } else {
+                    pending_subscription = Some(sub);
+                }
+            }
+            // Message authors and mentions to resolve
+            Some(req) = rx_member_requests.recv() => {
+                if ready_received {
+                    let _ = write.send(WsMessage::Text(req.to_payload().to_string())).await;
+                } else {
+                    pending_member_requests.push(req);
+                }
+            }
             // Voice: join or leave channel
             Some(cmd) = rx_voice_cmd.recv() => {
                 if !identified {
@@ -451,31 +921,19 @@ async fn run_gateway_loop(
                 match cmd {
                     VoiceCommand::Join { guild_id, channel_id, self_mute, self_deaf, input_device, output_device } => {
                         eprintln!("[voice gateway] Join received guild_id={:?} channel_id={}", guild_id, channel_id);
-                        current_voice = Some((guild_id.clone(), channel_id.clone(), self_mute, self_deaf, input_device.clone(), output_device.clone()));
-                        my_voice_session = None;
-                        last_voice_server = None;
-                        let d = serde_json::json!({
-                            "guild_id": guild_id,
-                            "channel_id": channel_id,
-                            "self_mute": self_mute,
-                            "self_deaf": self_deaf,
-                        });
-                        let payload = serde_json::json!({"op": 4, "d": d});
+                        let payload = voice_session.join(guild_id, channel_id, self_mute, self_deaf, input_device, output_device);
+                        let _ = write.send(WsMessage::Text(payload.to_string())).await;
+                    }
+                    VoiceCommand::SetSelfState { self_mute, self_deaf } => {
+                        let Some(payload) = voice_session.set_self_state(self_mute, self_deaf) else {
+                            continue;
+                        };
+                        let _ = tx_voice_message.send(VoiceMessage::SetSelfState { self_mute, self_deaf });
                         let _ = write.send(WsMessage::Text(payload.to_string())).await;
                     }
                     VoiceCommand::Leave => {
-                        let guild_id = current_voice.as_ref().and_then(|(g, _, _, _, _, _)| g.clone());
-                        current_voice = None;
-                        my_voice_session = None;
-                        last_voice_server = None;
+                        let payload = voice_session.leave();
                         let _ = tx_voice_message.send(VoiceMessage::Leave);
-                        let d = serde_json::json!({
-                            "guild_id": guild_id,
-                            "channel_id": serde_json::Value::Null,
-                            "self_mute": false,
-                            "self_deaf": false,
-                        });
-                        let payload = serde_json::json!({"op": 4, "d": d});
                         let _ = write.send(WsMessage::Text(payload.to_string())).await;
                     }
                 }
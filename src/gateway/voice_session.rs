@@ -0,0 +1,363 @@
+//! Our voice session on the main gateway.
+//! Joining sends op 4; the gateway answers with our VOICE_STATE_UPDATE (session) and a
+//! VOICE_SERVER_UPDATE (token, endpoint) in either order, and once both are in the voice task connects.
+//! While connected, a new VOICE_SERVER_UPDATE moves the call to another server and our own voice
+//! state tells us when a moderator moved us to another channel or disconnected us.
+
+use serde::Deserialize;
+
+use super::{deserialize_snowflake_string_opt, VoiceConnectionInfo, VoiceMessage};
+use crate::voice_state::VoiceState;
+
+#[derive(Debug, Deserialize)]
+pub(super) struct VoiceServerUpdateData {
+    pub token: String,
+    #[serde(default, deserialize_with = "deserialize_snowflake_string_opt")]
+    pub guild_id: Option<String>,
+    /// None while Discord reallocates the voice server; another update follows.
+    pub endpoint: Option<String>,
+}
+
+/// The channel the user asked for and how to connect to it.
+#[derive(Clone, Debug)]
+pub(super) struct Target {
+    guild_id: Option<String>,
+    channel_id: String,
+    self_mute: bool,
+    self_deaf: bool,
+    input_device: Option<String>,
+    output_device: Option<String>,
+}
+
+#[derive(Debug)]
+pub(super) struct Server {
+    token: String,
+    endpoint: String,
+}
+
+/// What the gateway loop does after a transition.
+#[derive(Debug)]
+pub(super) enum VoiceAction {
+    /// Hand to the voice task.
+    Voice(VoiceMessage),
+    /// Our channel changed without us asking: moved by a moderator, or disconnected when None.
+    ChannelChanged { guild_id: Option<String>, channel_id: Option<String> },
+}
+
+#[derive(Debug, Default)]
+pub(super) enum VoiceSession {
+    #[default]
+    Idle,
+    /// op 4 sent; waiting for our session and a voice server (again, after a server reallocation).
+    Joining {
+        target: Target,
+        session_id: Option<String>,
+        server: Option<Server>,
+    },
+    /// The voice task has the connection info.
+    Connected { target: Target, session_id: String },
+}
+
+/// op 4 payload; `channel_id` None leaves.
+fn voice_state_payload(guild_id: &Option<String>, channel_id: Option<&str>, self_mute: bool, self_deaf: bool) -> serde_json::Value {
+    serde_json::json!({
+        "op": 4,
+        "d": {
+            "guild_id": guild_id,
+            "channel_id": channel_id,
+            "self_mute": self_mute,
+            "self_deaf": self_deaf,
+        },
+    })
+}
+
+impl VoiceSession {
+    fn target_mut(&mut self) -> Option<&mut Target> {
+        match self {
+            VoiceSession::Idle => None,
+            VoiceSession::Joining { target, .. } | VoiceSession::Connected { target, .. } => Some(target),
+        }
+    }
+
+    /// Start joining a channel (leaving any other); returns the op 4 payload to send.
+    pub fn join(
+        &mut self,
+        guild_id: Option<String>,
+        channel_id: String,
+        self_mute: bool,
+        self_deaf: bool,
+        input_device: Option<String>,
+        output_device: Option<String>,
+    ) -> serde_json::Value {
+        let payload = voice_state_payload(&guild_id, Some(&channel_id), self_mute, self_deaf);
+        *self = VoiceSession::Joining {
+            target: Target {
+                guild_id,
+                channel_id,
+                self_mute,
+                self_deaf,
+                input_device,
+                output_device,
+            },
+            session_id: None,
+            server: None,
+        };
+        payload
+    }
+
+    /// Change self mute / deafen; returns the op 4 payload, or None outside a session.
+    pub fn set_self_state(&mut self, self_mute: bool, self_deaf: bool) -> Option<serde_json::Value> {
+        let target = self.target_mut()?;
+        target.self_mute = self_mute;
+        target.self_deaf = self_deaf;
+        Some(voice_state_payload(&target.guild_id, Some(&target.channel_id), self_mute, self_deaf))
+    }
+
+    /// End the session; returns the op 4 payload (also sent when idle, to be sure the gateway agrees).
+    pub fn leave(&mut self) -> serde_json::Value {
+        let guild_id = self.target_mut().and_then(|t| t.guild_id.clone());
+        *self = VoiceSession::Idle;
+        voice_state_payload(&guild_id, None, false, false)
+    }
+
+    /// Our own VOICE_STATE_UPDATE.
+    pub fn on_voice_state(&mut self, user_id: &str, vs: &VoiceState) -> Vec<VoiceAction> {
+        let Some(target) = self.target_mut() else {
+            return Vec::new();
+        };
+        // Leaving another guild's channel when switching guilds is not about this session.
+        if vs.guild_id != target.guild_id {
+            return Vec::new();
+        }
+        let Some(channel_id) = vs.channel_id.clone() else {
+            eprintln!("[voice gateway] disconnected from voice by the server");
+            let guild_id = target.guild_id.clone();
+            *self = VoiceSession::Idle;
+            return vec![
+                VoiceAction::Voice(VoiceMessage::Leave),
+                VoiceAction::ChannelChanged { guild_id, channel_id: None },
+            ];
+        };
+        let mut actions = Vec::new();
+        if channel_id != target.channel_id {
+            eprintln!("[voice gateway] moved to voice channel {}", channel_id);
+            target.channel_id = channel_id.clone();
+            actions.push(VoiceAction::ChannelChanged {
+                guild_id: target.guild_id.clone(),
+                channel_id: Some(channel_id),
+            });
+        }
+        match self {
+            VoiceSession::Joining { session_id, .. } => {
+                *session_id = Some(vs.session_id.clone());
+                actions.extend(self.try_connect(user_id));
+            }
+            VoiceSession::Connected { target, session_id, .. } if *session_id != vs.session_id => {
+                // Another client of ours joined voice and took the session over.
+                eprintln!("[voice gateway] voice session taken over by another client");
+                let guild_id = target.guild_id.clone();
+                *self = VoiceSession::Idle;
+                actions.push(VoiceAction::Voice(VoiceMessage::Leave));
+                actions.push(VoiceAction::ChannelChanged { guild_id, channel_id: None });
+            }
+            _ => {}
+        }
+        actions
+    }
+
+    /// VOICE_SERVER_UPDATE: the server for a join, a server move while connected, or a reallocation.
+    pub fn on_voice_server(&mut self, user_id: &str, data: VoiceServerUpdateData) -> Vec<VoiceAction> {
+        match self.target_mut() {
+            Some(target) if target.guild_id == data.guild_id => {}
+            _ => return Vec::new(),
+        }
+        let new_server = data.endpoint.map(|endpoint| Server {
+            token: data.token,
+            endpoint,
+        });
+        match std::mem::take(self) {
+            VoiceSession::Joining { target, session_id, .. } => {
+                *self = VoiceSession::Joining {
+                    target,
+                    session_id,
+                    server: new_server,
+                };
+                self.try_connect(user_id)
+            }
+            VoiceSession::Connected { target, session_id, .. } => {
+                eprintln!("[voice gateway] voice server changed while connected");
+                *self = VoiceSession::Joining {
+                    target,
+                    session_id: Some(session_id),
+                    server: new_server,
+                };
+                self.try_connect(user_id)
+            }
+            VoiceSession::Idle => Vec::new(),
+        }
+    }
+
+    /// Joining -> Connected once both halves are in; returns the Connect for the voice task.
+    fn try_connect(&mut self, user_id: &str) -> Vec<VoiceAction> {
+        let VoiceSession::Joining {
+            target,
+            session_id: Some(session_id),
+            server: Some(server),
+        } = self
+        else {
+            return Vec::new();
+        };
+        let message = VoiceMessage::Connect {
+            info: VoiceConnectionInfo {
+                guild_id: target.guild_id.clone(),
+                channel_id: target.channel_id.clone(),
+                endpoint: server.endpoint.clone(),
+                token: server.token.clone(),
+                session_id: session_id.clone(),
+                user_id: user_id.to_string(),
+            },
+            input_device: target.input_device.clone(),
+            output_device: target.output_device.clone(),
+            self_mute: target.self_mute,
+            self_deaf: target.self_deaf,
+        };
+        eprintln!("[voice gateway] sending Connect channel={}", target.channel_id);
+        *self = VoiceSession::Connected {
+            target: target.clone(),
+            session_id: session_id.clone(),
+        };
+        vec![VoiceAction::Voice(message)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER: &str = "10";
+    const GUILD: &str = "20";
+
+    fn voice_state(guild_id: &str, channel_id: Option<&str>, session_id: &str) -> VoiceState {
+        serde_json::from_value(serde_json::json!({
+            "guild_id": guild_id,
+            "channel_id": channel_id,
+            "user_id": USER,
+            "session_id": session_id,
+        }))
+        .unwrap()
+    }
+
+    fn server(guild_id: &str, endpoint: Option<&str>) -> VoiceServerUpdateData {
+        VoiceServerUpdateData {
+            token: "token".to_string(),
+            guild_id: Some(guild_id.to_string()),
+            endpoint: endpoint.map(str::to_string),
+        }
+    }
+
+    fn joining(channel_id: &str) -> VoiceSession {
+        let mut session = VoiceSession::Idle;
+        session.join(Some(GUILD.to_string()), channel_id.to_string(), false, false, None, None);
+        session
+    }
+
+    /// The endpoint and session of the single Connect in `actions`.
+    fn connect(actions: &[VoiceAction]) -> (String, String) {
+        match actions {
+            [VoiceAction::Voice(VoiceMessage::Connect { info, .. })] => (info.endpoint.clone(), info.session_id.clone()),
+            _ => panic!("expected one Connect, got {actions:?}"),
+        }
+    }
+
+    fn is_disconnect(actions: &[VoiceAction]) -> bool {
+        matches!(
+            actions,
+            [
+                VoiceAction::Voice(VoiceMessage::Leave),
+                VoiceAction::ChannelChanged { channel_id: None, .. },
+            ]
+        )
+    }
+
+    #[test]
+    fn connects_once_state_and_server_are_in() {
+        let mut session = joining("30");
+        assert!(session.on_voice_state(USER, &voice_state(GUILD, Some("30"), "s1")).is_empty());
+        let actions = session.on_voice_server(USER, server(GUILD, Some("a.discord.media")));
+        assert_eq!(connect(&actions), ("a.discord.media".to_string(), "s1".to_string()));
+        assert!(matches!(session, VoiceSession::Connected { .. }));
+    }
+
+    #[test]
+    fn connects_when_server_comes_first() {
+        let mut session = joining("30");
+        assert!(session.on_voice_server(USER, server(GUILD, Some("a.discord.media"))).is_empty());
+        let actions = session.on_voice_state(USER, &voice_state(GUILD, Some("30"), "s1"));
+        assert_eq!(connect(&actions), ("a.discord.media".to_string(), "s1".to_string()));
+    }
+
+    #[test]
+    fn waits_for_an_endpoint() {
+        let mut session = joining("30");
+        session.on_voice_state(USER, &voice_state(GUILD, Some("30"), "s1"));
+        assert!(session.on_voice_server(USER, server(GUILD, None)).is_empty());
+        let actions = session.on_voice_server(USER, server(GUILD, Some("b.discord.media")));
+        assert_eq!(connect(&actions).0, "b.discord.media");
+    }
+
+    #[test]
+    fn new_server_while_connected_reconnects() {
+        let mut session = joining("30");
+        session.on_voice_state(USER, &voice_state(GUILD, Some("30"), "s1"));
+        session.on_voice_server(USER, server(GUILD, Some("a.discord.media")));
+        let actions = session.on_voice_server(USER, server(GUILD, Some("b.discord.media")));
+        assert_eq!(connect(&actions), ("b.discord.media".to_string(), "s1".to_string()));
+        // A reallocation (no endpoint yet) waits for the next update instead.
+        assert!(session.on_voice_server(USER, server(GUILD, None)).is_empty());
+        assert_eq!(connect(&session.on_voice_server(USER, server(GUILD, Some("c.discord.media")))).0, "c.discord.media");
+    }
+
+    #[test]
+    fn moderator_move_keeps_the_call() {
+        let mut session = joining("30");
+        session.on_voice_state(USER, &voice_state(GUILD, Some("30"), "s1"));
+        session.on_voice_server(USER, server(GUILD, Some("a.discord.media")));
+        let actions = session.on_voice_state(USER, &voice_state(GUILD, Some("31"), "s1"));
+        assert!(
+            matches!(
+                actions.as_slice(),
+                [VoiceAction::ChannelChanged { channel_id: Some(c), .. }] if c == "31"
+            ),
+            "got {actions:?}"
+        );
+        assert!(matches!(&session, VoiceSession::Connected { target, .. } if target.channel_id == "31"));
+    }
+
+    #[test]
+    fn kick_leaves() {
+        let mut session = joining("30");
+        session.on_voice_state(USER, &voice_state(GUILD, Some("30"), "s1"));
+        session.on_voice_server(USER, server(GUILD, Some("a.discord.media")));
+        assert!(is_disconnect(&session.on_voice_state(USER, &voice_state(GUILD, None, "s1"))));
+        assert!(matches!(session, VoiceSession::Idle));
+    }
+
+    #[test]
+    fn session_takeover_leaves() {
+        let mut session = joining("30");
+        session.on_voice_state(USER, &voice_state(GUILD, Some("30"), "s1"));
+        session.on_voice_server(USER, server(GUILD, Some("a.discord.media")));
+        assert!(is_disconnect(&session.on_voice_state(USER, &voice_state(GUILD, Some("30"), "s2"))));
+        assert!(matches!(session, VoiceSession::Idle));
+    }
+
+    #[test]
+    fn ignores_other_guilds() {
+        let mut session = joining("30");
+        session.on_voice_state(USER, &voice_state(GUILD, Some("30"), "s1"));
+        session.on_voice_server(USER, server(GUILD, Some("a.discord.media")));
+        assert!(session.on_voice_state(USER, &voice_state("21", None, "s0")).is_empty());
+        assert!(session.on_voice_server(USER, server("21", Some("x.discord.media"))).is_empty());
+        assert!(matches!(session, VoiceSession::Connected { .. }));
+    }
+}
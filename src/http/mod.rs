@@ -1,12 +1,30 @@
 //! HTTP client (reqwest): REST API.
 //! - GET /users/@me — verify token, get current user
-//! - GET /users/@me/relationships — friends (undocumented, user token)
+//! - GET /users/@me/relationships — friends, pending requests, blocked (undocumented, user token)
+//! - POST /users/@me/relationships, PUT / DELETE /users/@me/relationships/{id} — send, accept,
+//!   block, remove
 //! - GET /users/@me/channels — DM channels (user token)
+//! - GET /guilds/{id}/roles, GET /users/@me/guilds/{id}/member — for permission computation
 //! - GET /channels/{id}/messages — channel messages (with optional before)
 //! - POST /channels/{id}/messages — send message
+//! - POST /channels/{id}/messages/{id}/ack — mark channel read up to a message
+//! - POST /read-states/ack-bulk — mark several channels read at once
+//! - PATCH /users/@me/guilds/{id}/settings — mute / notification settings (`@me` for DMs)
+//! - GET /guilds/{id}/threads/active, GET /channels/{id}/threads/archived/public,
+//!   GET /channels/{id}/users/@me/threads/archived/private — thread listing
+//! - POST /channels/{id}/threads, POST /channels/{id}/messages/{id}/threads — start a thread or forum post
+//! - PUT / DELETE /channels/{id}/thread-members/@me — follow / leave a thread
+//! - GET /channels/{id}/messages/{id} — single message (forum post previews)
+//! - GET /users/{id}/profile — bio, banner and mutual servers for the profile popout
+//! - GET /applications/{id}/rpc — name of an application publishing Rich Presence over IPC
+//! - GET /oauth2/applications/{id}/assets — its Rich Presence art, to resolve asset names to IDs
+//! - POST /users/@me/channels, PUT / DELETE /channels/{id}/recipients/{id}, PATCH / DELETE /channels/{id}
+//!   — create, edit and leave group DMs
 
 use serde::Deserialize;
 
+use crate::state::GuildNotificationSettings;
+
 const API_BASE: &str = "https://discord.com/api/v10";
 
 fn api_client() -> Result<reqwest::Client, String> {
@@ -30,15 +48,54 @@ pub struct DiscordUser {
     pub avatar: Option<String>,
 }
 
-/// Relationship (friend, blocked, etc.) — GET /users/@me/relationships.
-#[derive(Clone, Debug, Deserialize)]
+/// Relationship (friend, blocked, etc.) — GET /users/@me/relationships. `id` is the other user's ID.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub struct Relationship {
+    pub id: String,
     #[serde(default)]
-    pub r#type: i32, // 1 = friend, 2 = blocked, 3 = incoming, 4 = outgoing
+    pub r#type: RelationshipType,
     pub user: DiscordUser,
 }
 
+/// The numeric relationship `type`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(from = "i32")]
+pub enum RelationshipType {
+    #[default]
+    None,
+    Friend,
+    Blocked,
+    /// Friend request we received.
+    Incoming,
+    /// Friend request we sent.
+    Outgoing,
+    Unknown(i32),
+}
+
+impl From<i32> for RelationshipType {
+    fn from(v: i32) -> Self {
+        match v {
+            0 => Self::None,
+            1 => Self::Friend,
+            2 => Self::Blocked,
+            3 => Self::Incoming,
+            4 => Self::Outgoing,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+/// What the friends page can do to a relationship.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelationshipAction {
+    /// Accept an incoming request.
+    Accept,
+    Block,
+    /// Remove a friend, decline or cancel a request, or unblock.
+    Remove,
+}
+
 /// Guild (server) from GET /users/@me/guilds.
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -47,10 +104,145 @@ pub struct ApiGuild {
     pub name: String,
     #[serde(default)]
     pub icon: Option<String>,
+    /// True when the current user owns the guild.
+    #[serde(default)]
+    pub owner: bool,
 }
 
-/// Guild channel from GET /guilds/{id}/channels. type: 0=text, 2=voice, 4=category.
-#[derive(Clone, Debug, Deserialize)]
+/// Guild role from GET /guilds/{id}/roles. The @everyone role has the guild's ID.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct Role {
+    pub id: String,
+    pub name: String,
+    /// Permission bitfield as a decimal string.
+    #[serde(default)]
+    pub permissions: String,
+    #[serde(default)]
+    pub position: i32,
+    #[serde(default)]
+    pub color: u32,
+}
+
+/// Name color of a member: the color of their highest positioned role that has one (0 means none).
+pub fn role_color(roles: &[Role], member_roles: &[String]) -> Option<u32> {
+    roles
+        .iter()
+        .filter(|r| r.color != 0 && member_roles.contains(&r.id))
+        .max_by_key(|r| r.position)
+        .map(|r| r.color)
+}
+
+/// Channel permission overwrite. type: 0 = role, 1 = member.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct PermissionOverwrite {
+    pub id: String,
+    #[serde(default)]
+    pub r#type: i32,
+    #[serde(default)]
+    pub allow: String,
+    #[serde(default)]
+    pub deny: String,
+}
+
+/// Discord channel type (the numeric `type` field). Unknown values are kept so they can be skipped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(from = "i32")]
+pub enum ChannelType {
+    #[default]
+    Text,
+    Dm,
+    Voice,
+    GroupDm,
+    Category,
+    Announcement,
+    AnnouncementThread,
+    PublicThread,
+    PrivateThread,
+    Stage,
+    Directory,
+    Forum,
+    Media,
+    Unknown(i32),
+}
+
+impl From<i32> for ChannelType {
+    fn from(v: i32) -> Self {
+        match v {
+            0 => Self::Text,
+            1 => Self::Dm,
+            2 => Self::Voice,
+            3 => Self::GroupDm,
+            4 => Self::Category,
+            5 => Self::Announcement,
+            10 => Self::AnnouncementThread,
+            11 => Self::PublicThread,
+            12 => Self::PrivateThread,
+            13 => Self::Stage,
+            14 => Self::Directory,
+            15 => Self::Forum,
+            16 => Self::Media,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+impl ChannelType {
+    pub fn to_api(self) -> i32 {
+        match self {
+            Self::Text => 0,
+            Self::Dm => 1,
+            Self::Voice => 2,
+            Self::GroupDm => 3,
+            Self::Category => 4,
+            Self::Announcement => 5,
+            Self::AnnouncementThread => 10,
+            Self::PublicThread => 11,
+            Self::PrivateThread => 12,
+            Self::Stage => 13,
+            Self::Directory => 14,
+            Self::Forum => 15,
+            Self::Media => 16,
+            Self::Unknown(v) => v,
+        }
+    }
+
+    pub fn is_thread(self) -> bool {
+        matches!(self, Self::AnnouncementThread | Self::PublicThread | Self::PrivateThread)
+    }
+
+    /// Voice and stage channels: joined rather than opened.
+    pub fn is_voice(self) -> bool {
+        matches!(self, Self::Voice | Self::Stage)
+    }
+
+    /// Channels that hold posts (threads) instead of messages.
+    pub fn is_forum(self) -> bool {
+        matches!(self, Self::Forum | Self::Media)
+    }
+
+    /// Channels listed with the text channels of a category.
+    pub fn is_text_like(self) -> bool {
+        matches!(self, Self::Text | Self::Announcement) || self.is_forum()
+    }
+
+    /// Prefix shown before the channel name.
+    pub fn icon(self) -> &'static str {
+        match self {
+            Self::Announcement => "📢",
+            Self::Voice => "🔊",
+            Self::Stage => "🎙",
+            Self::Forum => "🗨",
+            Self::Media => "🖼",
+            Self::AnnouncementThread | Self::PublicThread | Self::PrivateThread => "🧵",
+            _ => "#",
+        }
+    }
+}
+
+/// Guild channel from GET /guilds/{id}/channels.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub struct GuildChannel {
     pub id: String,
@@ -58,14 +250,106 @@ pub struct GuildChannel {
     pub guild_id: Option<String>,
     pub name: String,
     #[serde(default)]
-    pub r#type: i32,
+    pub r#type: ChannelType,
+    #[serde(default)]
+    pub position: i32,
+    #[serde(default)]
+    pub topic: Option<String>,
+    #[serde(default)]
+    pub nsfw: bool,
+    /// Slowmode in seconds; 0 when off.
+    #[serde(default)]
+    pub rate_limit_per_user: u32,
+    /// For categories: the category. For threads: the channel the thread belongs to.
     #[serde(default)]
     pub parent_id: Option<String>,
+    #[serde(default)]
+    pub last_message_id: Option<String>,
+    #[serde(default)]
+    pub permission_overwrites: Vec<PermissionOverwrite>,
+    /// Threads only.
+    #[serde(default)]
+    pub thread_metadata: Option<ThreadMetadata>,
+    #[serde(default)]
+    pub owner_id: Option<String>,
+    #[serde(default)]
+    pub message_count: Option<u32>,
+    #[serde(default)]
+    pub member_count: Option<u32>,
+    /// Forum and media channels: tags posts can carry.
+    #[serde(default)]
+    pub available_tags: Vec<ForumTag>,
+    /// Forum posts: IDs of the parent's `available_tags` applied to this post.
+    #[serde(default)]
+    pub applied_tags: Vec<String>,
+    /// Forum and media channels: 0 = latest activity, 1 = creation date.
+    #[serde(default)]
+    pub default_sort_order: Option<i32>,
+}
+
+impl GuildChannel {
+    pub fn is_archived(&self) -> bool {
+        self.thread_metadata.as_ref().map(|m| m.archived).unwrap_or(false)
+    }
+}
+
+/// Tag of a forum or media channel.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct ForumTag {
+    pub id: String,
+    pub name: String,
+    /// Only moderators (MANAGE_THREADS) can apply it.
+    #[serde(default)]
+    pub moderated: bool,
+    #[serde(default)]
+    pub emoji_name: Option<String>,
+}
+
+/// Thread state (`thread_metadata` on thread channels).
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct ThreadMetadata {
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub locked: bool,
+    /// Minutes of inactivity before the thread is archived (60, 1440, 4320, 10080).
+    #[serde(default)]
+    pub auto_archive_duration: u32,
+    #[serde(default)]
+    pub archive_timestamp: Option<String>,
 }
 
-/// Guild member from GET /guilds/{id}/members.
+/// Our membership in a thread; `id` is the thread.
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
+pub struct ThreadMember {
+    #[serde(default)]
+    pub id: Option<String>,
+}
+
+/// Response of the thread listing endpoints. `members` holds our own memberships.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ThreadList {
+    #[serde(default)]
+    pub threads: Vec<GuildChannel>,
+    #[serde(default)]
+    pub members: Vec<ThreadMember>,
+}
+
+/// Which archived threads to list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchivedThreads {
+    Public,
+    /// Private threads we are a member of (listing all of them needs MANAGE_THREADS).
+    JoinedPrivate,
+}
+
+/// Guild member: our own from GET /users/@me/guilds/{id}/member, others from GUILD_MEMBERS_CHUNK.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
 pub struct GuildMember {
     #[serde(default)]
     pub user: Option<DiscordUser>,
@@ -73,21 +357,128 @@ pub struct GuildMember {
     pub nick: Option<String>,
     #[serde(default)]
     pub roles: Vec<String>,
+    /// Per-guild avatar hash; overrides the user avatar in this guild.
+    #[serde(default)]
+    pub avatar: Option<String>,
+    /// ISO8601; only sent with full member objects (chunks, profiles).
+    #[serde(default)]
+    pub joined_at: Option<String>,
+}
+
+impl GuildMember {
+    /// CDN URL of the per-guild avatar, if the member set one.
+    pub fn guild_avatar_url(&self, guild_id: &str) -> Option<String> {
+        let user = self.user.as_ref()?;
+        let hash = self.avatar.as_ref()?;
+        let ext = if hash.starts_with("a_") { "gif" } else { "png" };
+        Some(format!(
+            "https://cdn.discordapp.com/guilds/{}/users/{}/avatars/{}.{}",
+            guild_id, user.id, hash, ext
+        ))
+    }
+}
+
+/// Bio and banner of a user, or their per-guild override.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct ProfileMetadata {
+    #[serde(default)]
+    pub bio: Option<String>,
+    #[serde(default)]
+    pub banner: Option<String>,
+}
+
+/// A server we share with the user.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct MutualGuild {
+    pub id: String,
+    #[serde(default)]
+    pub nick: Option<String>,
+}
+
+/// GET /users/{id}/profile (undocumented, user token). `guild_member*` are only set when asked with a guild_id.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct UserProfile {
+    pub user: DiscordUser,
+    #[serde(default)]
+    pub user_profile: Option<ProfileMetadata>,
+    #[serde(default)]
+    pub guild_member: Option<GuildMember>,
+    #[serde(default)]
+    pub guild_member_profile: Option<ProfileMetadata>,
+    #[serde(default)]
+    pub mutual_guilds: Vec<MutualGuild>,
+}
+
+impl UserProfile {
+    /// The guild bio wins over the global one when set.
+    pub fn bio(&self) -> Option<&str> {
+        [&self.guild_member_profile, &self.user_profile]
+            .into_iter()
+            .flatten()
+            .filter_map(|p| p.bio.as_deref())
+            .find(|b| !b.trim().is_empty())
+    }
+
+    pub fn banner_url(&self, guild_id: Option<&str>) -> Option<String> {
+        let uid = &self.user.id;
+        let guild_banner = self
+            .guild_member_profile
+            .as_ref()
+            .and_then(|p| p.banner.as_ref())
+            .zip(guild_id)
+            .map(|(hash, gid)| format!("https://cdn.discordapp.com/guilds/{gid}/users/{uid}/banners/{hash}.png?size=600"));
+        guild_banner.or_else(|| {
+            let hash = self.user_profile.as_ref()?.banner.as_ref()?;
+            let ext = if hash.starts_with("a_") { "gif" } else { "png" };
+            Some(format!("https://cdn.discordapp.com/banners/{uid}/{hash}.{ext}?size=600"))
+        })
+    }
 }
 
 /// DM or Group DM channel — GET /users/@me/channels.
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub struct DmChannel {
     pub id: String,
+    /// `Dm` or `GroupDm`.
     #[serde(default)]
-    pub r#type: i32, // 1 = DM, 3 = Group DM
+    pub r#type: ChannelType,
+    /// Everyone but us.
     #[serde(default)]
     pub recipients: Vec<DiscordUser>,
     #[serde(default)]
     pub last_message_id: Option<String>,
     #[serde(default)]
     pub name: Option<String>,
+    /// Group DM icon hash.
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Group DM owner; only they can remove recipients.
+    #[serde(default)]
+    pub owner_id: Option<String>,
+}
+
+impl DmChannel {
+    pub fn is_group(&self) -> bool {
+        self.r#type == ChannelType::GroupDm
+    }
+
+    pub fn icon_url(&self) -> Option<String> {
+        let hash = self.icon.as_ref()?;
+        Some(format!("https://cdn.discordapp.com/channel-icons/{}/{}.png", self.id, hash))
+    }
+}
+
+/// Change to a group DM (PATCH /channels/{id}).
+#[derive(Clone, Debug, PartialEq)]
+pub enum GroupDmEdit {
+    /// Empty clears the name (the client then lists the recipients).
+    Name(String),
+    /// Image as a data URI, or None to remove the icon.
+    Icon(Option<String>),
 }
 
 /// Message from Discord API (GET /channels/{id}/messages).
@@ -102,6 +493,12 @@ pub struct ApiMessage {
     /// ISO8601 timestamp when the message was sent.
     #[serde(default)]
     pub timestamp: Option<String>,
+    #[serde(default)]
+    pub guild_id: Option<String>,
+    #[serde(default)]
+    pub mentions: Vec<DiscordUser>,
+    #[serde(default)]
+    pub mention_everyone: bool,
 }
 
 /// Verify token by fetching current user. Returns user on success, error message on 401/invalid.
@@ -127,7 +524,7 @@ pub async fn verify_token(token: &str) -> Result<DiscordUser, String> {
     Ok(user)
 }
 
-/// Get friends list (type=1). Undocumented; returns empty on 404/403.
+/// Get all relationships (friends, requests, blocked). Undocumented; returns empty on 404/403.
 pub async fn get_relationships(token: &str) -> Result<Vec<Relationship>, String> {
     let client = api_client()?;
     let resp = client
@@ -143,6 +540,46 @@ pub async fn get_relationships(token: &str) -> Result<Vec<Relationship>, String>
     Ok(list)
 }
 
+/// Send a friend request by username (the new unique usernames, no discriminator).
+pub async fn send_friend_request(token: &str, username: &str) -> Result<(), String> {
+    let client = api_client()?;
+    let resp = client
+        .post(format!("{API_BASE}/users/@me/relationships"))
+        .header("Authorization", token.trim())
+        .json(&serde_json::json!({ "username": username, "discriminator": null }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    Ok(())
+}
+
+/// Accept, block or remove the relationship with `user_id`.
+pub async fn update_relationship(token: &str, user_id: &str, action: RelationshipAction) -> Result<(), String> {
+    let client = api_client()?;
+    let url = format!("{API_BASE}/users/@me/relationships/{user_id}");
+    let req = match action {
+        RelationshipAction::Accept => client.put(url).json(&serde_json::json!({})),
+        RelationshipAction::Block => client.put(url).json(&serde_json::json!({ "type": 2 })),
+        RelationshipAction::Remove => client.delete(url),
+    };
+    let resp = req
+        .header("Authorization", token.trim())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    Ok(())
+}
+
 /// Get guilds (servers) the user is in. GET /users/@me/guilds.
 pub async fn get_user_guilds(token: &str) -> Result<Vec<ApiGuild>, String> {
     let client = api_client()?;
@@ -177,16 +614,11 @@ pub async fn get_guild_channels(token: &str, guild_id: &str) -> Result<Vec<Guild
     Ok(list)
 }
 
-/// Get members in a guild. GET /guilds/{guild_id}/members. Limit 1-1000.
-pub async fn get_guild_members(
-    token: &str,
-    guild_id: &str,
-    limit: u32,
-) -> Result<Vec<GuildMember>, String> {
+/// Get roles in a guild. GET /guilds/{guild_id}/roles.
+pub async fn get_guild_roles(token: &str, guild_id: &str) -> Result<Vec<Role>, String> {
     let client = api_client()?;
     let resp = client
-        .get(format!("{API_BASE}/guilds/{guild_id}/members"))
-        .query(&[("limit", limit)])
+        .get(format!("{API_BASE}/guilds/{guild_id}/roles"))
         .header("Authorization", token.trim())
         .send()
         .await
@@ -196,10 +628,28 @@ pub async fn get_guild_members(
         let body = resp.text().await.unwrap_or_default();
         return Err(format!("API error {}: {}", status, body));
     }
-    let list: Vec<GuildMember> = resp.json().await.map_err(|e| e.to_string())?;
+    let list: Vec<Role> = resp.json().await.map_err(|e| e.to_string())?;
     Ok(list)
 }
 
+/// Get the current user's member object (roles) in a guild. GET /users/@me/guilds/{guild_id}/member.
+pub async fn get_current_member(token: &str, guild_id: &str) -> Result<GuildMember, String> {
+    let client = api_client()?;
+    let resp = client
+        .get(format!("{API_BASE}/users/@me/guilds/{guild_id}/member"))
+        .header("Authorization", token.trim())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    let member: GuildMember = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(member)
+}
+
 /// Get DM channels. May return empty if endpoint not available for user token.
 pub async fn get_dm_channels(token: &str) -> Result<Vec<DmChannel>, String> {
     let client = api_client()?;
@@ -237,6 +687,83 @@ pub async fn create_dm(token: &str, recipient_id: &str) -> Result<DmChannel, Str
     Ok(ch)
 }
 
+/// Create a group DM with the given users (friends only; at most 9 besides us).
+pub async fn create_group_dm(token: &str, recipient_ids: &[String]) -> Result<DmChannel, String> {
+    let client = api_client()?;
+    let resp = client
+        .post(format!("{API_BASE}/users/@me/channels"))
+        .header("Authorization", token.trim())
+        .json(&serde_json::json!({ "recipients": recipient_ids }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    let ch: DmChannel = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(ch)
+}
+
+/// Add (`add`) or remove a user from a group DM.
+pub async fn set_group_recipient(token: &str, channel_id: &str, user_id: &str, add: bool) -> Result<(), String> {
+    let client = api_client()?;
+    let url = format!("{API_BASE}/channels/{channel_id}/recipients/{user_id}");
+    let req = if add { client.put(url) } else { client.delete(url) };
+    let resp = req
+        .header("Authorization", token.trim())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    Ok(())
+}
+
+/// Rename a group DM or change its icon. Returns the updated channel.
+pub async fn edit_group_dm(token: &str, channel_id: &str, edit: &GroupDmEdit) -> Result<DmChannel, String> {
+    let client = api_client()?;
+    let body = match edit {
+        GroupDmEdit::Name(name) => serde_json::json!({ "name": name }),
+        GroupDmEdit::Icon(icon) => serde_json::json!({ "icon": icon }),
+    };
+    let resp = client
+        .patch(format!("{API_BASE}/channels/{channel_id}"))
+        .header("Authorization", token.trim())
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    let ch: DmChannel = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(ch)
+}
+
+/// Leave a group DM (or close a DM).
+pub async fn close_channel(token: &str, channel_id: &str) -> Result<(), String> {
+    let client = api_client()?;
+    let resp = client
+        .delete(format!("{API_BASE}/channels/{channel_id}"))
+        .header("Authorization", token.trim())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    Ok(())
+}
+
 /// Get messages for a channel (DM or guild). Limit 1–100. Returns newest first.
 pub async fn fetch_channel_messages(
     token: &str,
@@ -260,6 +787,99 @@ pub async fn fetch_channel_messages(
     Ok(list)
 }
 
+/// Get one message. GET /channels/{channel_id}/messages/{message_id}.
+/// A forum post's starter message has the same ID as the post.
+pub async fn get_message(token: &str, channel_id: &str, message_id: &str) -> Result<ApiMessage, String> {
+    let client = api_client()?;
+    let resp = client
+        .get(format!("{API_BASE}/channels/{channel_id}/messages/{message_id}"))
+        .header("Authorization", token.trim())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    let msg: ApiMessage = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(msg)
+}
+
+/// Profile of a user. With `guild_id`, also their member object and guild profile in that guild.
+pub async fn get_user_profile(token: &str, user_id: &str, guild_id: Option<&str>) -> Result<UserProfile, String> {
+    let client = api_client()?;
+    let mut query: Vec<(&str, &str)> = vec![("with_mutual_guilds", "true")];
+    if let Some(gid) = guild_id {
+        query.push(("guild_id", gid));
+    }
+    let resp = client
+        .get(format!("{API_BASE}/users/{user_id}/profile"))
+        .query(&query)
+        .header("Authorization", token.trim())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    let profile: UserProfile = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(profile)
+}
+
+/// Public info of an application (GET /applications/{id}/rpc).
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct RpcApplication {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+/// Application name and icon for a Rich Presence client ID.
+pub async fn get_rpc_application(token: &str, application_id: &str) -> Result<RpcApplication, String> {
+    let client = api_client()?;
+    let resp = client
+        .get(format!("{API_BASE}/applications/{application_id}/rpc"))
+        .header("Authorization", token.trim())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    let app: RpcApplication = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(app)
+}
+
+/// One Rich Presence art asset of an application (GET /oauth2/applications/{id}/assets).
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct RpcAsset {
+    pub id: String,
+    pub name: String,
+}
+
+/// Art assets of a Rich Presence application; SET_ACTIVITY names them, op 3 wants their IDs.
+pub async fn get_application_assets(token: &str, application_id: &str) -> Result<Vec<RpcAsset>, String> {
+    let client = api_client()?;
+    let resp = client
+        .get(format!("{API_BASE}/oauth2/applications/{application_id}/assets"))
+        .header("Authorization", token.trim())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
 /// Get messages before a given message ID (for loading older messages). Returns newest first in batch.
 pub async fn fetch_channel_messages_before(
     token: &str,
@@ -326,3 +946,209 @@ pub async fn send_message(
     let msg: ApiMessage = resp.json().await.map_err(|e| e.to_string())?;
     Ok(msg)
 }
+
+/// Mark a channel as read up to `message_id`. POST /channels/{id}/messages/{id}/ack.
+pub async fn ack_message(token: &str, channel_id: &str, message_id: &str) -> Result<(), String> {
+    let client = api_client()?;
+    let body = serde_json::json!({ "token": serde_json::Value::Null });
+    let resp = client
+        .post(format!("{API_BASE}/channels/{channel_id}/messages/{message_id}/ack"))
+        .header("Authorization", token.trim())
+        .header("Content-Type", "application/json")
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("Ack API error: {}", resp.status()));
+    }
+    Ok(())
+}
+
+/// Mark several channels read in one request: (channel_id, message_id) pairs.
+pub async fn ack_bulk(token: &str, acks: &[(String, String)]) -> Result<(), String> {
+    let client = api_client()?;
+    let read_states: Vec<serde_json::Value> = acks
+        .iter()
+        .map(|(channel_id, message_id)| {
+            serde_json::json!({ "channel_id": channel_id, "message_id": message_id, "read_state_type": 0 })
+        })
+        .collect();
+    let body = serde_json::json!({ "read_states": read_states });
+    let resp = client
+        .post(format!("{API_BASE}/read-states/ack-bulk"))
+        .header("Authorization", token.trim())
+        .header("Content-Type", "application/json")
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("Ack API error: {}", resp.status()));
+    }
+    Ok(())
+}
+
+/// Update the user's notification settings for a guild (`None` = DMs). Returns the full new settings.
+pub async fn update_guild_settings(
+    token: &str,
+    guild_id: Option<&str>,
+    patch: &serde_json::Value,
+) -> Result<GuildNotificationSettings, String> {
+    let client = api_client()?;
+    let gid = guild_id.unwrap_or("@me");
+    let resp = client
+        .patch(format!("{API_BASE}/users/@me/guilds/{gid}/settings"))
+        .header("Authorization", token.trim())
+        .header("Content-Type", "application/json")
+        .json(patch)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    let settings: GuildNotificationSettings = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+/// Active (non-archived) threads in a guild. GET /guilds/{guild_id}/threads/active.
+pub async fn get_active_threads(token: &str, guild_id: &str) -> Result<ThreadList, String> {
+    let client = api_client()?;
+    let resp = client
+        .get(format!("{API_BASE}/guilds/{guild_id}/threads/active"))
+        .header("Authorization", token.trim())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    let list: ThreadList = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(list)
+}
+
+/// Archived threads of a channel, newest first. `before` is an ISO8601 archive timestamp for paging.
+pub async fn get_archived_threads(
+    token: &str,
+    channel_id: &str,
+    kind: ArchivedThreads,
+    before: Option<&str>,
+) -> Result<ThreadList, String> {
+    let client = api_client()?;
+    let url = match kind {
+        ArchivedThreads::Public => format!("{API_BASE}/channels/{channel_id}/threads/archived/public"),
+        ArchivedThreads::JoinedPrivate => {
+            format!("{API_BASE}/channels/{channel_id}/users/@me/threads/archived/private")
+        }
+    };
+    let mut req = client
+        .get(url)
+        .query(&[("limit", "50")])
+        .header("Authorization", token.trim());
+    if let Some(b) = before {
+        req = req.query(&[("before", b)]);
+    }
+    let resp = req.send().await.map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    let list: ThreadList = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(list)
+}
+
+/// Start a thread. With `message_id` it hangs off that message, otherwise it is a standalone
+/// public or private thread. POST /channels/{id}/messages/{id}/threads or /channels/{id}/threads.
+pub async fn start_thread(
+    token: &str,
+    channel_id: &str,
+    message_id: Option<&str>,
+    name: &str,
+    private: bool,
+) -> Result<GuildChannel, String> {
+    let client = api_client()?;
+    let (url, body) = match message_id {
+        Some(mid) => (
+            format!("{API_BASE}/channels/{channel_id}/messages/{mid}/threads"),
+            serde_json::json!({ "name": name, "auto_archive_duration": 1440 }),
+        ),
+        None => {
+            let kind = if private { ChannelType::PrivateThread } else { ChannelType::PublicThread };
+            (
+                format!("{API_BASE}/channels/{channel_id}/threads"),
+                serde_json::json!({ "name": name, "auto_archive_duration": 1440, "type": kind.to_api() }),
+            )
+        }
+    };
+    let resp = client
+        .post(url)
+        .header("Authorization", token.trim())
+        .header("Content-Type", "application/json")
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    let thread: GuildChannel = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(thread)
+}
+
+/// Create a forum or media post: a thread with a starter message. POST /channels/{id}/threads.
+pub async fn create_forum_post(
+    token: &str,
+    channel_id: &str,
+    title: &str,
+    content: &str,
+    applied_tags: &[String],
+) -> Result<GuildChannel, String> {
+    let client = api_client()?;
+    let body = serde_json::json!({
+        "name": title,
+        "auto_archive_duration": 4320,
+        "applied_tags": applied_tags,
+        "message": { "content": content },
+    });
+    let resp = client
+        .post(format!("{API_BASE}/channels/{channel_id}/threads"))
+        .header("Authorization", token.trim())
+        .header("Content-Type", "application/json")
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    let post: GuildChannel = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(post)
+}
+
+/// Follow (`join = true`) or leave a thread. PUT / DELETE /channels/{id}/thread-members/@me.
+pub async fn set_thread_membership(token: &str, thread_id: &str, join: bool) -> Result<(), String> {
+    let client = api_client()?;
+    let url = format!("{API_BASE}/channels/{thread_id}/thread-members/@me");
+    let req = if join { client.put(url) } else { client.delete(url) };
+    let resp = req
+        .header("Authorization", token.trim())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+    Ok(())
+}
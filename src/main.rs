@@ -1,6 +1,12 @@
 mod app;
 mod gateway;
 mod http;
+mod member_list;
+mod notifications;
+mod permissions;
+#[cfg(feature = "voice")]
+mod push_to_talk;
+mod rich_presence;
 mod state;
 mod ui;
 mod updater;
@@ -8,6 +14,9 @@ mod updater;
 mod voice;
 #[cfg(feature = "voice")]
 mod voice_audio;
+#[cfg(feature = "voice")]
+mod voice_dsp;
+mod voice_state;
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
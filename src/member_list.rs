@@ -0,0 +1,232 @@
+//! Lazy guild member list, the way the official client fills its member sidebar:
+//! - op 14 subscribes to ranges (100 rows each) of one channel's member list
+//! - GUILD_MEMBER_LIST_UPDATE answers with SYNC per range, then INSERT / UPDATE / DELETE / INVALIDATE
+//!   ops on a flat list of group headers (hoisted roles, online, offline) and members.
+//!
+//! Members outside the list (message authors, mentions) are fetched by ID with op 8 and arrive in
+//! GUILD_MEMBERS_CHUNK.
+
+use serde::Deserialize;
+
+use crate::http::DiscordUser;
+use crate::state::{deserialize_activities, Activity, PresenceStatus};
+
+/// Rows per subscribed range.
+pub const RANGE_SIZE: u32 = 100;
+/// The gateway keeps at most this many ranges per channel; the first one is always included.
+const MAX_RANGES: usize = 3;
+
+/// op 14: which ranges of `channel_id`'s member list we want updates for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GuildSubscription {
+    pub guild_id: String,
+    pub channel_id: String,
+    /// Inclusive row ranges.
+    pub ranges: Vec<(u32, u32)>,
+}
+
+impl GuildSubscription {
+    /// The first range plus the ones covering rows `first..=last` (what is on screen).
+    pub fn for_rows(guild_id: String, channel_id: String, first: u32, last: u32) -> Self {
+        let mut ranges = vec![(0, RANGE_SIZE - 1)];
+        for chunk in (first / RANGE_SIZE)..=(last / RANGE_SIZE) {
+            let range = (chunk * RANGE_SIZE, chunk * RANGE_SIZE + RANGE_SIZE - 1);
+            if !ranges.contains(&range) && ranges.len() < MAX_RANGES {
+                ranges.push(range);
+            }
+        }
+        Self {
+            guild_id,
+            channel_id,
+            ranges,
+        }
+    }
+
+    pub fn to_payload(&self) -> serde_json::Value {
+        let ranges: Vec<[u32; 2]> = self.ranges.iter().map(|&(a, b)| [a, b]).collect();
+        serde_json::json!({
+            "op": 14,
+            "d": {
+                "guild_id": self.guild_id,
+                "typing": true,
+                "threads": true,
+                "activities": true,
+                "members": [],
+                "channels": { self.channel_id.as_str(): ranges },
+            }
+        })
+    }
+}
+
+/// op 8 accepts at most this many `user_ids` per request.
+pub const MEMBER_REQUEST_MAX_IDS: usize = 100;
+
+/// op 8: fetch specific members of a guild by user ID.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemberRequest {
+    pub guild_id: String,
+    pub user_ids: Vec<String>,
+}
+
+impl MemberRequest {
+    pub fn to_payload(&self) -> serde_json::Value {
+        serde_json::json!({
+            "op": 8,
+            "d": {
+                "guild_id": self.guild_id,
+                "user_ids": self.user_ids,
+                "presences": false,
+            }
+        })
+    }
+}
+
+/// Group header: a hoisted role ID, "online" or "offline".
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct MemberListGroup {
+    pub id: String,
+    #[serde(default)]
+    pub count: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct MemberPresence {
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_activities")]
+    pub activities: Vec<Activity>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct ListMember {
+    pub user: DiscordUser,
+    #[serde(default)]
+    pub nick: Option<String>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub presence: Option<MemberPresence>,
+}
+
+impl ListMember {
+    pub fn display_name(&self) -> &str {
+        self.nick
+            .as_deref()
+            .filter(|n| !n.is_empty())
+            .or(self.user.global_name.as_deref())
+            .unwrap_or(self.user.username.as_str())
+    }
+
+    pub fn activities(&self) -> &[Activity] {
+        self.presence.as_ref().map(|p| p.activities.as_slice()).unwrap_or_default()
+    }
+
+    pub fn status(&self) -> PresenceStatus {
+        match self.presence.as_ref().and_then(|p| p.status.as_deref()) {
+            Some("online") => PresenceStatus::Online,
+            Some("idle") => PresenceStatus::Idle,
+            Some("dnd") => PresenceStatus::DoNotDisturb,
+            _ => PresenceStatus::Invisible,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemberListItem {
+    Group(MemberListGroup),
+    Member(ListMember),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "UPPERCASE")]
+pub enum MemberListOp {
+    Sync {
+        range: (u32, u32),
+        #[serde(default)]
+        items: Vec<MemberListItem>,
+    },
+    Insert { index: usize, item: MemberListItem },
+    Update { index: usize, item: MemberListItem },
+    Delete { index: usize },
+    Invalidate { range: (u32, u32) },
+}
+
+/// GUILD_MEMBER_LIST_UPDATE payload. `id` names the list ("everyone" or a hash of the view permissions).
+#[derive(Clone, Debug, Deserialize)]
+pub struct MemberListUpdate {
+    pub guild_id: String,
+    pub id: String,
+    #[serde(default)]
+    pub member_count: u32,
+    #[serde(default)]
+    pub online_count: u32,
+    #[serde(default)]
+    pub groups: Vec<MemberListGroup>,
+    #[serde(default)]
+    pub ops: Vec<MemberListOp>,
+}
+
+/// Client copy of one member list. Rows outside the subscribed ranges are None.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MemberList {
+    pub guild_id: String,
+    pub list_id: String,
+    pub member_count: u32,
+    pub online_count: u32,
+    pub groups: Vec<MemberListGroup>,
+    pub items: Vec<Option<MemberListItem>>,
+}
+
+impl MemberList {
+    pub fn apply(&mut self, update: MemberListUpdate) {
+        if update.guild_id != self.guild_id || update.id != self.list_id {
+            *self = MemberList {
+                guild_id: update.guild_id,
+                list_id: update.id,
+                ..Default::default()
+            };
+        }
+        self.member_count = update.member_count;
+        self.online_count = update.online_count;
+        self.groups = update.groups;
+        for op in update.ops {
+            match op {
+                MemberListOp::Sync { range, items } => {
+                    self.ensure_len(range.0 as usize + items.len());
+                    for (i, item) in items.into_iter().enumerate() {
+                        self.items[range.0 as usize + i] = Some(item);
+                    }
+                }
+                MemberListOp::Insert { index, item } => {
+                    self.ensure_len(index);
+                    self.items.insert(index, Some(item));
+                }
+                MemberListOp::Update { index, item } => {
+                    self.ensure_len(index + 1);
+                    self.items[index] = Some(item);
+                }
+                MemberListOp::Delete { index } => {
+                    if index < self.items.len() {
+                        self.items.remove(index);
+                    }
+                }
+                MemberListOp::Invalidate { range } => {
+                    let end = (range.1 as usize + 1).min(self.items.len());
+                    for slot in self.items.iter_mut().take(end).skip(range.0 as usize) {
+                        *slot = None;
+                    }
+                }
+            }
+        }
+        // Every non-empty group is one header row plus its members.
+        let total: usize = self.groups.iter().filter(|g| g.count > 0).map(|g| g.count as usize + 1).sum();
+        self.items.resize(total, None);
+    }
+
+    fn ensure_len(&mut self, len: usize) {
+        if self.items.len() < len {
+            self.items.resize(len, None);
+        }
+    }
+}
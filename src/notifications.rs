@@ -0,0 +1,264 @@
+//! Desktop notifications for new messages.
+//! - `should_notify`: mute settings, Discord notification levels, keywords, DND and window focus
+//! - `spawn_notifier`: posts notifications (freedesktop D-Bus on Linux, `notify-send` / `osascript` fallback)
+//!   and reports clicks back to the app as `(guild_id, channel_id)`.
+
+use dioxus::prelude::spawn;
+use tokio::sync::mpsc;
+
+use crate::state::{Message, NotificationLevel, NotificationSettings, PresenceStatus};
+
+/// Longest message preview shown in a notification body.
+const BODY_MAX_CHARS: usize = 200;
+
+/// A notification ready to post; clicking it opens `channel_id`.
+#[derive(Clone, Debug)]
+pub struct DesktopNotification {
+    pub title: String,
+    pub body: String,
+    pub guild_id: Option<String>,
+    pub channel_id: String,
+}
+
+/// Everything besides the message itself that decides whether to notify.
+pub struct NotifyContext<'a> {
+    pub enabled: bool,
+    pub user_id: &'a str,
+    pub presence: PresenceStatus,
+    pub window_focused: bool,
+    pub selected_channel_id: Option<&'a str>,
+    pub settings: &'a NotificationSettings,
+    pub keywords: &'a [String],
+}
+
+/// True when `msg` should raise a desktop notification.
+pub fn should_notify(msg: &Message, ctx: &NotifyContext) -> bool {
+    if !ctx.enabled || msg.author_id == ctx.user_id || ctx.presence == PresenceStatus::DoNotDisturb {
+        return false;
+    }
+    // The user is already looking at it.
+    if ctx.window_focused && ctx.selected_channel_id == Some(msg.channel_id.as_str()) {
+        return false;
+    }
+    let guild_id = msg.guild_id.as_deref();
+    if ctx.settings.is_muted(guild_id, &msg.channel_id) {
+        return false;
+    }
+    let suppress_everyone = ctx
+        .settings
+        .for_guild(guild_id)
+        .map(|g| g.suppress_everyone)
+        .unwrap_or(false);
+    let mentioned = msg.mention_ids.iter().any(|id| id == ctx.user_id)
+        || (msg.mention_everyone && !suppress_everyone)
+        || matches_keyword(&msg.content, ctx.keywords);
+    match ctx.settings.level_for(guild_id, &msg.channel_id) {
+        NotificationLevel::AllMessages => true,
+        NotificationLevel::OnlyMentions => mentioned,
+        NotificationLevel::Nothing | NotificationLevel::Inherit => false,
+    }
+}
+
+/// Case-insensitive whole-word match against the user's keywords.
+fn matches_keyword(content: &str, keywords: &[String]) -> bool {
+    let haystack = content.to_lowercase();
+    keywords
+        .iter()
+        .map(|k| k.trim().to_lowercase())
+        .filter(|k| !k.is_empty())
+        .any(|k| {
+            haystack.match_indices(k.as_str()).any(|(i, m)| {
+                let before = haystack[..i].chars().next_back();
+                let after = haystack[i + m.len()..].chars().next();
+                !before.map(char::is_alphanumeric).unwrap_or(false)
+                    && !after.map(char::is_alphanumeric).unwrap_or(false)
+            })
+        })
+}
+
+/// Title is the author, plus "#channel, Server" for guild messages.
+pub fn build_notification(msg: &Message, channel_name: Option<&str>, guild_name: Option<&str>) -> DesktopNotification {
+    let author = msg.author_username.clone().unwrap_or_else(|| "Someone".to_string());
+    let title = match (channel_name, guild_name) {
+        (Some(ch), Some(g)) => format!("{} (#{}, {})", author, ch, g),
+        (Some(ch), None) => format!("{} (#{})", author, ch),
+        (None, Some(g)) => format!("{} ({})", author, g),
+        (None, None) => author,
+    };
+    let mut body: String = msg.content.chars().take(BODY_MAX_CHARS).collect();
+    if msg.content.chars().count() > BODY_MAX_CHARS {
+        body.push('…');
+    }
+    if body.trim().is_empty() {
+        body = "Sent an attachment".to_string();
+    }
+    DesktopNotification {
+        title,
+        body,
+        guild_id: msg.guild_id.clone(),
+        channel_id: msg.channel_id.clone(),
+    }
+}
+
+/// Spawn the notification task. Clicked notifications are sent on `tx_clicks`
+/// (only the D-Bus backend reports clicks).
+pub fn spawn_notifier(
+    mut rx: mpsc::UnboundedReceiver<DesktopNotification>,
+    tx_clicks: mpsc::UnboundedSender<(Option<String>, String)>,
+) {
+    spawn(async move {
+        #[cfg(target_os = "linux")]
+        match dbus::connect().await {
+            Ok((conn, signals)) => {
+                dbus::run(conn, signals, &mut rx, &tx_clicks).await;
+                return;
+            }
+            Err(e) => eprintln!("[notifications] D-Bus unavailable, using fallback: {}", e),
+        }
+        #[cfg(not(target_os = "linux"))]
+        drop(tx_clicks);
+        while let Some(n) = rx.recv().await {
+            post_fallback(&n).await;
+        }
+    });
+}
+
+/// Post via a command-line tool when D-Bus is not available.
+async fn post_fallback(n: &DesktopNotification) {
+    #[cfg(target_os = "linux")]
+    let cmd = {
+        let mut c = tokio::process::Command::new("notify-send");
+        c.arg("--app-name=Velocity").arg(&n.title).arg(&n.body);
+        Some(c)
+    };
+    #[cfg(target_os = "macos")]
+    let cmd = {
+        let script = format!(
+            "display notification \"{}\" with title \"{}\"",
+            applescript_escape(&n.body),
+            applescript_escape(&n.title)
+        );
+        let mut c = tokio::process::Command::new("osascript");
+        c.arg("-e").arg(script);
+        Some(c)
+    };
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    let cmd: Option<tokio::process::Command> = None;
+
+    match cmd {
+        Some(mut c) => {
+            if let Err(e) = c.status().await {
+                eprintln!("[notifications] {}: {}", n.title, n.body);
+                eprintln!("[notifications] fallback failed: {}", e);
+            }
+        }
+        None => eprintln!("[notifications] {}: {}", n.title, n.body),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn applescript_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(target_os = "linux")]
+mod dbus {
+    use std::collections::HashMap;
+
+    use futures_util::StreamExt;
+    use tokio::sync::mpsc;
+    use zbus::zvariant::Value;
+    use zbus::{Connection, MatchRule, MessageStream, MessageType};
+
+    use super::{post_fallback, DesktopNotification};
+
+    const DEST: &str = "org.freedesktop.Notifications";
+    const PATH: &str = "/org/freedesktop/Notifications";
+
+    /// Session bus connection plus a stream of the notification server's signals.
+    pub async fn connect() -> zbus::Result<(Connection, MessageStream)> {
+        let conn = Connection::session().await?;
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .interface(DEST)?
+            .path(PATH)?
+            .build();
+        let signals = MessageStream::for_match_rule(rule, &conn, None).await?;
+        Ok((conn, signals))
+    }
+
+    pub async fn run(
+        conn: Connection,
+        mut signals: MessageStream,
+        rx: &mut mpsc::UnboundedReceiver<DesktopNotification>,
+        tx_clicks: &mpsc::UnboundedSender<(Option<String>, String)>,
+    ) {
+        // Notification ID -> channel to open when it is clicked.
+        let mut targets: HashMap<u32, (Option<String>, String)> = HashMap::new();
+        loop {
+            tokio::select! {
+                n = rx.recv() => {
+                    let Some(n) = n else { break };
+                    match notify(&conn, &n).await {
+                        Ok(id) => {
+                            targets.insert(id, (n.guild_id, n.channel_id));
+                        }
+                        Err(e) => {
+                            eprintln!("[notifications] Notify failed: {}", e);
+                            post_fallback(&n).await;
+                        }
+                    }
+                }
+                Some(Ok(msg)) = signals.next() => {
+                    let member = msg.member();
+                    match member.as_ref().map(|m| m.as_str()) {
+                        Some("ActionInvoked") => {
+                            if let Ok((id, _action)) = msg.body::<(u32, String)>() {
+                                if let Some(target) = targets.remove(&id) {
+                                    let _ = tx_clicks.send(target);
+                                }
+                            }
+                        }
+                        Some("NotificationClosed") => {
+                            if let Ok((id, _reason)) = msg.body::<(u32, u32)>() {
+                                targets.remove(&id);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    async fn notify(conn: &Connection, n: &DesktopNotification) -> zbus::Result<u32> {
+        let mut hints: HashMap<&str, Value> = HashMap::new();
+        hints.insert("desktop-entry", Value::from("velocity"));
+        hints.insert("category", Value::from("im.received"));
+        let body = escape_markup(&n.body);
+        let reply = conn
+            .call_method(
+                Some(DEST),
+                PATH,
+                Some(DEST),
+                "Notify",
+                &(
+                    "Velocity",
+                    0u32,
+                    "",
+                    n.title.as_str(),
+                    body.as_str(),
+                    vec!["default", "Open"],
+                    hints,
+                    -1i32,
+                ),
+            )
+            .await?;
+        reply.body::<u32>()
+    }
+
+    /// Servers with body-markup treat the body as a small HTML subset.
+    fn escape_markup(s: &str) -> String {
+        s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+    }
+}
@@ -0,0 +1,123 @@
+//! Guild permission computation (Discord's algorithm):
+//! base = @everyone role | member roles (owner and ADMINISTRATOR get everything),
+//! then per channel: @everyone overwrite, combined role overwrites, member overwrite.
+
+use std::collections::HashMap;
+
+use crate::http::{GuildChannel, Role};
+
+pub const ADMINISTRATOR: u64 = 1 << 3;
+pub const VIEW_CHANNEL: u64 = 1 << 10;
+pub const SEND_MESSAGES: u64 = 1 << 11;
+pub const CONNECT: u64 = 1 << 20;
+pub const SEND_MESSAGES_IN_THREADS: u64 = 1 << 38;
+pub const ALL: u64 = u64::MAX;
+
+/// Overwrite target type in `permission_overwrites`.
+const OVERWRITE_ROLE: i32 = 0;
+const OVERWRITE_MEMBER: i32 = 1;
+
+/// Permission bitfields are sent as decimal strings.
+pub fn parse_bits(s: &str) -> u64 {
+    s.parse().unwrap_or(0)
+}
+
+/// What we know about ourselves in one guild.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PermissionContext {
+    pub guild_id: String,
+    pub user_id: String,
+    pub is_owner: bool,
+    /// Role ID -> permissions; the @everyone role has the guild's ID.
+    pub role_permissions: HashMap<String, u64>,
+    pub member_roles: Vec<String>,
+}
+
+impl PermissionContext {
+    pub fn new(guild_id: String, user_id: String, is_owner: bool, roles: &[Role], member_roles: Vec<String>) -> Self {
+        Self {
+            guild_id,
+            user_id,
+            is_owner,
+            role_permissions: roles.iter().map(|r| (r.id.clone(), parse_bits(&r.permissions))).collect(),
+            member_roles,
+        }
+    }
+
+    /// Guild-wide permissions before channel overwrites.
+    pub fn base(&self) -> u64 {
+        if self.is_owner {
+            return ALL;
+        }
+        let mut perms = self.role_permissions.get(&self.guild_id).copied().unwrap_or(0);
+        for role in &self.member_roles {
+            perms |= self.role_permissions.get(role).copied().unwrap_or(0);
+        }
+        if perms & ADMINISTRATOR != 0 {
+            return ALL;
+        }
+        perms
+    }
+
+    /// Permissions in a channel. Threads use their parent's overwrites, so `channels` is used to look it up.
+    pub fn for_channel(&self, channel: &GuildChannel, channels: &[GuildChannel]) -> u64 {
+        let base = self.base();
+        if base & ADMINISTRATOR != 0 {
+            return ALL;
+        }
+        let source = if channel.r#type.is_thread() {
+            channel
+                .parent_id
+                .as_ref()
+                .and_then(|pid| channels.iter().find(|c| &c.id == pid))
+                .unwrap_or(channel)
+        } else {
+            channel
+        };
+        let overwrites = &source.permission_overwrites;
+        let mut perms = base;
+        if let Some(o) = overwrites.iter().find(|o| o.id == self.guild_id) {
+            perms &= !parse_bits(&o.deny);
+            perms |= parse_bits(&o.allow);
+        }
+        let (mut allow, mut deny) = (0u64, 0u64);
+        for o in overwrites
+            .iter()
+            .filter(|o| o.r#type == OVERWRITE_ROLE && self.member_roles.contains(&o.id))
+        {
+            allow |= parse_bits(&o.allow);
+            deny |= parse_bits(&o.deny);
+        }
+        perms &= !deny;
+        perms |= allow;
+        if let Some(o) = overwrites
+            .iter()
+            .find(|o| o.r#type == OVERWRITE_MEMBER && o.id == self.user_id)
+        {
+            perms &= !parse_bits(&o.deny);
+            perms |= parse_bits(&o.allow);
+        }
+        // Without VIEW_CHANNEL nothing else applies.
+        if perms & VIEW_CHANNEL == 0 {
+            return 0;
+        }
+        perms
+    }
+
+    pub fn can_view(&self, channel: &GuildChannel, channels: &[GuildChannel]) -> bool {
+        self.for_channel(channel, channels) & VIEW_CHANNEL != 0
+    }
+
+    pub fn can_send(&self, channel: &GuildChannel, channels: &[GuildChannel]) -> bool {
+        let needed = if channel.r#type.is_thread() {
+            SEND_MESSAGES_IN_THREADS
+        } else {
+            SEND_MESSAGES
+        };
+        self.for_channel(channel, channels) & needed != 0
+    }
+
+    pub fn can_connect(&self, channel: &GuildChannel, channels: &[GuildChannel]) -> bool {
+        self.for_channel(channel, channels) & CONNECT != 0
+    }
+}
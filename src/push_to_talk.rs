@@ -0,0 +1,63 @@
+//! Global push-to-talk key (voice feature).
+//! The key is registered system-wide so it works while the window is unfocused; press/release
+//! events set `VoiceControls::ptt_pressed`, which the input gate reads on the next captured block.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+
+use crate::voice_audio::VoiceControls;
+
+/// Owns the OS registration; create it on the main thread and keep it alive for the session.
+pub struct PushToTalk {
+    manager: GlobalHotKeyManager,
+    current: Option<HotKey>,
+    /// ID of `current` (0 when unbound), read by the event handler.
+    bound_id: Arc<AtomicU32>,
+    controls: Arc<VoiceControls>,
+}
+
+impl PushToTalk {
+    pub fn new(controls: Arc<VoiceControls>) -> Result<Self, String> {
+        let manager = GlobalHotKeyManager::new().map_err(|e| e.to_string())?;
+        let bound_id = Arc::new(AtomicU32::new(0));
+        let handler_id = bound_id.clone();
+        let handler_controls = controls.clone();
+        GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+            if event.id() == handler_id.load(Ordering::Relaxed) {
+                handler_controls
+                    .ptt_pressed
+                    .store(event.state() == HotKeyState::Pressed, Ordering::Relaxed);
+            }
+        }));
+        Ok(Self {
+            manager,
+            current: None,
+            bound_id,
+            controls,
+        })
+    }
+
+    /// Replace the registered key. `key` uses the settings format, e.g. "shift+KeyT" or "Backquote".
+    pub fn bind(&mut self, key: Option<&str>) -> Result<(), String> {
+        let hotkey = key
+            .map(|k| k.parse::<HotKey>().map_err(|e| format!("invalid keybind {k:?}: {e}")))
+            .transpose()?;
+        if hotkey == self.current {
+            return Ok(());
+        }
+        if let Some(old) = self.current.take() {
+            let _ = self.manager.unregister(old);
+        }
+        self.bound_id.store(0, Ordering::Relaxed);
+        self.controls.ptt_pressed.store(false, Ordering::Relaxed);
+        if let Some(hotkey) = hotkey {
+            self.manager.register(hotkey).map_err(|e| e.to_string())?;
+            self.bound_id.store(hotkey.id(), Ordering::Relaxed);
+            self.current = Some(hotkey);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,326 @@
+//! Local Rich Presence server: the `discord-ipc-N` socket games and tools use to publish activities.
+//! - frames are `op: u32 LE`, `len: u32 LE`, then `len` bytes of JSON
+//! - op 0 HANDSHAKE `{v, client_id}` is answered with a READY dispatch
+//! - op 1 FRAME carries commands; SET_ACTIVITY sets or clears the client's activity
+//! - op 2 CLOSE, op 3 PING (answered with op 4 PONG)
+//!
+//! Each connection is reported to the app as `IpcEvent`s, which forwards the activities in op 3.
+//! Activity art comes as the application's asset names and is sent as asset IDs.
+
+use std::collections::HashMap;
+
+use dioxus::prelude::*;
+use tokio::sync::mpsc;
+
+use crate::http::DiscordUser;
+use crate::state::{Activity, ActivityKind};
+
+/// An application connected over IPC, as shown in settings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IpcClient {
+    pub client_id: String,
+    /// From GET /applications/{id}/rpc; None until loaded.
+    pub app_name: Option<String>,
+    pub app_icon: Option<String>,
+    /// Rich Presence asset IDs by lowercase name, from GET /oauth2/applications/{id}/assets; None until loaded.
+    pub assets: Option<HashMap<String, String>>,
+    pub activity: Option<Activity>,
+}
+
+impl IpcClient {
+    pub fn display_name(&self) -> &str {
+        self.app_name.as_deref().unwrap_or(self.client_id.as_str())
+    }
+
+    pub fn icon_url(&self) -> Option<String> {
+        self.app_icon
+            .as_ref()
+            .map(|hash| format!("https://cdn.discordapp.com/app-icons/{}/{}.png", self.client_id, hash))
+    }
+
+    /// The activity as sent in op 3: named after the application and tagged with its ID.
+    pub fn presence_activity(&self) -> Option<Activity> {
+        let mut activity = self.activity.clone()?;
+        if activity.name.is_empty() {
+            activity.name = self.display_name().to_string();
+        }
+        activity.application_id = Some(self.client_id.clone());
+        if let Some(assets) = activity.assets.as_mut() {
+            assets.large_image = self.resolve_asset(assets.large_image.take());
+            assets.small_image = self.resolve_asset(assets.small_image.take());
+        }
+        Some(activity)
+    }
+
+    /// An image key as op 3 takes it: an asset ID or an `mp:` media proxy path pass through, an asset
+    /// name is looked up; anything else (unknown names, plain URLs) would not render and is dropped.
+    fn resolve_asset(&self, key: Option<String>) -> Option<String> {
+        let key = key.filter(|k| !k.is_empty())?;
+        if key.starts_with("mp:") || key.bytes().all(|b| b.is_ascii_digit()) {
+            return Some(key);
+        }
+        self.assets.as_ref()?.get(&key.to_lowercase()).cloned()
+    }
+}
+
+/// What happened on one connection (`conn` is a per-process connection counter).
+#[derive(Clone, Debug)]
+pub enum IpcEvent {
+    Connected { conn: u64, client_id: String },
+    /// None clears the activity.
+    SetActivity { conn: u64, activity: Option<Box<Activity>> },
+    Disconnected { conn: u64 },
+}
+
+/// Start listening on the first free `discord-ipc-N`. `current_user` is reported in the READY dispatch.
+pub fn spawn_ipc_server(current_user: Signal<Option<DiscordUser>>, tx: mpsc::UnboundedSender<IpcEvent>) {
+    #[cfg(unix)]
+    spawn(async move {
+        // `_socket_file` lives as long as this task: it is unlinked on an accept error or at shutdown.
+        let (listener, _socket_file) = match unix::bind().await {
+            Ok(bound) => bound,
+            Err(e) => {
+                eprintln!("[rpc] IPC server not started: {}", e);
+                return;
+            }
+        };
+        let mut next_conn = 0u64;
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    next_conn += 1;
+                    let conn = next_conn;
+                    let tx = tx.clone();
+                    spawn(async move {
+                        if let Err(e) = unix::serve(stream, conn, current_user, &tx).await {
+                            eprintln!("[rpc] connection {} closed: {}", conn, e);
+                        }
+                        let _ = tx.send(IpcEvent::Disconnected { conn });
+                    });
+                }
+                Err(e) => {
+                    eprintln!("[rpc] accept failed: {}", e);
+                    return;
+                }
+            }
+        }
+    });
+    #[cfg(not(unix))]
+    {
+        let _ = (current_user, tx);
+        eprintln!("[rpc] IPC server is only available on Unix");
+    }
+}
+
+/// Activity from SET_ACTIVITY `args.activity`; RPC sends timestamps in seconds, the gateway wants milliseconds.
+fn activity_from_rpc(value: serde_json::Value) -> Result<Option<Activity>, String> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    let mut activity: Activity = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    if activity.kind == ActivityKind::Custom {
+        return Err("custom status can not be set over RPC".to_string());
+    }
+    if let Some(ts) = activity.timestamps.as_mut() {
+        for t in [&mut ts.start, &mut ts.end].into_iter().flatten() {
+            if *t < 10_000_000_000 {
+                *t *= 1000;
+            }
+        }
+    }
+    Ok(Some(activity))
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::path::PathBuf;
+
+    use dioxus::prelude::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{UnixListener, UnixStream};
+    use tokio::sync::mpsc;
+
+    use super::{activity_from_rpc, IpcEvent};
+    use crate::http::DiscordUser;
+
+    const OP_HANDSHAKE: u32 = 0;
+    const OP_FRAME: u32 = 1;
+    const OP_CLOSE: u32 = 2;
+    const OP_PING: u32 = 3;
+    const OP_PONG: u32 = 4;
+    /// Clients try discord-ipc-0 through discord-ipc-9.
+    const MAX_SOCKETS: u32 = 10;
+    /// Larger frames are treated as a broken client.
+    const MAX_FRAME_LEN: usize = 64 * 1024;
+
+    fn socket_dir() -> PathBuf {
+        ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+            .iter()
+            .find_map(std::env::var_os)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+    }
+
+    /// Bind the first `discord-ipc-N` not held by a running client. Stale sockets are replaced.
+    pub async fn bind() -> Result<(UnixListener, SocketFile), String> {
+        let dir = socket_dir();
+        for n in 0..MAX_SOCKETS {
+            let path = dir.join(format!("discord-ipc-{n}"));
+            // symlink_metadata: a symlink planted in a shared /tmp must not be followed.
+            if let Ok(meta) = std::fs::symlink_metadata(&path) {
+                if UnixStream::connect(&path).await.is_ok() {
+                    continue;
+                }
+                if let Err(e) = check_stale_socket(&meta) {
+                    eprintln!("[rpc] {}: {}", path.display(), e);
+                    continue;
+                }
+                let _ = std::fs::remove_file(&path);
+            }
+            match UnixListener::bind(&path) {
+                Ok(listener) => {
+                    eprintln!("[rpc] listening on {}", path.display());
+                    return Ok((listener, SocketFile(path)));
+                }
+                Err(e) => eprintln!("[rpc] {}: {}", path.display(), e),
+            }
+        }
+        Err(format!("no free discord-ipc socket in {}", dir.display()))
+    }
+
+    /// The bound socket's path; unlinked when the server stops so the next start finds the name free.
+    pub struct SocketFile(PathBuf);
+
+    impl Drop for SocketFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// Only our own socket file may be removed; anything else at that path is left alone.
+    fn check_stale_socket(meta: &std::fs::Metadata) -> Result<(), String> {
+        use std::os::unix::fs::{FileTypeExt, MetadataExt};
+        if !meta.file_type().is_socket() {
+            return Err("not a socket, leaving it in place".to_string());
+        }
+        // SAFETY: geteuid has no preconditions and cannot fail.
+        if meta.uid() != unsafe { libc::geteuid() } {
+            return Err("socket owned by another user, leaving it in place".to_string());
+        }
+        Ok(())
+    }
+
+    async fn read_frame(stream: &mut UnixStream) -> Result<(u32, serde_json::Value), String> {
+        let mut header = [0u8; 8];
+        stream.read_exact(&mut header).await.map_err(|e| e.to_string())?;
+        let op = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        if len > MAX_FRAME_LEN {
+            return Err(format!("frame too large ({len} bytes)"));
+        }
+        let mut body = vec![0u8; len];
+        stream.read_exact(&mut body).await.map_err(|e| e.to_string())?;
+        let value = serde_json::from_slice(&body).map_err(|e| e.to_string())?;
+        Ok((op, value))
+    }
+
+    async fn write_frame(stream: &mut UnixStream, op: u32, value: &serde_json::Value) -> Result<(), String> {
+        let body = value.to_string();
+        let mut frame = Vec::with_capacity(8 + body.len());
+        frame.extend_from_slice(&op.to_le_bytes());
+        frame.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        frame.extend_from_slice(body.as_bytes());
+        stream.write_all(&frame).await.map_err(|e| e.to_string())
+    }
+
+    fn ready_payload(user: Option<DiscordUser>) -> serde_json::Value {
+        let user = user.map(|u| {
+            serde_json::json!({
+                "id": u.id,
+                "username": u.username,
+                "global_name": u.global_name,
+                "discriminator": u.discriminator.unwrap_or_else(|| "0".to_string()),
+                "avatar": u.avatar,
+                "bot": false,
+            })
+        });
+        serde_json::json!({
+            "cmd": "DISPATCH",
+            "evt": "READY",
+            "data": {
+                "v": 1,
+                "config": {
+                    "cdn_host": "cdn.discordapp.com",
+                    "api_endpoint": "//discord.com/api",
+                    "environment": "production",
+                },
+                "user": user,
+            },
+            "nonce": null,
+        })
+    }
+
+    fn error_payload(cmd: &str, nonce: &serde_json::Value, message: &str) -> serde_json::Value {
+        serde_json::json!({
+            "cmd": cmd,
+            "evt": "ERROR",
+            "data": { "code": 4000, "message": message },
+            "nonce": nonce,
+        })
+    }
+
+    /// Handshake, then answer commands until the client closes.
+    pub async fn serve(
+        mut stream: UnixStream,
+        conn: u64,
+        current_user: Signal<Option<DiscordUser>>,
+        tx: &mpsc::UnboundedSender<IpcEvent>,
+    ) -> Result<(), String> {
+        let (op, handshake) = read_frame(&mut stream).await?;
+        let client_id = handshake.get("client_id").and_then(|v| v.as_str()).unwrap_or_default();
+        if op != OP_HANDSHAKE || client_id.is_empty() {
+            let close = serde_json::json!({ "code": 4000, "message": "Invalid handshake" });
+            let _ = write_frame(&mut stream, OP_CLOSE, &close).await;
+            return Err("invalid handshake".to_string());
+        }
+        let _ = tx.send(IpcEvent::Connected {
+            conn,
+            client_id: client_id.to_string(),
+        });
+        let user = current_user.peek().clone();
+        write_frame(&mut stream, OP_FRAME, &ready_payload(user)).await?;
+
+        loop {
+            let (op, value) = read_frame(&mut stream).await?;
+            match op {
+                OP_FRAME => {
+                    let cmd = value.get("cmd").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+                    let nonce = value.get("nonce").cloned().unwrap_or(serde_json::Value::Null);
+                    let args = value.get("args").cloned().unwrap_or(serde_json::Value::Null);
+                    let reply = match cmd.as_str() {
+                        "SET_ACTIVITY" => {
+                            let raw = args.get("activity").cloned().unwrap_or(serde_json::Value::Null);
+                            match activity_from_rpc(raw.clone()) {
+                                Ok(activity) => {
+                                    let _ = tx.send(IpcEvent::SetActivity { conn, activity: activity.map(Box::new) });
+                                    serde_json::json!({ "cmd": cmd, "evt": null, "data": raw, "nonce": nonce })
+                                }
+                                Err(e) => error_payload(&cmd, &nonce, &e),
+                            }
+                        }
+                        // Join / spectate requests are never sent, but clients expect the subscription to succeed.
+                        "SUBSCRIBE" | "UNSUBSCRIBE" => {
+                            let evt = args.get("evt").cloned().unwrap_or(serde_json::Value::Null);
+                            serde_json::json!({ "cmd": cmd, "evt": null, "data": { "evt": evt }, "nonce": nonce })
+                        }
+                        _ => error_payload(&cmd, &nonce, "Unknown command"),
+                    };
+                    write_frame(&mut stream, OP_FRAME, &reply).await?;
+                }
+                OP_PING => write_frame(&mut stream, OP_PONG, &value).await?,
+                OP_CLOSE => return Ok(()),
+                other => return Err(format!("unexpected op {other}")),
+            }
+        }
+    }
+}
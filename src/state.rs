@@ -24,15 +24,329 @@ pub struct Channel {
 pub struct Message {
     pub id: String,
     pub channel_id: String,
+    /// None for DMs and group DMs.
+    pub guild_id: Option<String>,
     pub author_id: String,
     pub author_username: Option<String>,
+    /// Avatar hash of the author's user account.
+    pub author_avatar: Option<String>,
     pub content: String,
     pub timestamp: Option<String>,
+    /// IDs of users mentioned with <@id>.
+    pub mention_ids: Vec<String>,
+    pub mention_everyone: bool,
     /// When true, shows "Sending…" instead of content.
     #[allow(dead_code)]
     pub sending: bool,
 }
 
+/// Read state of one channel: the newest message the user has seen vs. the newest message sent.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReadState {
+    /// None for DMs.
+    pub guild_id: Option<String>,
+    pub last_read_id: Option<String>,
+    pub last_message_id: Option<String>,
+    /// Unacked mentions (every DM message counts as one).
+    pub mention_count: u32,
+}
+
+impl ReadState {
+    /// Channels we have never acked only count as unread when they hold mentions,
+    /// otherwise every old channel in every guild would light up.
+    pub fn is_unread(&self) -> bool {
+        match (&self.last_message_id, &self.last_read_id) {
+            (Some(last), Some(read)) => snowflake_cmp(last, read).is_gt(),
+            (Some(_), None) => self.mention_count > 0,
+            _ => false,
+        }
+    }
+}
+
+/// Compare two snowflake IDs by age without parsing (longer IDs are newer).
+pub fn snowflake_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// Creation time encoded in a snowflake (milliseconds since the Discord epoch, 2015-01-01).
+pub fn snowflake_time(id: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    const DISCORD_EPOCH_MS: u64 = 1_420_070_400_000;
+    let raw: u64 = id.parse().ok()?;
+    chrono::DateTime::from_timestamp_millis(((raw >> 22) + DISCORD_EPOCH_MS) as i64)
+}
+
+/// True for real message IDs; optimistic sends use a temporary "sending-…" ID.
+pub fn is_snowflake(id: &str) -> bool {
+    !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Discord message notification level (guild default, user guild setting or channel override).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(from = "Option<i64>")]
+pub enum NotificationLevel {
+    AllMessages,
+    OnlyMentions,
+    Nothing,
+    /// Use the next level up (channel -> guild -> guild default).
+    #[default]
+    Inherit,
+}
+
+impl NotificationLevel {
+    pub fn to_api(self) -> i64 {
+        match self {
+            NotificationLevel::AllMessages => 0,
+            NotificationLevel::OnlyMentions => 1,
+            NotificationLevel::Nothing => 2,
+            NotificationLevel::Inherit => 3,
+        }
+    }
+}
+
+impl From<Option<i64>> for NotificationLevel {
+    fn from(v: Option<i64>) -> Self {
+        match v {
+            Some(0) => NotificationLevel::AllMessages,
+            Some(1) => NotificationLevel::OnlyMentions,
+            Some(2) => NotificationLevel::Nothing,
+            _ => NotificationLevel::Inherit,
+        }
+    }
+}
+
+/// Timed mute; no end time means muted until turned off.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct MuteConfig {
+    #[serde(default)]
+    pub end_time: Option<String>,
+}
+
+fn mute_active(muted: bool, config: &Option<MuteConfig>) -> bool {
+    if !muted {
+        return false;
+    }
+    match config.as_ref().and_then(|c| c.end_time.as_deref()) {
+        Some(end) => chrono::DateTime::parse_from_rfc3339(end)
+            .map(|t| t > chrono::Utc::now())
+            .unwrap_or(true),
+        None => true,
+    }
+}
+
+/// Per-channel entry of `user_guild_settings.channel_overrides`.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct ChannelNotificationOverride {
+    pub channel_id: String,
+    #[serde(default)]
+    pub muted: bool,
+    #[serde(default)]
+    pub mute_config: Option<MuteConfig>,
+    #[serde(default)]
+    pub message_notifications: NotificationLevel,
+}
+
+impl ChannelNotificationOverride {
+    pub fn is_muted(&self) -> bool {
+        mute_active(self.muted, &self.mute_config)
+    }
+}
+
+/// User's notification settings for one guild (READY `user_guild_settings`, USER_GUILD_SETTINGS_UPDATE).
+/// `guild_id` is None for the DM settings, which only carry channel overrides.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct GuildNotificationSettings {
+    #[serde(default)]
+    pub guild_id: Option<String>,
+    #[serde(default)]
+    pub muted: bool,
+    #[serde(default)]
+    pub mute_config: Option<MuteConfig>,
+    #[serde(default)]
+    pub message_notifications: NotificationLevel,
+    #[serde(default)]
+    pub suppress_everyone: bool,
+    #[serde(default)]
+    pub channel_overrides: Vec<ChannelNotificationOverride>,
+}
+
+impl GuildNotificationSettings {
+    pub fn is_muted(&self) -> bool {
+        mute_active(self.muted, &self.mute_config)
+    }
+
+    pub fn channel_override(&self, channel_id: &str) -> Option<&ChannelNotificationOverride> {
+        self.channel_overrides.iter().find(|o| o.channel_id == channel_id)
+    }
+}
+
+/// Key of the DM entry in `NotificationSettings::guilds` (matches the `@me` REST path).
+pub const DM_SETTINGS_KEY: &str = "@me";
+
+/// All notification settings synced from Discord.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NotificationSettings {
+    /// By guild ID, or `DM_SETTINGS_KEY` for direct messages.
+    pub guilds: HashMap<String, GuildNotificationSettings>,
+    /// Server-wide default (`default_message_notifications`) by guild ID.
+    pub guild_defaults: HashMap<String, NotificationLevel>,
+}
+
+impl NotificationSettings {
+    pub fn for_guild(&self, guild_id: Option<&str>) -> Option<&GuildNotificationSettings> {
+        self.guilds.get(guild_id.unwrap_or(DM_SETTINGS_KEY))
+    }
+
+    pub fn insert(&mut self, settings: GuildNotificationSettings) {
+        let key = settings.guild_id.clone().unwrap_or_else(|| DM_SETTINGS_KEY.to_string());
+        self.guilds.insert(key, settings);
+    }
+
+    /// Apply a change locally (before the server confirms it).
+    pub fn apply(&mut self, change: &NotificationSettingsChange) {
+        let key = change.guild_id.clone().unwrap_or_else(|| DM_SETTINGS_KEY.to_string());
+        let g = self.guilds.entry(key).or_insert_with(|| GuildNotificationSettings {
+            guild_id: change.guild_id.clone(),
+            ..Default::default()
+        });
+        let (muted, mute_config, _) = change.mute_fields();
+        match &change.channel_id {
+            Some(cid) => {
+                let pos = match g.channel_overrides.iter().position(|o| &o.channel_id == cid) {
+                    Some(pos) => pos,
+                    None => {
+                        g.channel_overrides.push(ChannelNotificationOverride {
+                            channel_id: cid.clone(),
+                            ..Default::default()
+                        });
+                        g.channel_overrides.len() - 1
+                    }
+                };
+                let o = &mut g.channel_overrides[pos];
+                match change.change {
+                    NotificationChange::Mute(_) | NotificationChange::Unmute => {
+                        o.muted = muted;
+                        o.mute_config = mute_config;
+                    }
+                    NotificationChange::Level(level) => o.message_notifications = level,
+                    NotificationChange::SuppressEveryone(_) => {}
+                }
+            }
+            None => match change.change {
+                NotificationChange::Mute(_) | NotificationChange::Unmute => {
+                    g.muted = muted;
+                    g.mute_config = mute_config;
+                }
+                NotificationChange::Level(level) => g.message_notifications = level,
+                NotificationChange::SuppressEveryone(on) => g.suppress_everyone = on,
+            },
+        }
+    }
+
+    pub fn is_guild_muted(&self, guild_id: &str) -> bool {
+        self.guilds.get(guild_id).map(|g| g.is_muted()).unwrap_or(false)
+    }
+
+    /// True when the guild or the channel itself is muted.
+    pub fn is_muted(&self, guild_id: Option<&str>, channel_id: &str) -> bool {
+        let Some(g) = self.for_guild(guild_id) else {
+            return false;
+        };
+        (guild_id.is_some() && g.is_muted())
+            || g.channel_override(channel_id).map(|o| o.is_muted()).unwrap_or(false)
+    }
+
+    /// True when only the channel (not its guild) is muted.
+    pub fn is_channel_muted(&self, guild_id: Option<&str>, channel_id: &str) -> bool {
+        self.for_guild(guild_id)
+            .and_then(|g| g.channel_override(channel_id))
+            .map(|o| o.is_muted())
+            .unwrap_or(false)
+    }
+
+    /// Effective level: channel override, then the user's guild setting, then the guild default.
+    /// DMs notify for every message unless overridden.
+    pub fn level_for(&self, guild_id: Option<&str>, channel_id: &str) -> NotificationLevel {
+        let g = self.for_guild(guild_id);
+        if let Some(level) = g
+            .and_then(|g| g.channel_override(channel_id))
+            .map(|o| o.message_notifications)
+            .filter(|l| *l != NotificationLevel::Inherit)
+        {
+            return level;
+        }
+        let Some(gid) = guild_id else {
+            return NotificationLevel::AllMessages;
+        };
+        if let Some(level) = g
+            .map(|g| g.message_notifications)
+            .filter(|l| *l != NotificationLevel::Inherit)
+        {
+            return level;
+        }
+        self.guild_defaults
+            .get(gid)
+            .copied()
+            .filter(|l| *l != NotificationLevel::Inherit)
+            .unwrap_or(NotificationLevel::OnlyMentions)
+    }
+}
+
+/// What a notification menu action changes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NotificationChange {
+    /// Mute for this many minutes; None mutes until turned off.
+    Mute(Option<u32>),
+    Unmute,
+    Level(NotificationLevel),
+    /// Guild only.
+    SuppressEveryone(bool),
+}
+
+/// A change to the notification settings of a guild (`channel_id` None) or of one channel.
+/// `guild_id` None targets DMs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NotificationSettingsChange {
+    pub guild_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub change: NotificationChange,
+}
+
+impl NotificationSettingsChange {
+    fn mute_fields(&self) -> (bool, Option<MuteConfig>, serde_json::Value) {
+        match self.change {
+            NotificationChange::Mute(minutes) => {
+                let end_time = minutes.map(|m| (chrono::Utc::now() + chrono::Duration::minutes(m as i64)).to_rfc3339());
+                let window = minutes.map(|m| m as i64 * 60).unwrap_or(-1);
+                let json = serde_json::json!({ "selected_time_window": window, "end_time": end_time });
+                (true, Some(MuteConfig { end_time }), json)
+            }
+            _ => (false, None, serde_json::Value::Null),
+        }
+    }
+
+    /// Body for PATCH /users/@me/guilds/{id}/settings.
+    pub fn to_patch(&self) -> serde_json::Value {
+        let (muted, _, mute_config) = self.mute_fields();
+        let mut fields = serde_json::Map::new();
+        match self.change {
+            NotificationChange::Mute(_) | NotificationChange::Unmute => {
+                fields.insert("muted".into(), muted.into());
+                fields.insert("mute_config".into(), mute_config);
+            }
+            NotificationChange::Level(level) => {
+                fields.insert("message_notifications".into(), level.to_api().into());
+            }
+            NotificationChange::SuppressEveryone(on) => {
+                fields.insert("suppress_everyone".into(), on.into());
+            }
+        }
+        match &self.channel_id {
+            Some(cid) => serde_json::json!({ "channel_overrides": { cid.as_str(): fields } }),
+            None => serde_json::Value::Object(fields),
+        }
+    }
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct State {
     pub token: Option<String>,
@@ -74,6 +388,244 @@ pub enum PresenceStatus {
     Invisible,
 }
 
+/// Activity `type`: what the user is doing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "u8", into = "u8")]
+pub enum ActivityKind {
+    #[default]
+    Playing,
+    Streaming,
+    Listening,
+    Watching,
+    Custom,
+    Competing,
+    Unknown(u8),
+}
+
+impl From<u8> for ActivityKind {
+    fn from(v: u8) -> Self {
+        match v {
+            0 => ActivityKind::Playing,
+            1 => ActivityKind::Streaming,
+            2 => ActivityKind::Listening,
+            3 => ActivityKind::Watching,
+            4 => ActivityKind::Custom,
+            5 => ActivityKind::Competing,
+            other => ActivityKind::Unknown(other),
+        }
+    }
+}
+
+impl From<ActivityKind> for u8 {
+    fn from(k: ActivityKind) -> u8 {
+        match k {
+            ActivityKind::Playing => 0,
+            ActivityKind::Streaming => 1,
+            ActivityKind::Listening => 2,
+            ActivityKind::Watching => 3,
+            ActivityKind::Custom => 4,
+            ActivityKind::Competing => 5,
+            ActivityKind::Unknown(v) => v,
+        }
+    }
+}
+
+/// Emoji of a custom status: a unicode emoji in `name`, or a guild emoji with `id`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActivityEmoji {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub animated: bool,
+}
+
+impl ActivityEmoji {
+    /// Text form: the unicode emoji itself, or `:name:` for guild emoji.
+    pub fn text(&self) -> String {
+        if self.id.is_some() {
+            format!(":{}:", self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+/// Start / end of an activity in unix milliseconds.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActivityTimestamps {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<i64>,
+}
+
+/// Rich presence images (application asset keys or external URLs) and their hover texts.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActivityAssets {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub large_image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub large_text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub small_image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub small_text: Option<String>,
+}
+
+/// One presence activity (op 3 / PRESENCE_UPDATE `activities[]`, RPC SET_ACTIVITY).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Activity {
+    /// Empty in RPC SET_ACTIVITY; filled from the application name.
+    #[serde(default)]
+    pub name: String,
+    #[serde(rename = "type", default)]
+    pub kind: ActivityKind,
+    /// Custom status text, or the second line of a rich presence ("In a match").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    /// Stream URL (Streaming only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<ActivityEmoji>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub application_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamps: Option<ActivityTimestamps>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assets: Option<ActivityAssets>,
+}
+
+impl Activity {
+    /// One-line description: "Playing Minecraft", "Listening to Spotify", or the custom status.
+    pub fn summary(&self) -> String {
+        match self.kind {
+            ActivityKind::Custom => {
+                let emoji = self.emoji.as_ref().map(|e| e.text());
+                match (emoji, self.state.as_deref()) {
+                    (Some(e), Some(s)) => format!("{e} {s}"),
+                    (Some(e), None) => e,
+                    (None, Some(s)) => s.to_string(),
+                    (None, None) => String::new(),
+                }
+            }
+            ActivityKind::Streaming => format!("Streaming {}", self.details.as_deref().unwrap_or(&self.name)),
+            ActivityKind::Listening => format!("Listening to {}", self.name),
+            ActivityKind::Watching => format!("Watching {}", self.name),
+            ActivityKind::Competing => format!("Competing in {}", self.name),
+            ActivityKind::Playing | ActivityKind::Unknown(_) => format!("Playing {}", self.name),
+        }
+    }
+}
+
+/// Line shown under a user's name: the custom status if set, else the first other activity.
+pub fn activity_line(activities: &[Activity]) -> Option<String> {
+    activities
+        .iter()
+        .find(|a| a.kind == ActivityKind::Custom)
+        .or_else(|| activities.first())
+        .map(Activity::summary)
+        .filter(|s| !s.is_empty())
+}
+
+/// Parse activities one by one so a single unexpected entry does not drop the whole presence.
+pub fn deserialize_activities<'de, D>(deserializer: D) -> Result<Vec<Activity>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw: Option<Vec<serde_json::Value>> = Option::deserialize(deserializer)?;
+    Ok(raw
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|v| serde_json::from_value(v).ok())
+        .collect())
+}
+
+/// User-set custom status, sent as a `Custom` activity until `expires_at` (unix seconds).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CustomStatus {
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub emoji: Option<String>,
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+}
+
+impl CustomStatus {
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|t| t <= now)
+    }
+
+    pub fn to_activity(&self) -> Activity {
+        Activity {
+            name: "Custom Status".to_string(),
+            kind: ActivityKind::Custom,
+            state: Some(self.text.clone()).filter(|t| !t.is_empty()),
+            emoji: self.emoji.clone().filter(|e| !e.is_empty()).map(|name| ActivityEmoji {
+                name,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+/// Our own presence as sent in Identify and op 3.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnPresence {
+    pub status: PresenceStatus,
+    pub activities: Vec<Activity>,
+}
+
+/// What opens the microphone in a call.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VoiceInputMode {
+    /// Transmit while the input level is above `vad_threshold_db`.
+    #[default]
+    VoiceActivity,
+    /// Transmit while `ptt_key` is held.
+    PushToTalk,
+}
+
+/// Local playback adjustments for one user in calls; only affects what we hear.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct UserVoiceSettings {
+    /// Percent, 0–200.
+    #[serde(default = "default_user_volume")]
+    pub volume: u16,
+    #[serde(default)]
+    pub muted: bool,
+}
+
+impl Default for UserVoiceSettings {
+    fn default() -> Self {
+        Self {
+            volume: default_user_volume(),
+            muted: false,
+        }
+    }
+}
+
+impl UserVoiceSettings {
+    /// Linear gain applied while mixing.
+    pub fn gain(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.volume as f32 / 100.0
+        }
+    }
+}
+
+fn default_user_volume() -> u16 {
+    100
+}
+
 /// Application settings persisted to disk.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AppSettings {
@@ -89,6 +641,9 @@ pub struct AppSettings {
     /// Preferred presence (online / idle / dnd / invisible).
     #[serde(default = "default_presence")]
     pub presence: PresenceStatus,
+    /// Custom status (emoji, text, expiry) shown to others.
+    #[serde(default)]
+    pub custom_status: Option<CustomStatus>,
     /// When true, do not send typing events ("ghost typing").
     #[serde(default)]
     pub ghost_typing: bool,
@@ -101,6 +656,70 @@ pub struct AppSettings {
     /// Voice: preferred output device name (speaker). None = default.
     #[serde(default)]
     pub voice_output_device: Option<String>,
+    /// Voice: voice activity or push-to-talk.
+    #[serde(default)]
+    pub voice_input_mode: VoiceInputMode,
+    /// Voice activity: input level (dBFS) that opens the mic.
+    #[serde(default = "default_vad_threshold_db")]
+    pub vad_threshold_db: f32,
+    /// Voice activity: keep transmitting this long after the level drops (ms).
+    #[serde(default = "default_vad_hangover_ms")]
+    pub vad_hangover_ms: u32,
+    /// Push-to-talk key as an accelerator ("shift+KeyT", "Backquote"). None = unbound.
+    #[serde(default)]
+    pub ptt_key: Option<String>,
+    /// Push-to-talk: keep transmitting this long after the key is released (ms).
+    #[serde(default = "default_ptt_release_delay_ms")]
+    pub ptt_release_delay_ms: u32,
+    /// Voice: microphone gain in percent, 0–200, applied before processing and the gate.
+    #[serde(default = "default_voice_input_gain")]
+    pub voice_input_gain: u16,
+    /// Voice: per-user volume and local mute, by user ID. Users at the defaults are not stored.
+    #[serde(default)]
+    pub voice_users: HashMap<String, UserVoiceSettings>,
+    /// Voice: suppress steady background noise (fans, keyboard hiss) in the mic.
+    #[serde(default = "default_true")]
+    pub noise_suppression: bool,
+    /// Voice: remove the speaker's output picked up by the mic.
+    #[serde(default = "default_true")]
+    pub echo_cancellation: bool,
+    /// Voice: even out the mic level.
+    #[serde(default = "default_true")]
+    pub auto_gain_control: bool,
+    /// Show desktop notifications for DMs, mentions and keywords.
+    #[serde(default = "default_true")]
+    pub notifications_enabled: bool,
+    /// Extra words that notify like a mention (case-insensitive, whole words).
+    #[serde(default)]
+    pub notification_keywords: Vec<String>,
+}
+
+impl AppSettings {
+    /// Status plus the custom status activity, unless it has expired.
+    pub fn own_presence(&self) -> OwnPresence {
+        let now = chrono::Utc::now().timestamp();
+        OwnPresence {
+            status: self.presence,
+            activities: self
+                .custom_status
+                .iter()
+                .filter(|c| !c.is_expired(now))
+                .map(CustomStatus::to_activity)
+                .collect(),
+        }
+    }
+
+    pub fn voice_user(&self, user_id: &str) -> UserVoiceSettings {
+        self.voice_users.get(user_id).cloned().unwrap_or_default()
+    }
+
+    pub fn set_voice_user(&mut self, user_id: &str, value: UserVoiceSettings) {
+        if value == UserVoiceSettings::default() {
+            self.voice_users.remove(user_id);
+        } else {
+            self.voice_users.insert(user_id.to_string(), value);
+        }
+    }
 }
 
 fn default_true() -> bool {
@@ -111,6 +730,22 @@ fn default_presence() -> PresenceStatus {
     PresenceStatus::Online
 }
 
+fn default_vad_threshold_db() -> f32 {
+    -50.0
+}
+
+fn default_vad_hangover_ms() -> u32 {
+    300
+}
+
+fn default_ptt_release_delay_ms() -> u32 {
+    200
+}
+
+fn default_voice_input_gain() -> u16 {
+    100
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
@@ -118,10 +753,23 @@ impl Default for AppSettings {
             welcome_seen: false,
             animations_enabled: true,
             presence: PresenceStatus::Online,
+            custom_status: None,
             ghost_typing: false,
             show_private_channels: false,
             voice_input_device: None,
             voice_output_device: None,
+            voice_input_mode: VoiceInputMode::VoiceActivity,
+            vad_threshold_db: default_vad_threshold_db(),
+            vad_hangover_ms: default_vad_hangover_ms(),
+            ptt_key: None,
+            ptt_release_delay_ms: default_ptt_release_delay_ms(),
+            voice_input_gain: default_voice_input_gain(),
+            voice_users: HashMap::new(),
+            noise_suppression: true,
+            echo_cancellation: true,
+            auto_gain_control: true,
+            notifications_enabled: true,
+            notification_keywords: Vec::new(),
         }
     }
 }
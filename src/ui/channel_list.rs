@@ -1,8 +1,10 @@
 use dioxus::prelude::*;
 
-use crate::http::{DiscordUser, DmChannel, Relationship};
-use crate::state::PresenceStatus;
-use crate::ui::ChannelContextMenu;
+use crate::http::{DiscordUser, DmChannel, Relationship, RelationshipType};
+use crate::state::{
+    activity_line, Activity, NotificationSettings, NotificationSettingsChange, PresenceStatus, ReadState,
+};
+use crate::ui::{ChannelContextMenu, NewGroupDmPicker};
 
 fn status_dot_color(s: PresenceStatus) -> &'static str {
     match s {
@@ -30,17 +32,39 @@ fn avatar_url(u: &DiscordUser) -> Option<String> {
     })
 }
 
+/// One friend in the sidebar list.
+#[derive(Clone)]
+struct FriendRow {
+    user_id: String,
+    name: String,
+    avatar_url: Option<String>,
+    status: PresenceStatus,
+    /// Custom status or current game.
+    activity: Option<String>,
+}
+
+/// One DM or group DM in the sidebar list.
+#[derive(Clone)]
+struct DmRow {
+    channel_id: String,
+    label: String,
+    avatar_url: Option<String>,
+    fallback: char,
+    status: PresenceStatus,
+    activity: Option<String>,
+}
+
 fn dm_channel_label(ch: &DmChannel) -> String {
-    if ch.recipients.is_empty() {
-        ch.name.as_deref().unwrap_or("DM").to_string()
-    } else {
-        ch.recipients.iter().map(display_name).collect::<Vec<_>>().join(", ")
+    match ch.name.as_deref().filter(|n| !n.is_empty()) {
+        Some(name) => name.to_string(),
+        None if ch.recipients.is_empty() => "DM".to_string(),
+        None => ch.recipients.iter().map(display_name).collect::<Vec<_>>().join(", "),
     }
 }
 
 /// Avatar with a small status dot (online=green, idle=yellow, dnd=red, invisible=gray).
 #[component]
-fn AvatarWithStatus(
+pub(super) fn AvatarWithStatus(
     avatar_url: Option<String>,
     fallback: char,
     status: PresenceStatus,
@@ -101,56 +125,78 @@ pub fn ChannelList(
     friends: Signal<Vec<Relationship>>,
     dm_channels: Signal<Vec<DmChannel>>,
     selected_channel_id: Signal<Option<String>>,
-    unread_counts: Signal<std::collections::HashMap<String, u32>>,
+    read_states: Signal<std::collections::HashMap<String, ReadState>>,
     presence_map: Signal<std::collections::HashMap<String, PresenceStatus>>,
+    activity_map: Signal<std::collections::HashMap<String, Vec<Activity>>>,
     on_select_channel: EventHandler<Option<String>>,
     on_open_friend: EventHandler<String>,
+    on_create_group_dm: EventHandler<Vec<String>>,
     on_mark_read: EventHandler<String>,
+    notification_settings: Signal<NotificationSettings>,
+    on_update_notifications: EventHandler<NotificationSettingsChange>,
 ) -> Element {
     let mut channel_context = use_signal(|| None::<(f64, f64, String)>);
+    let mut group_picker = use_signal(|| None::<(f64, f64)>);
     let friends_list = friends();
     let dm_list = dm_channels();
     let selected = selected_channel_id();
-    let unread = unread_counts();
+    let reads = read_states();
+    let notif = notification_settings();
     let presence = presence_map();
-    let friends_filtered: Vec<(String, String, String, Option<String>, PresenceStatus)> =
+    let activities = activity_map();
+    let activity_of = |uid: &str| activities.get(uid).and_then(|a| activity_line(a));
+    let friends_filtered: Vec<FriendRow> =
         friends_list
             .iter()
-            .filter(|r| r.r#type == 1)
+            .filter(|r| r.r#type == RelationshipType::Friend)
             .map(|r| {
                 let uid = r.user.id.clone();
                 let status = presence.get(&uid).copied().unwrap_or(PresenceStatus::Invisible);
-                (
-                    uid.clone(),
-                    uid,
-                    display_name(&r.user),
-                    avatar_url(&r.user),
+                FriendRow {
+                    activity: activity_of(&uid),
+                    user_id: uid,
+                    name: display_name(&r.user),
+                    avatar_url: avatar_url(&r.user),
                     status,
-                )
+                }
             })
             .collect();
+    let pending_requests = friends_list
+        .iter()
+        .filter(|r| r.r#type == RelationshipType::Incoming)
+        .count();
     let mut seen_dm_ids = std::collections::HashSet::new();
-    let dm_owned: Vec<(String, String, String, Option<String>, char, PresenceStatus)> = dm_list
+    let dm_owned: Vec<DmRow> = dm_list
         .iter()
         .filter(|ch| seen_dm_ids.insert(ch.id.clone()))
         .map(|ch| {
             let label = dm_channel_label(ch);
-            let (avatar_opt, fallback, status) = ch.recipients.first().map(|u| {
-                let sid = presence.get(&u.id).copied().unwrap_or(PresenceStatus::Invisible);
-                (
-                    avatar_url(u),
-                    display_name(u).chars().next().unwrap_or('?'),
-                    sid,
-                )
-            }).unwrap_or((None, '?', PresenceStatus::Invisible));
-            (
-                ch.id.clone(),
-                ch.id.clone(),
+            let (avatar_opt, fallback, status) = if ch.is_group() {
+                // Group DMs have no single presence; show the group icon with an offline dot.
+                (ch.icon_url(), label.chars().next().unwrap_or('?'), PresenceStatus::Invisible)
+            } else {
+                ch.recipients.first().map(|u| {
+                    let sid = presence.get(&u.id).copied().unwrap_or(PresenceStatus::Invisible);
+                    (
+                        avatar_url(u),
+                        display_name(u).chars().next().unwrap_or('?'),
+                        sid,
+                    )
+                }).unwrap_or((None, '?', PresenceStatus::Invisible))
+            };
+            let activity = if ch.is_group() {
+                None
+            } else {
+                ch.recipients.first().and_then(|u| activity_of(&u.id))
+            };
+            DmRow {
+                channel_id: ch.id.clone(),
                 label,
-                avatar_opt,
+                avatar_url: avatar_opt,
                 fallback,
                 status,
-            )
+                activity,
+            }
         })
         .collect();
 
@@ -159,12 +205,24 @@ pub fn ChannelList(
     let key_empty = "empty-state";
 
     rsx! {
+        if let Some((x, y)) = group_picker() {
+            NewGroupDmPicker {
+                x,
+                y,
+                friends,
+                on_create: on_create_group_dm,
+                on_close: move |_| group_picker.set(None),
+            }
+        }
         if let Some((x, y, ref ch_id)) = channel_context() {
             ChannelContextMenu {
                 x,
                 y,
                 channel_id: ch_id.clone(),
+                guild_id: None,
+                notification_settings,
                 on_mark_read,
+                on_update_notifications,
                 on_close: move |_| channel_context.set(None),
             }
         }
@@ -186,12 +244,35 @@ pub fn ChannelList(
                     flex: 1 1 0; min-height: 0; overflow-y: auto; overflow-x: hidden;
                     padding: 0.5rem; list-style: none; margin: 0;
                 ",
+            li {
+                key: "friends-page",
+                class: "anim-channel-item",
+                style: if selected.is_none() {
+                    "display: flex; align-items: center; gap: 0.5rem; padding: 0.5rem; border-radius: 6px; color: #e5e7eb; font-size: 0.875rem; cursor: pointer; background: rgba(0,255,245,0.1); margin-bottom: 0.25rem;"
+                } else {
+                    "display: flex; align-items: center; gap: 0.5rem; padding: 0.5rem; border-radius: 6px; color: #9ca3af; font-size: 0.875rem; cursor: pointer; background: transparent; margin-bottom: 0.25rem;"
+                },
+                onclick: move |_| on_select_channel.call(None),
+                span { "👥" }
+                span { style: "flex: 1;", "Friends" }
+                if pending_requests > 0 {
+                    span {
+                        style: "
+                            background: #f43f5e; color: #fff;
+                            font-size: 0.65rem; font-weight: 700;
+                            padding: 0.15em 0.4em; border-radius: 10px;
+                            min-width: 1.25em; text-align: center;
+                        ",
+                        "{pending_requests}"
+                    }
+                }
+            }
             li {
                 key: "{key_section_friends}",
                 style: "padding: 0.25rem 0.5rem; font-size: 0.65rem; text-transform: uppercase; letter-spacing: 0.05em; color: #6b7280;",
                 "Friends"
             }
-            for (uid, uid_click, name, avatar_opt, friend_status) in friends_filtered.clone().into_iter() {
+            for FriendRow { user_id: uid, name, avatar_url: avatar_opt, status: friend_status, activity } in friends_filtered.clone().into_iter() {
                 li {
                     key: "friend-{uid}",
                     class: "anim-channel-item",
@@ -200,34 +281,64 @@ pub fn ChannelList(
                         padding: 0.375rem 0.5rem; border-radius: 6px;
                         color: #e5e7eb; font-size: 0.875rem; cursor: pointer;
                     ",
-                    onclick: move |_| on_open_friend.call(uid_click.clone()),
+                    onclick: {
+                        let uid = uid.clone();
+                        move |_| on_open_friend.call(uid.clone())
+                    },
                     AvatarWithStatus {
                         avatar_url: avatar_opt.clone(),
                         fallback: name.chars().next().unwrap_or('?'),
                         status: friend_status,
                     }
-                    span {
-                        style: "flex: 1; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
-                        title: "{name}",
-                        "{name}"
+                    div {
+                        style: "flex: 1; min-width: 0; display: flex; flex-direction: column;",
+                        span {
+                            style: "overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
+                            title: "{name}",
+                            "{name}"
+                        }
+                        if let Some(ref activity) = activity {
+                            span {
+                                style: "font-size: 0.7rem; color: #6b7280; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
+                                title: "{activity}",
+                                "{activity}"
+                            }
+                        }
                     }
                 }
             }
             li {
                 key: "{key_section_dms}",
-                style: "padding: 0.25rem 0.5rem; font-size: 0.65rem; text-transform: uppercase; letter-spacing: 0.05em; color: #6b7280;",
-                "Direct Messages"
+                style: "display: flex; align-items: center; padding: 0.25rem 0.5rem; font-size: 0.65rem; text-transform: uppercase; letter-spacing: 0.05em; color: #6b7280;",
+                span { style: "flex: 1;", "Direct Messages" }
+                button {
+                    class: "anim-btn",
+                    style: "background: transparent; border: none; color: #9ca3af; cursor: pointer; font-size: 0.9rem; padding: 0 0.2rem;",
+                    title: "Create DM",
+                    onclick: move |evt| {
+                        let coords = evt.client_coordinates();
+                        group_picker.set(Some((coords.x, coords.y)));
+                    },
+                    "+"
+                }
             }
-            for (ch_id, ch_id_click, label, avatar_opt, fallback, dm_status) in dm_owned.clone().into_iter() {
+            for DmRow { channel_id: ch_id, label, avatar_url: avatar_opt, fallback, status: dm_status, activity } in dm_owned.clone().into_iter() {
                 li {
                     key: "dm-{ch_id}",
                     class: "anim-channel-item",
                     style: if selected.as_ref() == Some(&ch_id) {
                         "display: flex; align-items: center; gap: 0.5rem; padding: 0.375rem 0.5rem; border-radius: 6px; color: #9ca3af; font-size: 0.875rem; cursor: pointer; background: rgba(0,255,245,0.1);"
+                    } else if notif.is_muted(None, &ch_id) {
+                        "display: flex; align-items: center; gap: 0.5rem; padding: 0.375rem 0.5rem; border-radius: 6px; color: #9ca3af; font-size: 0.875rem; cursor: pointer; background: transparent; opacity: 0.5;"
+                    } else if reads.get(&ch_id).map(|r| r.is_unread()).unwrap_or(false) {
+                        "display: flex; align-items: center; gap: 0.5rem; padding: 0.375rem 0.5rem; border-radius: 6px; color: #f3f4f6; font-size: 0.875rem; font-weight: 600; cursor: pointer; background: transparent;"
                     } else {
                         "display: flex; align-items: center; gap: 0.5rem; padding: 0.375rem 0.5rem; border-radius: 6px; color: #9ca3af; font-size: 0.875rem; cursor: pointer; background: transparent;"
                     },
-                    onclick: move |_| on_select_channel.call(Some(ch_id_click.clone())),
+                    onclick: {
+                        let ch_id = ch_id.clone();
+                        move |_| on_select_channel.call(Some(ch_id.clone()))
+                    },
                     oncontextmenu: move |evt| {
                         evt.prevent_default();
                         let coords = evt.client_coordinates();
@@ -238,12 +349,22 @@ pub fn ChannelList(
                         fallback,
                         status: dm_status,
                     }
-                    span {
-                        style: "flex: 1; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
-                        title: "{label}",
-                        "{label}"
+                    div {
+                        style: "flex: 1; min-width: 0; display: flex; flex-direction: column;",
+                        span {
+                            style: "overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
+                            title: "{label}",
+                            "{label}"
+                        }
+                        if let Some(ref activity) = activity {
+                            span {
+                                style: "font-size: 0.7rem; font-weight: 400; color: #6b7280; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
+                                title: "{activity}",
+                                "{activity}"
+                            }
+                        }
                     }
-                    if unread.get(&ch_id).copied().unwrap_or(0) > 0 {
+                    if reads.get(&ch_id).map(|r| r.mention_count).unwrap_or(0) > 0 {
                         span {
                             style: "
                                 background: #00fff5; color: #0a0a0f;
@@ -251,7 +372,7 @@ pub fn ChannelList(
                                 padding: 0.15em 0.4em; border-radius: 10px;
                                 min-width: 1.25em; text-align: center;
                             ",
-                            "{unread.get(&ch_id).copied().unwrap_or(0).min(99)}"
+                            "{reads.get(&ch_id).map(|r| r.mention_count).unwrap_or(0).min(99)}"
                         }
                     }
                 }
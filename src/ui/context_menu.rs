@@ -1,20 +1,161 @@
 use dioxus::prelude::*;
 
+use crate::state::{
+    save_settings, AppSettings, NotificationChange, NotificationLevel, NotificationSettings, NotificationSettingsChange,
+};
+
 #[derive(Clone, PartialEq)]
 pub struct ContextMenuItem {
     pub label: String,
     pub on_click: Option<EventHandler<()>>,
 }
 
-/// Context menu for a channel: Mark as read.
+/// Mute durations offered in the menus: (label, minutes; None = until turned off).
+const MUTE_DURATIONS: [(&str, Option<u32>); 5] = [
+    ("15m", Some(15)),
+    ("1h", Some(60)),
+    ("8h", Some(8 * 60)),
+    ("24h", Some(24 * 60)),
+    ("Forever", None),
+];
+
+const MENU_ITEM_STYLE: &str = "
+    display: block; width: 100%; padding: 0.5rem 0.75rem;
+    text-align: left; font-size: 0.875rem;
+    background: transparent; border: none;
+    color: #e5e7eb; cursor: pointer;
+    border-radius: 4px;
+";
+
+const MENU_HEADER_STYLE: &str = "
+    padding: 0.375rem 0.75rem 0.125rem;
+    font-size: 0.7rem; font-weight: 600; color: #6b7280;
+    text-transform: uppercase; letter-spacing: 0.05em;
+";
+
+/// Mute, notification level and @everyone controls shared by the guild and channel menus.
+/// `channel_id` None edits the guild itself; `guild_id` None edits a DM (mute only).
+#[component]
+fn NotificationMenuSection(
+    guild_id: Option<String>,
+    channel_id: Option<String>,
+    notification_settings: Signal<NotificationSettings>,
+    on_update_notifications: EventHandler<NotificationSettingsChange>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let settings = notification_settings();
+    let guild = settings.for_guild(guild_id.as_deref());
+    let (muted, level) = match (&channel_id, guild) {
+        (Some(cid), Some(g)) => g
+            .channel_override(cid)
+            .map(|o| (o.is_muted(), o.message_notifications))
+            .unwrap_or((false, NotificationLevel::Inherit)),
+        (None, Some(g)) => (g.is_muted(), g.message_notifications),
+        (_, None) => (false, NotificationLevel::Inherit),
+    };
+    let suppress_everyone = guild.map(|g| g.suppress_everyone).unwrap_or(false);
+    let show_levels = guild_id.is_some();
+    let show_suppress = guild_id.is_some() && channel_id.is_none();
+    let default_label = if channel_id.is_some() { "Use server default" } else { "Server default" };
+    let levels = [
+        (default_label, NotificationLevel::Inherit),
+        ("All messages", NotificationLevel::AllMessages),
+        ("Only @mentions", NotificationLevel::OnlyMentions),
+        ("Nothing", NotificationLevel::Nothing),
+    ];
+    let send = move |change: NotificationChange| {
+        on_update_notifications.call(NotificationSettingsChange {
+            guild_id: guild_id.clone(),
+            channel_id: channel_id.clone(),
+            change,
+        });
+        on_close.call(());
+    };
+
+    rsx! {
+        div { style: "height: 1px; margin: 0.25rem 0; background: rgba(255,255,255,0.08);" }
+        if muted {
+            button {
+                class: "anim-btn",
+                style: "{MENU_ITEM_STYLE}",
+                onclick: {
+                    let send = send.clone();
+                    move |_| send(NotificationChange::Unmute)
+                },
+                "Unmute"
+            }
+        } else {
+            div { style: "{MENU_HEADER_STYLE}", "Mute" }
+            div {
+                style: "display: flex; flex-wrap: wrap; gap: 0.25rem; padding: 0.25rem 0.5rem;",
+                for (label, minutes) in MUTE_DURATIONS {
+                    button {
+                        class: "anim-btn",
+                        style: "
+                            padding: 0.2rem 0.5rem; font-size: 0.75rem;
+                            background: rgba(255,255,255,0.06);
+                            border: 1px solid rgba(255,255,255,0.12);
+                            border-radius: 999px; color: #e5e7eb; cursor: pointer;
+                        ",
+                        onclick: {
+                            let send = send.clone();
+                            move |_| send(NotificationChange::Mute(minutes))
+                        },
+                        "{label}"
+                    }
+                }
+            }
+        }
+        if show_levels {
+            div { style: "{MENU_HEADER_STYLE}", "Notifications" }
+            for (label, value) in levels {
+                button {
+                    class: "anim-btn",
+                    style: "{MENU_ITEM_STYLE}",
+                    onclick: {
+                        let send = send.clone();
+                        move |_| send(NotificationChange::Level(value))
+                    },
+                    span {
+                        style: "display: inline-block; width: 1.1rem; color: #00fff5;",
+                        if level == value { "●" } else { "○" }
+                    }
+                    "{label}"
+                }
+            }
+        }
+        if show_suppress {
+            button {
+                class: "anim-btn",
+                style: "{MENU_ITEM_STYLE}",
+                onclick: {
+                    let send = send.clone();
+                    move |_| send(NotificationChange::SuppressEveryone(!suppress_everyone))
+                },
+                span {
+                    style: "display: inline-block; width: 1.1rem; color: #00fff5;",
+                    if suppress_everyone { "☑" } else { "☐" }
+                }
+                "Suppress @everyone and @here"
+            }
+        }
+    }
+}
+
+/// Context menu for a channel: Mark as read, mute and notification overrides.
 #[component]
 pub fn ChannelContextMenu(
     x: f64,
     y: f64,
     channel_id: String,
+    /// None for DMs.
+    guild_id: Option<String>,
+    notification_settings: Signal<NotificationSettings>,
     on_mark_read: EventHandler<String>,
+    on_update_notifications: EventHandler<NotificationSettingsChange>,
     on_close: EventHandler<()>,
 ) -> Element {
+    let section_channel_id = channel_id.clone();
     rsx! {
         div {
             style: "position: fixed; inset: 0; z-index: 1499;",
@@ -33,36 +174,93 @@ pub fn ChannelContextMenu(
                 border: 1px solid rgba(255,255,255,0.12);
                 border-radius: 8px;
                 padding: 0.25rem;
-                min-width: 10rem;
+                min-width: 12rem;
                 box-shadow: 0 8px 24px rgba(0,0,0,0.4);
                 z-index: 1500;
             ",
             oncontextmenu: move |evt| evt.prevent_default(),
             button {
                 class: "anim-btn",
-                style: "
-                    display: block; width: 100%; padding: 0.5rem 0.75rem;
-                    text-align: left; font-size: 0.875rem;
-                    background: transparent; border: none;
-                    color: #e5e7eb; cursor: pointer;
-                    border-radius: 4px;
-                ",
+                style: "{MENU_ITEM_STYLE}",
                 onclick: move |_| {
                     on_mark_read.call(channel_id.clone());
                     on_close.call(());
                 },
                 "Mark as read"
             }
+            NotificationMenuSection {
+                guild_id,
+                channel_id: Some(section_channel_id),
+                notification_settings,
+                on_update_notifications,
+                on_close,
+            }
         }
     }
 }
 
-/// Context menu for a message: Copy text, etc.
+/// Context menu for a guild icon: Mark as read, mute, notification level, @everyone.
+#[component]
+pub fn GuildContextMenu(
+    x: f64,
+    y: f64,
+    guild_id: String,
+    notification_settings: Signal<NotificationSettings>,
+    on_mark_read: EventHandler<String>,
+    on_update_notifications: EventHandler<NotificationSettingsChange>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let section_guild_id = guild_id.clone();
+    rsx! {
+        div {
+            style: "position: fixed; inset: 0; z-index: 1499;",
+            onclick: move |_| on_close.call(()),
+            oncontextmenu: move |evt| {
+                evt.prevent_default();
+                evt.stop_propagation();
+                on_close.call(());
+            },
+        }
+        div {
+            class: "anim-modal-content",
+            style: "
+                position: fixed; left: {x}px; top: {y}px;
+                background: #12121a;
+                border: 1px solid rgba(255,255,255,0.12);
+                border-radius: 8px;
+                padding: 0.25rem;
+                min-width: 12rem;
+                box-shadow: 0 8px 24px rgba(0,0,0,0.4);
+                z-index: 1500;
+            ",
+            oncontextmenu: move |evt| evt.prevent_default(),
+            button {
+                class: "anim-btn",
+                style: "{MENU_ITEM_STYLE}",
+                onclick: move |_| {
+                    on_mark_read.call(guild_id.clone());
+                    on_close.call(());
+                },
+                "Mark server as read"
+            }
+            NotificationMenuSection {
+                guild_id: Some(section_guild_id),
+                channel_id: None,
+                notification_settings,
+                on_update_notifications,
+                on_close,
+            }
+        }
+    }
+}
+
+/// Context menu for a message: Copy text, etc. `on_create_thread` is None where threads can't be started.
 #[component]
 pub fn MessageContextMenu(
     x: f64,
     y: f64,
     content: String,
+    on_create_thread: Option<EventHandler<()>>,
     on_close: EventHandler<()>,
 ) -> Element {
     rsx! {
@@ -105,6 +303,102 @@ pub fn MessageContextMenu(
                 },
                 "Copy text"
             }
+            if let Some(handler) = on_create_thread {
+                button {
+                    class: "anim-btn",
+                    style: "{MENU_ITEM_STYLE}",
+                    onclick: move |_| {
+                        handler.call(());
+                        on_close.call(());
+                    },
+                    "Create thread"
+                }
+            }
+        }
+    }
+}
+
+/// Change and save one user's voice settings; None leaves a value as it is.
+fn update_voice_user(mut settings: Signal<AppSettings>, user_id: &str, volume: Option<u16>, muted: Option<bool>) {
+    let mut s = settings();
+    let mut value = s.voice_user(user_id);
+    if let Some(v) = volume {
+        value.volume = v.min(200);
+    }
+    if let Some(m) = muted {
+        value.muted = m;
+    }
+    s.set_voice_user(user_id, value);
+    settings.set(s.clone());
+    let _ = save_settings(&s);
+}
+
+/// Context menu for a voice participant: local volume (0–200%) and mute, saved per user.
+#[component]
+pub fn VoiceUserContextMenu(
+    x: f64,
+    y: f64,
+    user_id: String,
+    name: String,
+    settings: Signal<AppSettings>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let current = settings().voice_user(&user_id);
+    let (slider_id, mute_id, reset_id) = (user_id.clone(), user_id.clone(), user_id.clone());
+    rsx! {
+        div {
+            style: "position: fixed; inset: 0; z-index: 1499;",
+            onclick: move |_| on_close.call(()),
+            oncontextmenu: move |evt| {
+                evt.prevent_default();
+                evt.stop_propagation();
+                on_close.call(());
+            },
+        }
+        div {
+            class: "anim-modal-content",
+            style: "
+                position: fixed; left: {x}px; top: {y}px;
+                background: #12121a;
+                border: 1px solid rgba(255,255,255,0.12);
+                border-radius: 8px;
+                padding: 0.25rem;
+                min-width: 12rem;
+                box-shadow: 0 8px 24px rgba(0,0,0,0.4);
+                z-index: 1500;
+            ",
+            oncontextmenu: move |evt| evt.prevent_default(),
+            div { style: "{MENU_HEADER_STYLE}", "{name}" }
+            div {
+                style: "padding: 0.375rem 0.75rem; display: flex; flex-direction: column; gap: 0.25rem;",
+                span { style: "font-size: 0.8rem; color: #9ca3af;", "User volume: {current.volume}%" }
+                input {
+                    r#type: "range",
+                    min: "0",
+                    max: "200",
+                    step: "5",
+                    value: "{current.volume}",
+                    oninput: move |evt| {
+                        if let Ok(v) = evt.value().parse::<u16>() {
+                            update_voice_user(settings, &slider_id, Some(v), None);
+                        }
+                    },
+                }
+            }
+            button {
+                class: "anim-btn",
+                style: "{MENU_ITEM_STYLE}",
+                onclick: move |_| update_voice_user(settings, &mute_id, None, Some(!current.muted)),
+                if current.muted { "Unmute" } else { "Mute" }
+            }
+            if current.volume != 100 {
+                button {
+                    class: "anim-btn",
+                    style: "{MENU_ITEM_STYLE}",
+                    onclick: move |_| update_voice_user(settings, &reset_id, Some(100), None),
+                    "Reset volume"
+                }
+            }
         }
     }
 }
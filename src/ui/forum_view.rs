@@ -0,0 +1,405 @@
+use chrono::Utc;
+use dioxus::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+use crate::http::{ForumTag, GuildChannel};
+use crate::state::{snowflake_cmp, snowflake_time, Message};
+
+/// The API accepts at most five tags per post.
+const MAX_APPLIED_TAGS: usize = 5;
+/// Starter messages fetched per batch; the rest load as the forum grows.
+const PREVIEW_BATCH: usize = 25;
+const PREVIEW_MAX_CHARS: usize = 180;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ForumSort {
+    LatestActivity,
+    CreationDate,
+}
+
+/// Newest message in a post, or the post itself when it has no replies.
+fn last_activity_id(post: &GuildChannel) -> &str {
+    post.last_message_id
+        .as_deref()
+        .filter(|m| snowflake_cmp(m, &post.id).is_gt())
+        .unwrap_or(&post.id)
+}
+
+/// "just now", "5m ago", "3h ago", "2d ago", or a date for anything older than a week.
+fn format_relative(id: &str) -> String {
+    let Some(t) = snowflake_time(id) else {
+        return String::new();
+    };
+    let secs = (Utc::now() - t).num_seconds().max(0);
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86_399 => format!("{}h ago", secs / 3600),
+        86_400..=604_799 => format!("{}d ago", secs / 86_400),
+        _ => t.format("%d %b %Y").to_string(),
+    }
+}
+
+fn tag_label(tag: &ForumTag) -> String {
+    match tag.emoji_name.as_deref() {
+        Some(e) if !e.is_empty() => format!("{} {}", e, tag.name),
+        _ => tag.name.clone(),
+    }
+}
+
+fn preview_text(content: &str) -> String {
+    let flat = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut text: String = flat.chars().take(PREVIEW_MAX_CHARS).collect();
+    if flat.chars().count() > PREVIEW_MAX_CHARS {
+        text.push('…');
+    }
+    text
+}
+
+#[component]
+fn TagChip(label: String, active: bool, onclick: EventHandler<()>) -> Element {
+    let (border, color, background) = if active {
+        ("rgba(0,255,245,0.5)", "#00fff5", "rgba(0,255,245,0.12)")
+    } else {
+        ("rgba(255,255,255,0.12)", "#9ca3af", "transparent")
+    };
+    rsx! {
+        button {
+            class: "anim-btn",
+            style: "
+                padding: 0.15rem 0.5rem; font-size: 0.75rem; border-radius: 999px; cursor: pointer;
+                border: 1px solid {border}; color: {color}; background: {background};
+                white-space: nowrap;
+            ",
+            onclick: move |evt| {
+                evt.stop_propagation();
+                onclick.call(());
+            },
+            "{label}"
+        }
+    }
+}
+
+#[component]
+fn PostCard(
+    post: GuildChannel,
+    tags: Vec<String>,
+    preview: Option<Message>,
+    on_open_thread: EventHandler<String>,
+) -> Element {
+    let open_id = post.id.clone();
+    let replies = post.message_count.unwrap_or(0);
+    let activity = format_relative(last_activity_id(&post));
+    let archived = post.is_archived();
+    let (author, body) = match preview {
+        Some(ref m) => (
+            m.author_username.clone().unwrap_or_default(),
+            preview_text(&m.content),
+        ),
+        None => (String::new(), String::new()),
+    };
+    rsx! {
+        div {
+            class: "anim-channel-item",
+            style: "
+                display: flex; flex-direction: column; gap: 0.4rem;
+                padding: 0.75rem 1rem; border-radius: 10px; cursor: pointer;
+                background: rgba(255,255,255,0.04); border: 1px solid rgba(255,255,255,0.08);
+            ",
+            onclick: move |_| on_open_thread.call(open_id.clone()),
+            div {
+                style: "display: flex; align-items: center; gap: 0.5rem;",
+                span {
+                    style: "flex: 1; min-width: 0; font-weight: 600; color: #e5e7eb; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
+                    title: "{post.name}",
+                    "{post.name}"
+                }
+                if archived {
+                    span { style: "font-size: 0.7rem; color: #6b7280;", "Archived" }
+                }
+            }
+            if !tags.is_empty() {
+                div {
+                    style: "display: flex; flex-wrap: wrap; gap: 0.25rem;",
+                    for t in tags.iter() {
+                        span {
+                            style: "
+                                padding: 0.05rem 0.45rem; font-size: 0.7rem; border-radius: 999px;
+                                background: rgba(255,255,255,0.08); color: #d1d5db;
+                            ",
+                            "{t}"
+                        }
+                    }
+                }
+            }
+            if !body.is_empty() {
+                div {
+                    style: "font-size: 0.85rem; color: #9ca3af; line-height: 1.4;",
+                    if !author.is_empty() {
+                        span { style: "color: #00fff5;", "{author}: " }
+                    }
+                    "{body}"
+                }
+            }
+            div {
+                style: "display: flex; gap: 0.75rem; font-size: 0.75rem; color: #6b7280;",
+                span { "💬 {replies}" }
+                if !activity.is_empty() {
+                    span { "{activity}" }
+                }
+            }
+        }
+    }
+}
+
+/// Forum and media channels: posts as cards with tag filter, sorting and a "New post" form.
+/// Shown in place of `MessageList`; opening a post selects its thread.
+#[component]
+pub fn ForumView(
+    forum: GuildChannel,
+    guild_channels: Signal<Vec<GuildChannel>>,
+    archived_threads: Signal<HashMap<String, Vec<GuildChannel>>>,
+    /// Thread ID -> starter message; None while loading or when it was deleted.
+    forum_previews: Signal<HashMap<String, Option<Message>>>,
+    can_post: bool,
+    on_open_thread: EventHandler<String>,
+    on_load_archived: EventHandler<String>,
+    on_load_previews: EventHandler<Vec<String>>,
+    on_create_post: EventHandler<(String, String, String, Vec<String>)>,
+) -> Element {
+    let default_sort = if forum.default_sort_order == Some(1) {
+        ForumSort::CreationDate
+    } else {
+        ForumSort::LatestActivity
+    };
+    let mut sort = use_signal(move || default_sort);
+    let mut tag_filter = use_signal(HashSet::<String>::new);
+    let mut composing = use_signal(|| false);
+    let mut title = use_signal(String::new);
+    let mut body = use_signal(String::new);
+    let mut post_tags = use_signal(Vec::<String>::new);
+
+    // Archived posts are listed too; the layout keys this view by forum, so this runs once per forum.
+    let forum_id = forum.id.clone();
+    use_hook(move || on_load_archived.call(forum_id));
+
+    let mut posts: Vec<GuildChannel> = guild_channels()
+        .into_iter()
+        .filter(|c| c.r#type.is_thread() && c.parent_id.as_deref() == Some(forum.id.as_str()))
+        .collect();
+    for t in archived_threads().get(&forum.id).cloned().unwrap_or_default() {
+        if !posts.iter().any(|p| p.id == t.id) {
+            posts.push(t);
+        }
+    }
+
+    let forum_id = forum.id.clone();
+    use_effect(move || {
+        let previews = forum_previews();
+        let mut ids: Vec<String> = guild_channels()
+            .into_iter()
+            .filter(|c| c.parent_id.as_deref() == Some(forum_id.as_str()))
+            .chain(archived_threads().get(&forum_id).cloned().unwrap_or_default())
+            .map(|p| p.id)
+            .filter(|id| !previews.contains_key(id))
+            .collect();
+        ids.sort_by(|a, b| snowflake_cmp(b, a));
+        ids.dedup();
+        ids.truncate(PREVIEW_BATCH);
+        if !ids.is_empty() {
+            on_load_previews.call(ids);
+        }
+    });
+
+    let filter = tag_filter();
+    if !filter.is_empty() {
+        posts.retain(|p| p.applied_tags.iter().any(|t| filter.contains(t)));
+    }
+    match sort() {
+        ForumSort::LatestActivity => posts.sort_by(|a, b| snowflake_cmp(last_activity_id(b), last_activity_id(a))),
+        ForumSort::CreationDate => posts.sort_by(|a, b| snowflake_cmp(&b.id, &a.id)),
+    }
+    let previews = forum_previews();
+    let tag_names: HashMap<String, String> = forum
+        .available_tags
+        .iter()
+        .map(|t| (t.id.clone(), tag_label(t)))
+        .collect();
+    let cards: Vec<(GuildChannel, Vec<String>, Option<Message>)> = posts
+        .into_iter()
+        .map(|p| {
+            let tags = p.applied_tags.iter().filter_map(|id| tag_names.get(id).cloned()).collect();
+            let preview = previews.get(&p.id).cloned().flatten();
+            (p, tags, preview)
+        })
+        .collect();
+
+    let topic = forum.topic.clone().filter(|t| !t.trim().is_empty());
+    let icon = forum.r#type.icon();
+    let can_submit = !title().trim().is_empty() && !body().trim().is_empty();
+    let submit_id = forum.id.clone();
+
+    rsx! {
+        div {
+            style: "flex: 1 1 0; display: flex; flex-direction: column; min-width: 0; min-height: 0; overflow: hidden;",
+            header {
+                style: "flex-shrink: 0; padding: 0.75rem 1rem; border-bottom: 1px solid rgba(255,255,255,0.1); display: flex; align-items: center; gap: 0.5rem;",
+                span { style: "color: #00fff5;", "{icon}" }
+                span { style: "font-weight: 500; flex-shrink: 0;", "{forum.name}" }
+                if let Some(ref topic) = topic {
+                    span { style: "width: 1px; height: 1.25rem; background: rgba(255,255,255,0.15); flex-shrink: 0;" }
+                    span {
+                        style: "
+                            font-size: 0.85rem; color: #9ca3af; min-width: 0;
+                            overflow: hidden; text-overflow: ellipsis; white-space: nowrap;
+                        ",
+                        title: "{topic}",
+                        "{topic}"
+                    }
+                }
+                select {
+                    style: "
+                        margin-left: auto; flex-shrink: 0; padding: 0.3rem 0.5rem; font-size: 0.8rem;
+                        background: rgba(255,255,255,0.06); border: 1px solid rgba(255,255,255,0.12);
+                        border-radius: 8px; color: #e5e7eb;
+                    ",
+                    value: if sort() == ForumSort::CreationDate { "created" } else { "activity" },
+                    onchange: move |evt| {
+                        sort.set(if evt.value() == "created" { ForumSort::CreationDate } else { ForumSort::LatestActivity });
+                    },
+                    option { value: "activity", "Latest activity" }
+                    option { value: "created", "Creation date" }
+                }
+                if can_post {
+                    button {
+                        class: "anim-btn",
+                        style: "
+                            flex-shrink: 0; padding: 0.35rem 0.75rem; font-size: 0.85rem;
+                            background: rgba(0,255,245,0.2); border: 1px solid rgba(0,255,245,0.4);
+                            border-radius: 8px; color: #00fff5; cursor: pointer;
+                        ",
+                        onclick: move |_| composing.set(!composing()),
+                        if composing() { "Cancel" } else { "New post" }
+                    }
+                }
+            }
+            if !forum.available_tags.is_empty() {
+                div {
+                    style: "flex-shrink: 0; display: flex; flex-wrap: wrap; gap: 0.35rem; padding: 0.5rem 1rem; border-bottom: 1px solid rgba(255,255,255,0.06);",
+                    for tag in forum.available_tags.iter() {
+                        TagChip {
+                            key: "{tag.id}",
+                            label: tag_label(tag),
+                            active: filter.contains(&tag.id),
+                            onclick: {
+                                let id = tag.id.clone();
+                                move |_| {
+                                    let mut f = tag_filter.write();
+                                    if !f.remove(&id) {
+                                        f.insert(id.clone());
+                                    }
+                                }
+                            },
+                        }
+                    }
+                }
+            }
+            if composing() {
+                div {
+                    style: "
+                        flex-shrink: 0; display: flex; flex-direction: column; gap: 0.5rem;
+                        padding: 0.75rem 1rem; border-bottom: 1px solid rgba(255,255,255,0.1);
+                    ",
+                    input {
+                        style: "
+                            padding: 0.5rem 0.75rem; font-size: 0.9375rem;
+                            background: rgba(255,255,255,0.06); border: 1px solid rgba(255,255,255,0.12);
+                            border-radius: 8px; color: #e5e7eb; outline: none;
+                        ",
+                        placeholder: "Post title",
+                        maxlength: "100",
+                        value: "{title()}",
+                        oninput: move |evt| title.set(evt.value()),
+                    }
+                    textarea {
+                        style: "
+                            padding: 0.5rem 0.75rem; font-size: 0.9375rem; min-height: 5rem; resize: vertical;
+                            background: rgba(255,255,255,0.06); border: 1px solid rgba(255,255,255,0.12);
+                            border-radius: 8px; color: #e5e7eb; outline: none; font-family: inherit;
+                        ",
+                        placeholder: "Write your first message",
+                        value: "{body()}",
+                        oninput: move |evt| body.set(evt.value()),
+                    }
+                    if !forum.available_tags.is_empty() {
+                        div {
+                            style: "display: flex; flex-wrap: wrap; gap: 0.35rem; align-items: center;",
+                            span { style: "font-size: 0.75rem; color: #6b7280;", "Tags (up to {MAX_APPLIED_TAGS}):" }
+                            for tag in forum.available_tags.iter() {
+                                TagChip {
+                                    key: "new-{tag.id}",
+                                    label: tag_label(tag),
+                                    active: post_tags().contains(&tag.id),
+                                    onclick: {
+                                        let id = tag.id.clone();
+                                        move |_| {
+                                            let mut tags = post_tags.write();
+                                            if let Some(pos) = tags.iter().position(|t| *t == id) {
+                                                tags.remove(pos);
+                                            } else if tags.len() < MAX_APPLIED_TAGS {
+                                                tags.push(id.clone());
+                                            }
+                                        }
+                                    },
+                                }
+                            }
+                        }
+                    }
+                    div {
+                        style: "display: flex; justify-content: flex-end;",
+                        button {
+                            class: "anim-btn",
+                            style: "
+                                padding: 0.45rem 1rem; font-size: 0.9rem; font-weight: 500;
+                                background: rgba(0,255,245,0.2); border: 1px solid rgba(0,255,245,0.4);
+                                border-radius: 8px; color: #00fff5; cursor: pointer;
+                            ",
+                            disabled: !can_submit,
+                            onclick: move |_| {
+                                let (t, b) = (title().trim().to_string(), body().trim().to_string());
+                                if t.is_empty() || b.is_empty() {
+                                    return;
+                                }
+                                on_create_post.call((submit_id.clone(), t, b, post_tags()));
+                                title.set(String::new());
+                                body.set(String::new());
+                                post_tags.set(Vec::new());
+                                composing.set(false);
+                            },
+                            "Post"
+                        }
+                    }
+                }
+            }
+            div {
+                class: "custom-scroll",
+                style: "flex: 1 1 0; min-height: 0; overflow-y: auto; padding: 1rem; display: flex; flex-direction: column; gap: 0.6rem;",
+                if cards.is_empty() {
+                    div {
+                        style: "padding: 2rem; text-align: center; color: #6b7280; font-size: 0.9rem;",
+                        if filter.is_empty() { "No posts yet." } else { "No posts match the selected tags." }
+                    }
+                }
+                for (post, tags, preview) in cards.into_iter() {
+                    PostCard {
+                        key: "{post.id}",
+                        post,
+                        tags,
+                        preview,
+                        on_open_thread,
+                    }
+                }
+            }
+        }
+    }
+}
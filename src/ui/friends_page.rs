@@ -0,0 +1,291 @@
+use dioxus::prelude::*;
+use std::collections::HashMap;
+
+use crate::http::{DiscordUser, Relationship, RelationshipAction, RelationshipType};
+use crate::state::{activity_line, Activity, PresenceStatus};
+use crate::ui::channel_list::AvatarWithStatus;
+
+fn display_name(u: &DiscordUser) -> String {
+    u.global_name
+        .as_deref()
+        .unwrap_or(u.username.as_str())
+        .to_string()
+}
+
+fn avatar_url(u: &DiscordUser) -> Option<String> {
+    u.avatar.as_ref().map(|hash| {
+        let ext = if hash.starts_with("a_") { "gif" } else { "png" };
+        format!(
+            "https://cdn.discordapp.com/avatars/{}/{}.{}",
+            u.id, hash, ext
+        )
+    })
+}
+
+fn status_label(s: PresenceStatus) -> &'static str {
+    match s {
+        PresenceStatus::Online => "Online",
+        PresenceStatus::Idle => "Idle",
+        PresenceStatus::DoNotDisturb => "Do Not Disturb",
+        PresenceStatus::Invisible => "Offline",
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FriendsTab {
+    Online,
+    All,
+    Pending,
+    Blocked,
+    Add,
+}
+
+#[component]
+fn FriendRow(
+    relationship: Relationship,
+    status: PresenceStatus,
+    /// Custom status or current activity, shown instead of the status for friends.
+    activity: Option<String>,
+    on_open_friend: EventHandler<String>,
+    on_relationship_action: EventHandler<(String, RelationshipAction)>,
+) -> Element {
+    let user = relationship.user.clone();
+    let name = display_name(&user);
+    let subtitle = match relationship.r#type {
+        RelationshipType::Incoming => "Incoming friend request".to_string(),
+        RelationshipType::Outgoing => "Outgoing friend request".to_string(),
+        RelationshipType::Blocked => "Blocked".to_string(),
+        _ => activity.unwrap_or_else(|| status_label(status).to_string()),
+    };
+    // (label, action) per relationship type; None opens the DM.
+    let actions: Vec<(&'static str, Option<RelationshipAction>)> = match relationship.r#type {
+        RelationshipType::Friend => vec![
+            ("Message", None),
+            ("Remove", Some(RelationshipAction::Remove)),
+            ("Block", Some(RelationshipAction::Block)),
+        ],
+        RelationshipType::Incoming => vec![
+            ("Accept", Some(RelationshipAction::Accept)),
+            ("Decline", Some(RelationshipAction::Remove)),
+        ],
+        RelationshipType::Outgoing => vec![("Cancel", Some(RelationshipAction::Remove))],
+        RelationshipType::Blocked => vec![("Unblock", Some(RelationshipAction::Remove))],
+        _ => Vec::new(),
+    };
+    let uid = relationship.id.clone();
+
+    rsx! {
+        div {
+            class: "anim-channel-item",
+            style: "
+                display: flex; align-items: center; gap: 0.75rem;
+                padding: 0.6rem 0.75rem; border-radius: 8px;
+                border-top: 1px solid rgba(255,255,255,0.06);
+            ",
+            AvatarWithStatus {
+                avatar_url: avatar_url(&user),
+                fallback: name.chars().next().unwrap_or('?'),
+                status,
+            }
+            div {
+                style: "flex: 1; min-width: 0; display: flex; flex-direction: column;",
+                span {
+                    style: "font-size: 0.9rem; color: #e5e7eb; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
+                    "{name} "
+                    span { style: "font-size: 0.75rem; color: #6b7280;", "@{user.username}" }
+                }
+                span { style: "font-size: 0.75rem; color: #9ca3af;", "{subtitle}" }
+            }
+            for (label, action) in actions.into_iter() {
+                button {
+                    key: "{label}",
+                    class: "anim-btn",
+                    style: if matches!(action, Some(RelationshipAction::Accept) | None) {
+                        "padding: 0.3rem 0.65rem; font-size: 0.75rem; border-radius: 6px; cursor: pointer; border: 1px solid rgba(0,255,245,0.4); background: rgba(0,255,245,0.15); color: #00fff5;"
+                    } else {
+                        "padding: 0.3rem 0.65rem; font-size: 0.75rem; border-radius: 6px; cursor: pointer; border: 1px solid rgba(255,255,255,0.12); background: transparent; color: #9ca3af;"
+                    },
+                    onclick: {
+                        let uid = uid.clone();
+                        move |_| match action {
+                            Some(action) => on_relationship_action.call((uid.clone(), action)),
+                            None => on_open_friend.call(uid.clone()),
+                        }
+                    },
+                    "{label}"
+                }
+            }
+        }
+    }
+}
+
+/// Home view when no DM is open: friends by tab, pending requests and blocked users, plus adding by username.
+#[component]
+pub fn FriendsPage(
+    friends: Signal<Vec<Relationship>>,
+    presence_map: Signal<HashMap<String, PresenceStatus>>,
+    activity_map: Signal<HashMap<String, Vec<Activity>>>,
+    on_open_friend: EventHandler<String>,
+    on_relationship_action: EventHandler<(String, RelationshipAction)>,
+    on_send_friend_request: EventHandler<String>,
+) -> Element {
+    let mut tab = use_signal(|| FriendsTab::Online);
+    let mut add_input = use_signal(String::new);
+    let all = friends();
+    let presence = presence_map();
+    let status_of = |id: &str| presence.get(id).copied().unwrap_or(PresenceStatus::Invisible);
+    let activities = activity_map();
+
+    let pending_count = all.iter().filter(|r| r.r#type == RelationshipType::Incoming).count();
+    let mut shown: Vec<Relationship> = all
+        .iter()
+        .filter(|r| match tab() {
+            FriendsTab::Online => r.r#type == RelationshipType::Friend && status_of(&r.id) != PresenceStatus::Invisible,
+            FriendsTab::All => r.r#type == RelationshipType::Friend,
+            FriendsTab::Pending => matches!(r.r#type, RelationshipType::Incoming | RelationshipType::Outgoing),
+            FriendsTab::Blocked => r.r#type == RelationshipType::Blocked,
+            FriendsTab::Add => false,
+        })
+        .cloned()
+        .collect();
+    // Incoming requests first, then by name.
+    shown.sort_by_key(|r| (r.r#type != RelationshipType::Incoming, display_name(&r.user).to_lowercase()));
+    let section_title = match tab() {
+        FriendsTab::Online => format!("Online — {}", shown.len()),
+        FriendsTab::All => format!("All friends — {}", shown.len()),
+        FriendsTab::Pending => format!("Pending — {}", shown.len()),
+        FriendsTab::Blocked => format!("Blocked — {}", shown.len()),
+        FriendsTab::Add => String::new(),
+    };
+    let can_send = !add_input().trim().is_empty();
+    let submit = move || {
+        let username = add_input().trim().to_lowercase();
+        if !username.is_empty() {
+            on_send_friend_request.call(username);
+            let mut add_input = add_input;
+            add_input.set(String::new());
+        }
+    };
+    let tabs = [
+        (FriendsTab::Online, "Online"),
+        (FriendsTab::All, "All"),
+        (FriendsTab::Pending, "Pending"),
+        (FriendsTab::Blocked, "Blocked"),
+    ];
+
+    rsx! {
+        div {
+            style: "flex: 1; display: flex; flex-direction: column; min-height: 0; background: #0a0a0f;",
+            header {
+                style: "
+                    flex-shrink: 0; padding: 0.75rem 1rem; display: flex; align-items: center; gap: 0.5rem;
+                    border-bottom: 1px solid rgba(255,255,255,0.1);
+                ",
+                span { style: "font-weight: 500; margin-right: 0.75rem;", "👥 Friends" }
+                for (t, label) in tabs.into_iter() {
+                    button {
+                        key: "{label}",
+                        class: "anim-btn",
+                        style: if tab() == t {
+                            "padding: 0.3rem 0.65rem; font-size: 0.85rem; border-radius: 6px; cursor: pointer; border: none; background: rgba(255,255,255,0.12); color: #e5e7eb;"
+                        } else {
+                            "padding: 0.3rem 0.65rem; font-size: 0.85rem; border-radius: 6px; cursor: pointer; border: none; background: transparent; color: #9ca3af;"
+                        },
+                        onclick: move |_| tab.set(t),
+                        "{label}"
+                        if t == FriendsTab::Pending && pending_count > 0 {
+                            span {
+                                style: "
+                                    margin-left: 0.35rem; background: #f43f5e; color: #fff;
+                                    font-size: 0.65rem; font-weight: 700;
+                                    padding: 0.05em 0.4em; border-radius: 10px;
+                                ",
+                                "{pending_count}"
+                            }
+                        }
+                    }
+                }
+                button {
+                    class: "anim-btn",
+                    style: if tab() == FriendsTab::Add {
+                        "padding: 0.3rem 0.65rem; font-size: 0.85rem; border-radius: 6px; cursor: pointer; border: none; background: transparent; color: #22c55e;"
+                    } else {
+                        "padding: 0.3rem 0.65rem; font-size: 0.85rem; border-radius: 6px; cursor: pointer; border: none; background: #15803d; color: #fff;"
+                    },
+                    onclick: move |_| tab.set(FriendsTab::Add),
+                    "Add Friend"
+                }
+            }
+            if tab() == FriendsTab::Add {
+                div {
+                    style: "padding: 1.25rem 1.5rem; display: flex; flex-direction: column; gap: 0.5rem;",
+                    h2 { style: "font-size: 1rem; font-weight: 600; color: #e5e7eb; margin: 0;", "Add Friend" }
+                    p {
+                        style: "font-size: 0.85rem; color: #9ca3af; margin: 0;",
+                        "You can add friends with their username."
+                    }
+                    div {
+                        style: "
+                            display: flex; gap: 0.5rem; padding: 0.4rem;
+                            background: rgba(255,255,255,0.06); border: 1px solid rgba(255,255,255,0.12);
+                            border-radius: 8px;
+                        ",
+                        input {
+                            style: "flex: 1; background: transparent; border: none; outline: none; color: #e5e7eb; font-size: 0.9rem; padding: 0.3rem;",
+                            placeholder: "Enter a username",
+                            value: "{add_input()}",
+                            oninput: move |evt| add_input.set(evt.value()),
+                            onkeydown: move |evt: KeyboardEvent| {
+                                if evt.key() == Key::Enter {
+                                    evt.prevent_default();
+                                    submit();
+                                }
+                            },
+                        }
+                        button {
+                            class: "anim-btn",
+                            style: "
+                                padding: 0.35rem 0.85rem; font-size: 0.8rem;
+                                background: rgba(0,255,245,0.2); border: 1px solid rgba(0,255,245,0.4);
+                                border-radius: 6px; color: #00fff5; cursor: pointer;
+                            ",
+                            disabled: !can_send,
+                            onclick: move |_| submit(),
+                            "Send Friend Request"
+                        }
+                    }
+                }
+            } else {
+                div {
+                    class: "custom-scroll",
+                    style: "flex: 1; min-height: 0; overflow-y: auto; padding: 1rem 1.5rem;",
+                    div {
+                        style: "padding-bottom: 0.5rem; font-size: 0.7rem; font-weight: 600; color: #9ca3af; text-transform: uppercase; letter-spacing: 0.05em;",
+                        "{section_title}"
+                    }
+                    if shown.is_empty() {
+                        div {
+                            style: "padding: 2rem 0; text-align: center; color: #6b7280; font-size: 0.875rem;",
+                            match tab() {
+                                FriendsTab::Online => "No one is around right now.",
+                                FriendsTab::Pending => "There are no pending friend requests.",
+                                FriendsTab::Blocked => "You haven't blocked anyone.",
+                                _ => "No friends yet. Add some by username.",
+                            }
+                        }
+                    }
+                    for r in shown.into_iter() {
+                        FriendRow {
+                            key: "{r.id}",
+                            status: status_of(&r.id),
+                            activity: activities.get(&r.id).and_then(|a| activity_line(a)),
+                            relationship: r,
+                            on_open_friend,
+                            on_relationship_action,
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,386 @@
+use base64::Engine;
+use dioxus::prelude::*;
+use std::collections::HashSet;
+
+use crate::http::{DiscordUser, DmChannel, GroupDmEdit, Relationship, RelationshipType};
+
+/// Group DMs hold at most this many users, us included.
+const GROUP_DM_MAX_MEMBERS: usize = 10;
+
+fn display_name(u: &DiscordUser) -> String {
+    u.global_name
+        .as_deref()
+        .unwrap_or(u.username.as_str())
+        .to_string()
+}
+
+fn avatar_url(u: &DiscordUser) -> Option<String> {
+    u.avatar.as_ref().map(|hash| {
+        let ext = if hash.starts_with("a_") { "gif" } else { "png" };
+        format!(
+            "https://cdn.discordapp.com/avatars/{}/{}.{}",
+            u.id, hash, ext
+        )
+    })
+}
+
+/// Desktop file inputs often report no MIME type; guess it from the extension.
+fn image_mime(name: &str, content_type: Option<String>) -> String {
+    content_type.filter(|t| t.starts_with("image/")).unwrap_or_else(|| {
+        match name.rsplit('.').next().map(|e| e.to_ascii_lowercase()).as_deref() {
+            Some("jpg") | Some("jpeg") => "image/jpeg",
+            Some("gif") => "image/gif",
+            Some("webp") => "image/webp",
+            _ => "image/png",
+        }
+        .to_string()
+    })
+}
+
+#[component]
+fn PersonRow(user: DiscordUser, badge: Option<String>, action: Option<(String, EventHandler<()>)>) -> Element {
+    let name = display_name(&user);
+    rsx! {
+        div {
+            style: "display: flex; align-items: center; gap: 0.5rem; padding: 0.35rem 0.5rem; border-radius: 6px;",
+            {match avatar_url(&user) {
+                Some(url) => rsx! {
+                    img { src: "{url}", alt: "", style: "width: 1.75rem; height: 1.75rem; border-radius: 50%; object-fit: cover; flex-shrink: 0;" }
+                },
+                None => rsx! {
+                    div {
+                        style: "
+                            width: 1.75rem; height: 1.75rem; border-radius: 50%; flex-shrink: 0;
+                            background: rgba(0,255,245,0.2);
+                            display: flex; align-items: center; justify-content: center;
+                            font-size: 0.65rem; font-weight: 600; color: #00fff5;
+                        ",
+                        "{name.chars().next().unwrap_or('?')}"
+                    }
+                },
+            }}
+            span {
+                style: "flex: 1; min-width: 0; font-size: 0.85rem; color: #e5e7eb; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
+                title: "{name}",
+                "{name}"
+            }
+            if let Some(ref badge) = badge {
+                span { style: "font-size: 0.8rem;", title: "Group owner", "{badge}" }
+            }
+            if let Some((label, handler)) = action {
+                button {
+                    class: "anim-btn",
+                    style: "
+                        padding: 0.2rem 0.5rem; font-size: 0.7rem; border-radius: 6px; cursor: pointer;
+                        border: 1px solid rgba(255,255,255,0.12); background: transparent; color: #9ca3af;
+                    ",
+                    onclick: move |_| handler.call(()),
+                    "{label}"
+                }
+            }
+        }
+    }
+}
+
+/// Popover to pick friends for a new group DM.
+#[component]
+pub fn NewGroupDmPicker(
+    x: f64,
+    y: f64,
+    friends: Signal<Vec<Relationship>>,
+    on_create: EventHandler<Vec<String>>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut picked = use_signal(HashSet::<String>::new);
+    let mut filter = use_signal(String::new);
+    let query = filter().trim().to_lowercase();
+    let mut candidates: Vec<DiscordUser> = friends()
+        .into_iter()
+        .filter(|r| r.r#type == RelationshipType::Friend)
+        .map(|r| r.user)
+        .filter(|u| query.is_empty() || display_name(u).to_lowercase().contains(&query) || u.username.contains(&query))
+        .collect();
+    candidates.sort_by_key(|u| display_name(u).to_lowercase());
+    let count = picked().len();
+    let full = count + 1 >= GROUP_DM_MAX_MEMBERS;
+
+    rsx! {
+        div {
+            style: "position: fixed; inset: 0; z-index: 1499;",
+            onclick: move |_| on_close.call(()),
+        }
+        div {
+            class: "anim-modal-content",
+            style: "
+                position: fixed; left: {x}px; top: {y}px;
+                width: 17rem; max-height: 24rem; display: flex; flex-direction: column;
+                background: #12121a; border: 1px solid rgba(255,255,255,0.12); border-radius: 12px;
+                box-shadow: 0 8px 24px rgba(0,0,0,0.4); z-index: 1500;
+            ",
+            div {
+                style: "padding: 0.75rem; display: flex; flex-direction: column; gap: 0.5rem; border-bottom: 1px solid rgba(255,255,255,0.1);",
+                div { style: "font-weight: 600; color: #e5e7eb; font-size: 0.9rem;", "Select friends" }
+                div {
+                    style: "font-size: 0.75rem; color: #6b7280;",
+                    "You can add {GROUP_DM_MAX_MEMBERS - 1 - count} more friends."
+                }
+                input {
+                    style: "
+                        padding: 0.4rem 0.5rem; font-size: 0.8rem;
+                        background: rgba(255,255,255,0.06); border: 1px solid rgba(255,255,255,0.12);
+                        border-radius: 6px; color: #e5e7eb; outline: none;
+                    ",
+                    placeholder: "Find a friend",
+                    value: "{filter()}",
+                    oninput: move |evt| filter.set(evt.value()),
+                }
+            }
+            div {
+                class: "custom-scroll",
+                style: "flex: 1; min-height: 0; overflow-y: auto; padding: 0.35rem;",
+                if candidates.is_empty() {
+                    div { style: "padding: 0.5rem; font-size: 0.8rem; color: #6b7280;", "No friends found." }
+                }
+                for u in candidates.into_iter() {
+                    label {
+                        key: "{u.id}",
+                        style: "display: flex; align-items: center; gap: 0.5rem; padding: 0.35rem 0.5rem; border-radius: 6px; cursor: pointer; font-size: 0.85rem; color: #e5e7eb;",
+                        input {
+                            r#type: "checkbox",
+                            checked: picked().contains(&u.id),
+                            disabled: full && !picked().contains(&u.id),
+                            onchange: {
+                                let id = u.id.clone();
+                                move |evt: FormEvent| {
+                                    if evt.checked() {
+                                        picked.write().insert(id.clone());
+                                    } else {
+                                        picked.write().remove(&id);
+                                    }
+                                }
+                            },
+                        }
+                        "{display_name(&u)}"
+                    }
+                }
+            }
+            div {
+                style: "padding: 0.75rem; border-top: 1px solid rgba(255,255,255,0.1);",
+                button {
+                    class: "anim-btn",
+                    style: "
+                        width: 100%; padding: 0.45rem; font-size: 0.8rem;
+                        background: rgba(0,255,245,0.2); border: 1px solid rgba(0,255,245,0.4);
+                        border-radius: 8px; color: #00fff5; cursor: pointer;
+                    ",
+                    disabled: count == 0,
+                    onclick: move |_| {
+                        on_create.call(picked().into_iter().collect());
+                        on_close.call(());
+                    },
+                    if count > 1 { "Create Group DM" } else { "Create DM" }
+                }
+            }
+        }
+    }
+}
+
+/// Side panel of an open group DM: icon, name, members (add / remove) and leaving.
+#[component]
+pub fn GroupDmPanel(
+    channel: DmChannel,
+    current_user: Signal<Option<DiscordUser>>,
+    friends: Signal<Vec<Relationship>>,
+    on_group_recipient: EventHandler<(String, String, bool)>,
+    on_edit_group_dm: EventHandler<(String, GroupDmEdit)>,
+    on_leave_group_dm: EventHandler<String>,
+    on_error: EventHandler<String>,
+    on_close: EventHandler<()>,
+) -> Element {
+    // The layout keys this panel by channel, so the name draft starts from the current name.
+    let initial_name = channel.name.clone().unwrap_or_default();
+    let mut name = use_signal(move || initial_name);
+    let mut show_add = use_signal(|| false);
+    let me = current_user();
+    let my_id = me.as_ref().map(|u| u.id.clone()).unwrap_or_default();
+    let is_owner = channel.owner_id.as_deref() == Some(my_id.as_str());
+    let cid = channel.id.clone();
+    let in_group: HashSet<String> = channel.recipients.iter().map(|u| u.id.clone()).collect();
+    let room = GROUP_DM_MAX_MEMBERS.saturating_sub(channel.recipients.len() + 1);
+    let mut addable: Vec<DiscordUser> = friends()
+        .into_iter()
+        .filter(|r| r.r#type == RelationshipType::Friend && !in_group.contains(&r.id))
+        .map(|r| r.user)
+        .collect();
+    addable.sort_by_key(|u| display_name(u).to_lowercase());
+    let mut members: Vec<DiscordUser> = me.into_iter().chain(channel.recipients.iter().cloned()).collect();
+    members.sort_by_key(|u| (channel.owner_id.as_deref() != Some(u.id.as_str()), display_name(u).to_lowercase()));
+    let name_changed = name().trim() != channel.name.as_deref().unwrap_or("").trim();
+    let icon = channel.icon_url();
+    let has_icon = icon.is_some();
+    let section_label = "padding: 0.25rem 0.5rem; font-size: 0.65rem; text-transform: uppercase; letter-spacing: 0.05em; color: #6b7280;";
+
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; height: 100%; min-height: 0;",
+            header {
+                style: "
+                    flex-shrink: 0; padding: 0.75rem; display: flex; align-items: center; gap: 0.5rem;
+                    border-bottom: 1px solid rgba(255,255,255,0.1);
+                ",
+                span { style: "color: #00fff5;", "👥" }
+                span { style: "flex: 1; font-weight: 500;", "Group DM" }
+                button {
+                    class: "anim-btn",
+                    style: "background: transparent; border: none; color: #9ca3af; cursor: pointer; font-size: 1rem;",
+                    onclick: move |_| on_close.call(()),
+                    "✕"
+                }
+            }
+            div {
+                class: "custom-scroll",
+                style: "flex: 1; min-height: 0; overflow-y: auto; padding: 0.75rem; display: flex; flex-direction: column; gap: 0.75rem;",
+                div {
+                    style: "display: flex; align-items: center; gap: 0.75rem;",
+                    label {
+                        title: "Change icon",
+                        style: "cursor: pointer; flex-shrink: 0;",
+                        {match icon {
+                            Some(url) => rsx! {
+                                img { src: "{url}", alt: "", style: "width: 3rem; height: 3rem; border-radius: 50%; object-fit: cover;" }
+                            },
+                            None => rsx! {
+                                div {
+                                    style: "
+                                        width: 3rem; height: 3rem; border-radius: 50%;
+                                        border: 1px dashed rgba(255,255,255,0.25);
+                                        display: flex; align-items: center; justify-content: center;
+                                        font-size: 0.6rem; color: #9ca3af; text-align: center;
+                                    ",
+                                    "Upload"
+                                }
+                            },
+                        }}
+                        input {
+                            r#type: "file",
+                            accept: "image/*",
+                            style: "display: none;",
+                            onchange: {
+                                let cid = cid.clone();
+                                move |evt: FormEvent| {
+                                    let Some(file) = evt.files().into_iter().next() else { return };
+                                    let cid = cid.clone();
+                                    spawn(async move {
+                                        match file.read_bytes().await {
+                                            Ok(bytes) => {
+                                                let mime = image_mime(&file.name(), file.content_type());
+                                                let data = base64::engine::general_purpose::STANDARD.encode(&bytes);
+                                                let uri = format!("data:{};base64,{}", mime, data);
+                                                on_edit_group_dm.call((cid, GroupDmEdit::Icon(Some(uri))));
+                                            }
+                                            Err(e) => {
+                                                eprintln!("[group dm] could not read icon {}: {}", file.name(), e);
+                                                on_error.call(format!("Could not read {}: {e}", file.name()));
+                                            }
+                                        }
+                                    });
+                                }
+                            },
+                        }
+                    }
+                    if has_icon {
+                        button {
+                            class: "anim-btn",
+                            style: "background: transparent; border: none; color: #9ca3af; cursor: pointer; font-size: 0.75rem;",
+                            onclick: {
+                                let cid = cid.clone();
+                                move |_| on_edit_group_dm.call((cid.clone(), GroupDmEdit::Icon(None)))
+                            },
+                            "Remove icon"
+                        }
+                    }
+                }
+                div {
+                    style: "display: flex; gap: 0.4rem;",
+                    input {
+                        style: "
+                            flex: 1; min-width: 0; padding: 0.4rem 0.5rem; font-size: 0.85rem;
+                            background: rgba(255,255,255,0.06); border: 1px solid rgba(255,255,255,0.12);
+                            border-radius: 6px; color: #e5e7eb; outline: none;
+                        ",
+                        placeholder: "Group name",
+                        maxlength: "100",
+                        value: "{name()}",
+                        oninput: move |evt| name.set(evt.value()),
+                    }
+                    button {
+                        class: "anim-btn",
+                        style: "
+                            padding: 0.35rem 0.6rem; font-size: 0.75rem;
+                            background: rgba(0,255,245,0.2); border: 1px solid rgba(0,255,245,0.4);
+                            border-radius: 6px; color: #00fff5; cursor: pointer;
+                        ",
+                        disabled: !name_changed,
+                        onclick: {
+                            let cid = cid.clone();
+                            move |_| on_edit_group_dm.call((cid.clone(), GroupDmEdit::Name(name().trim().to_string())))
+                        },
+                        "Save"
+                    }
+                }
+                div {
+                    div { style: "{section_label}", "Members — {members.len()}" }
+                    for u in members.into_iter() {
+                        PersonRow {
+                            key: "{u.id}",
+                            badge: (channel.owner_id.as_deref() == Some(u.id.as_str())).then(|| "👑".to_string()),
+                            action: (is_owner && u.id != my_id).then(|| {
+                                let (cid, uid) = (cid.clone(), u.id.clone());
+                                ("Remove".to_string(), EventHandler::new(move |_| on_group_recipient.call((cid.clone(), uid.clone(), false))))
+                            }),
+                            user: u,
+                        }
+                    }
+                }
+                if room > 0 {
+                    div {
+                        button {
+                            class: "anim-btn",
+                            style: "padding: 0.25rem 0.5rem; background: transparent; border: none; color: #9ca3af; cursor: pointer; font-size: 0.75rem;",
+                            onclick: move |_| show_add.set(!show_add()),
+                            if show_add() { "▾ Add friends" } else { "▸ Add friends" }
+                        }
+                        if show_add() {
+                            if addable.is_empty() {
+                                div { style: "padding: 0.25rem 0.5rem; font-size: 0.8rem; color: #6b7280;", "All your friends are already here." }
+                            }
+                            for u in addable.into_iter() {
+                                PersonRow {
+                                    key: "add-{u.id}",
+                                    action: {
+                                        let (cid, uid) = (cid.clone(), u.id.clone());
+                                        Some(("Add".to_string(), EventHandler::new(move |_| on_group_recipient.call((cid.clone(), uid.clone(), true)))))
+                                    },
+                                    user: u,
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            div {
+                style: "flex-shrink: 0; padding: 0.75rem; border-top: 1px solid rgba(255,255,255,0.1);",
+                button {
+                    class: "anim-btn",
+                    style: "
+                        width: 100%; padding: 0.45rem; font-size: 0.8rem;
+                        background: rgba(239,68,68,0.15); border: 1px solid rgba(239,68,68,0.4);
+                        border-radius: 8px; color: #fca5a5; cursor: pointer;
+                    ",
+                    onclick: move |_| on_leave_group_dm.call(cid.clone()),
+                    "Leave Group"
+                }
+            }
+        }
+    }
+}
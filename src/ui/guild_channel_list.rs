@@ -1,44 +1,215 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
 use dioxus::prelude::*;
 
-use crate::http::GuildChannel;
+use crate::http::{ChannelType, DiscordUser, GuildChannel, GuildMember};
+use crate::permissions::PermissionContext;
+use crate::state::{snowflake_cmp, AppSettings, NotificationSettings, NotificationSettingsChange, ReadState};
+use crate::ui::{ChannelContextMenu, VoiceUserContextMenu};
+use crate::voice_state::{VoiceState, VoiceStates};
+
+fn avatar_url(user: &DiscordUser) -> Option<String> {
+    user.avatar.as_ref().map(|hash| {
+        let ext = if hash.starts_with("a_") { "gif" } else { "png" };
+        format!("https://cdn.discordapp.com/avatars/{}/{}.{}", user.id, hash, ext)
+    })
+}
+
+/// Discord's sidebar order: `position`, ties broken by creation (ID).
+fn by_position(a: &GuildChannel, b: &GuildChannel) -> Ordering {
+    a.position.cmp(&b.position).then_with(|| snowflake_cmp(&a.id, &b.id))
+}
 
-/// Text channel type in Discord API.
-const CHANNEL_TYPE_TEXT: i32 = 0;
-/// Voice channel type.
-const CHANNEL_TYPE_VOICE: i32 = 2;
-/// Category channel type.
-const CHANNEL_TYPE_CATEGORY: i32 = 4;
-/// Private thread (only for users with permission).
-const CHANNEL_TYPE_PRIVATE_THREAD: i32 = 12;
+/// One entry of the text section: a text-like channel or a thread nested under one.
+#[derive(Clone, PartialEq)]
+struct ChannelRow {
+    id: String,
+    name: String,
+    icon: &'static str,
+    color: &'static str,
+    private: bool,
+    thread: bool,
+    unread: bool,
+    muted: bool,
+    mentions: u32,
+}
 
 #[component]
 fn ChannelButton(
     channel_id: String,
     channel_name: String,
+    icon: &'static str,
     color: &'static str,
     show_lock: bool,
+    thread: bool,
+    unread: bool,
+    muted: bool,
+    mention_count: u32,
     on_select_channel: EventHandler<Option<String>>,
+    on_context: EventHandler<(f64, f64, String)>,
 ) -> Element {
-    let prefix = if show_lock { "🔒" } else { "#" };
+    let prefix = if show_lock { "🔒" } else { icon };
     let prefix_style = if show_lock {
         "opacity: 0.9; font-size: 0.75rem;"
     } else {
         "opacity: 0.8;"
     };
+    let weight = if unread { "600" } else { "400" };
+    let opacity = if muted { "0.5" } else { "1" };
+    let (indent, font_size) = if thread { ("1.75rem", "0.85rem") } else { ("0.75rem", "0.9rem") };
+    let context_id = channel_id.clone();
     rsx! {
         button {
             class: "anim-btn",
             style: "
                 display: flex; align-items: center; gap: 0.5rem;
-                width: 100%; padding: 0.4rem 0.75rem;
+                width: 100%; padding: 0.4rem 0.75rem 0.4rem {indent};
                 text-align: left; border: none; background: transparent;
-                color: {color};
-                font-size: 0.9rem; cursor: pointer;
+                color: {color}; font-weight: {weight}; opacity: {opacity};
+                font-size: {font_size}; cursor: pointer;
                 border-radius: 0.25rem; margin: 0 0.25rem;
+                position: relative;
             ",
             onclick: move |_| on_select_channel.call(Some(channel_id.clone())),
+            oncontextmenu: move |evt| {
+                evt.prevent_default();
+                let coords = evt.client_coordinates();
+                on_context.call((coords.x, coords.y, context_id.clone()));
+            },
+            if unread {
+                span {
+                    style: "
+                        position: absolute; left: -0.25rem; top: 50%;
+                        width: 4px; height: 8px; margin-top: -4px;
+                        border-radius: 0 4px 4px 0; background: #f3f4f6;
+                    ",
+                }
+            }
             span { style: "{prefix_style}", "{prefix}" }
-            "{channel_name}"
+            span { style: "flex: 1; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;", "{channel_name}" }
+            if mention_count > 0 {
+                span {
+                    style: "
+                        min-width: 1.1rem; height: 1.1rem; padding: 0 0.3rem;
+                        border-radius: 9999px; background: #ef4444; color: #fff;
+                        font-size: 0.7rem; font-weight: 700;
+                        display: flex; align-items: center; justify-content: center;
+                    ",
+                    "{mention_count.min(99)}"
+                }
+            }
+        }
+    }
+}
+
+/// Someone in a voice channel, as listed under it.
+#[derive(Clone, PartialEq)]
+struct VoiceParticipant {
+    user_id: String,
+    name: String,
+    avatar: Option<String>,
+    speaking: bool,
+    muted: bool,
+    deafened: bool,
+    streaming: bool,
+    /// Muted or turned down to 0% by us.
+    locally_muted: bool,
+    is_self: bool,
+}
+
+impl VoiceParticipant {
+    /// Name and avatar from the member cache, else the member sent with the voice state.
+    fn new(
+        vs: &VoiceState,
+        guild_id: Option<&str>,
+        members: Option<&HashMap<String, GuildMember>>,
+        settings: &AppSettings,
+        self_id: Option<&str>,
+        speaking: bool,
+    ) -> Self {
+        let member = members.and_then(|m| m.get(&vs.user_id)).or(vs.member.as_ref());
+        let user = member.and_then(|m| m.user.as_ref());
+        let name = member
+            .and_then(|m| m.nick.clone())
+            .filter(|n| !n.is_empty())
+            .or_else(|| user.map(|u| u.global_name.clone().unwrap_or_else(|| u.username.clone())))
+            .unwrap_or_else(|| vs.user_id.clone());
+        let avatar = member
+            .zip(guild_id)
+            .and_then(|(m, g)| m.guild_avatar_url(g))
+            .or_else(|| user.and_then(avatar_url));
+        Self {
+            user_id: vs.user_id.clone(),
+            name,
+            avatar,
+            speaking,
+            muted: vs.is_muted(),
+            deafened: vs.is_deafened(),
+            streaming: vs.self_stream,
+            locally_muted: settings.voice_user(&vs.user_id).gain() <= 0.0,
+            is_self: self_id == Some(vs.user_id.as_str()),
+        }
+    }
+}
+
+/// `on_context` gets (x, y, user ID, name); not offered for ourselves.
+#[component]
+fn VoiceParticipantRow(participant: VoiceParticipant, on_context: EventHandler<(f64, f64, String, String)>) -> Element {
+    let p = participant;
+    let context = (p.user_id.clone(), p.name.clone());
+    let ring = if p.speaking { "box-shadow: 0 0 0 2px #22c55e;" } else { "" };
+    let name_color = if p.speaking { "#e5e7eb" } else { "#9ca3af" };
+    rsx! {
+        div {
+            style: "
+                display: flex; align-items: center; gap: 0.5rem;
+                padding: 0.2rem 0.75rem 0.2rem 1.75rem; margin: 0 0.25rem;
+                font-size: 0.8rem;
+            ",
+            oncontextmenu: move |evt| {
+                evt.prevent_default();
+                if !p.is_self {
+                    let coords = evt.client_coordinates();
+                    on_context.call((coords.x, coords.y, context.0.clone(), context.1.clone()));
+                }
+            },
+            {match p.avatar {
+                Some(url) => rsx! {
+                    img {
+                        src: "{url}",
+                        alt: "",
+                        style: "width: 1.25rem; height: 1.25rem; border-radius: 50%; object-fit: cover; flex-shrink: 0; {ring}",
+                    }
+                },
+                None => rsx! {
+                    div {
+                        style: "
+                            width: 1.25rem; height: 1.25rem; border-radius: 50%; flex-shrink: 0;
+                            background: #0f3b3d; color: #00fff5; font-size: 0.65rem; font-weight: 600;
+                            display: flex; align-items: center; justify-content: center; {ring}
+                        ",
+                        "{p.name.chars().next().unwrap_or('?')}"
+                    }
+                },
+            }}
+            span {
+                style: "flex: 1; min-width: 0; color: {name_color}; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
+                "{p.name}"
+            }
+            if p.streaming {
+                span {
+                    style: "padding: 0 0.3rem; border-radius: 4px; background: #ef4444; color: #fff; font-size: 0.6rem; font-weight: 700;",
+                    "LIVE"
+                }
+            }
+            if p.deafened {
+                span { style: "font-size: 0.75rem; opacity: 0.7;", title: "Deafened", "🔕" }
+            } else if p.muted {
+                span { style: "font-size: 0.75rem; opacity: 0.7;", title: "Muted", "🔇" }
+            } else if p.locally_muted {
+                span { style: "font-size: 0.75rem; opacity: 0.4;", title: "Muted for you", "🔇" }
+            }
         }
     }
 }
@@ -51,8 +222,21 @@ pub fn GuildChannelList(
     access_denied_channel_ids: Signal<std::collections::HashSet<String>>,
     current_voice_channel_id: Signal<Option<String>>,
     current_voice_guild_id: Signal<Option<String>>,
+    voice_states: Signal<VoiceStates>,
+    /// Users in our call who are talking right now.
+    speaking_users: Signal<HashSet<String>>,
+    member_cache: Signal<HashMap<String, HashMap<String, GuildMember>>>,
+    /// Per-user voice volume and local mute.
+    settings: Signal<AppSettings>,
+    current_user: Signal<Option<DiscordUser>>,
     selected_guild_id: Signal<Option<String>>,
+    read_states: Signal<std::collections::HashMap<String, ReadState>>,
+    notification_settings: Signal<NotificationSettings>,
+    guild_permissions: Signal<Option<PermissionContext>>,
+    joined_threads: Signal<std::collections::HashSet<String>>,
     on_select_channel: EventHandler<Option<String>>,
+    on_mark_read: EventHandler<String>,
+    on_update_notifications: EventHandler<NotificationSettingsChange>,
     on_join_voice: EventHandler<(Option<String>, String)>,
     on_leave_voice: EventHandler<()>,
 ) -> Element {
@@ -62,90 +246,131 @@ pub fn GuildChannelList(
     let in_voice = current_voice_channel_id();
     let voice_guild = current_voice_guild_id();
     let guild_id = selected_guild_id();
+    let reads = read_states();
+    let notif = notification_settings();
+    let perms = guild_permissions();
+    let joined = joined_threads();
+    let voice = voice_states();
+    let speaking = speaking_users();
+    let cache = member_cache();
+    let guild_members = guild_id.as_ref().and_then(|g| cache.get(g));
+    let app_settings = settings();
+    let self_id = current_user().map(|u| u.id);
+    let mut channel_context = use_signal(|| None::<(f64, f64, String)>);
+    let mut voice_user_context = use_signal(|| None::<(f64, f64, String, String)>);
 
-    let text_channels: Vec<&GuildChannel> = channels
-        .iter()
-        .filter(|c| c.r#type == CHANNEL_TYPE_TEXT || c.r#type == CHANNEL_TYPE_PRIVATE_THREAD)
-        .collect();
-    let voice_channels: Vec<&GuildChannel> = channels
-        .iter()
-        .filter(|c| c.r#type == CHANNEL_TYPE_VOICE)
-        .collect();
-    let categories: Vec<&GuildChannel> = channels
+    let mut categories: Vec<&GuildChannel> = channels
         .iter()
-        .filter(|c| c.r#type == CHANNEL_TYPE_CATEGORY)
+        .filter(|c| c.r#type == ChannelType::Category)
         .collect();
+    categories.sort_by(|a, b| by_position(a, b));
 
-    type ChannelRow = (String, String, bool, &'static str, bool);
-    type VoiceRow = (String, String, bool, Option<String>); // (id, name, is_connected, guild_id for join)
+    type VoiceRow = (String, String, &'static str, bool, Option<String>, bool, Vec<VoiceParticipant>); // (id, name, icon, is_connected, guild_id for join, can_connect, participants)
     let selected_ref = selected.as_ref().map(|s| s.as_str());
 
+    // Computed from roles and overwrites once loaded; until then private threads and 403s are all we know.
     let is_private = |c: &GuildChannel| -> bool {
-        c.r#type == CHANNEL_TYPE_PRIVATE_THREAD || access_denied.contains(&c.id)
+        access_denied.contains(&c.id)
+            || perms
+                .as_ref()
+                .map(|p| !p.can_view(c, &channels))
+                .unwrap_or(c.r#type == ChannelType::PrivateThread)
+    };
+    let can_connect = |c: &GuildChannel| -> bool {
+        perms.as_ref().map(|p| p.can_connect(c, &channels)).unwrap_or(true)
+    };
+    let make_row = |c: &GuildChannel| -> ChannelRow {
+        let is_sel = selected_ref == Some(c.id.as_str());
+        let read = reads.get(&c.id);
+        let muted = notif.is_channel_muted(guild_id.as_deref(), &c.id);
+        let unread = !is_sel && !muted && read.map(|r| r.is_unread()).unwrap_or(false);
+        ChannelRow {
+            id: c.id.clone(),
+            name: c.name.clone(),
+            icon: c.r#type.icon(),
+            color: if is_sel { "#00fff5" } else if unread { "#f3f4f6" } else { "#9ca3af" },
+            private: is_private(c),
+            thread: c.r#type.is_thread(),
+            unread,
+            muted,
+            mentions: read.map(|r| r.mention_count).unwrap_or(0),
+        }
+    };
+    // Children of a category (None = top level) matching `kind`, in sidebar order.
+    let children = |parent: Option<&str>, kind: fn(ChannelType) -> bool| -> Vec<&GuildChannel> {
+        let mut list: Vec<&GuildChannel> = channels
+            .iter()
+            .filter(|c| c.parent_id.as_deref() == parent && kind(c.r#type))
+            .filter(|c| show_private_channels || !is_private(c))
+            .collect();
+        list.sort_by(|a, b| by_position(a, b));
+        list
     };
 
+    let groups: Vec<(Option<String>, Option<&str>)> = std::iter::once((None, None))
+        .chain(categories.iter().map(|cat| (Some(cat.name.clone()), Some(cat.id.as_str()))))
+        .collect();
     let mut cat_names: Vec<Option<String>> = Vec::new();
     let mut cat_rows: Vec<Vec<ChannelRow>> = Vec::new();
     let mut voice_entries: Vec<VoiceRow> = Vec::new();
-    for c in voice_channels.iter().filter(|c| c.parent_id.is_none()) {
-        let connected = in_voice.as_ref() == Some(&c.id) && voice_guild.as_ref() == guild_id.as_ref();
-        voice_entries.push((c.id.clone(), c.name.clone(), connected, guild_id.clone()));
-    }
-    voice_entries.sort_by(|a, b| a.1.cmp(&b.1));
-    for cat in categories.iter() {
-        for c in voice_channels.iter().filter(|c| c.parent_id.as_deref() == Some(cat.id.as_str())) {
-            let connected = in_voice.as_ref() == Some(&c.id) && voice_guild.as_ref() == guild_id.as_ref();
-            voice_entries.push((c.id.clone(), c.name.clone(), connected, guild_id.clone()));
+    for (name, parent) in groups.iter() {
+        let mut rows: Vec<ChannelRow> = Vec::new();
+        for c in children(*parent, ChannelType::is_text_like) {
+            rows.push(make_row(c));
+            // Followed threads sit under the channel they were started in, newest first.
+            let mut threads: Vec<&GuildChannel> = children(Some(c.id.as_str()), ChannelType::is_thread)
+                .into_iter()
+                .filter(|t| {
+                    let open = selected_ref == Some(t.id.as_str());
+                    open || (joined.contains(&t.id) && !t.is_archived())
+                })
+                .collect();
+            threads.sort_by(|a, b| snowflake_cmp(&b.id, &a.id));
+            rows.extend(threads.into_iter().map(make_row));
         }
-    }
-    voice_entries.sort_by(|a, b| a.1.cmp(&b.1));
-
-    let mut uncategorized: Vec<ChannelRow> = text_channels
-        .iter()
-        .filter(|c| c.parent_id.is_none())
-        .filter(|c| show_private_channels || !is_private(c))
-        .map(|c| {
-            let is_sel = selected_ref == Some(c.id.as_str());
-            let priv_ = is_private(c);
-            (
-                c.id.clone(),
-                c.name.clone(),
-                is_sel,
-                if is_sel { "#00fff5" } else { "#9ca3af" },
-                priv_,
-            )
-        })
-        .collect();
-    uncategorized.sort_by(|a, b| a.1.cmp(&b.1));
-    if !uncategorized.is_empty() {
-        cat_names.push(None);
-        cat_rows.push(uncategorized);
-    }
-    for cat in categories.iter() {
-        let mut chs: Vec<ChannelRow> = text_channels
-            .iter()
-            .filter(|c| c.parent_id.as_deref() == Some(cat.id.as_str()))
-            .filter(|c| show_private_channels || !is_private(c))
-            .map(|c| {
-                let is_sel = selected_ref == Some(c.id.as_str());
-                let priv_ = is_private(c);
-                (
-                    c.id.clone(),
-                    c.name.clone(),
-                    is_sel,
-                    if is_sel { "#00fff5" } else { "#9ca3af" },
-                    priv_,
-                )
-            })
-            .collect();
-        chs.sort_by(|a, b| a.1.cmp(&b.1));
-        if !chs.is_empty() {
-            cat_names.push(Some(cat.name.clone()));
-            cat_rows.push(chs);
+        if !rows.is_empty() {
+            cat_names.push(name.clone());
+            cat_rows.push(rows);
+        }
+        for c in children(*parent, ChannelType::is_voice) {
+            let connected = in_voice.as_ref() == Some(&c.id) && voice_guild.as_ref() == guild_id.as_ref();
+            // Speaking is only known for our own call.
+            let mut participants: Vec<VoiceParticipant> = voice
+                .in_channel(&c.id)
+                .into_iter()
+                .map(|vs| {
+                    let is_speaking = connected && speaking.contains(&vs.user_id);
+                    VoiceParticipant::new(vs, guild_id.as_deref(), guild_members, &app_settings, self_id.as_deref(), is_speaking)
+                })
+                .collect();
+            participants.sort_by_key(|p| p.name.to_lowercase());
+            voice_entries.push((c.id.clone(), c.name.clone(), c.r#type.icon(), connected, guild_id.clone(), can_connect(c), participants));
         }
     }
 
     rsx! {
+        if let Some((x, y, ref ch_id)) = channel_context() {
+            ChannelContextMenu {
+                x,
+                y,
+                channel_id: ch_id.clone(),
+                guild_id: guild_id.clone(),
+                notification_settings,
+                on_mark_read,
+                on_update_notifications,
+                on_close: move |_| channel_context.set(None),
+            }
+        }
+        if let Some((x, y, ref user_id, ref name)) = voice_user_context() {
+            VoiceUserContextMenu {
+                x,
+                y,
+                user_id: user_id.clone(),
+                name: name.clone(),
+                settings,
+                on_close: move |_| voice_user_context.set(None),
+            }
+        }
         div {
             class: "custom-scroll",
             style: "
@@ -172,13 +397,20 @@ pub fn GuildChannelList(
                         "{name}"
                     }
                 }
-                for (ch_s, ch_n, _is_sel, ch_color, ch_private) in rows.iter() {
+                for row in rows.iter() {
                     ChannelButton {
-                        channel_id: ch_s.clone(),
-                        channel_name: ch_n.clone(),
-                        color: ch_color,
-                        show_lock: *ch_private,
+                        key: "{row.id}",
+                        channel_id: row.id.clone(),
+                        channel_name: row.name.clone(),
+                        icon: row.icon,
+                        color: row.color,
+                        show_lock: row.private,
+                        thread: row.thread,
+                        unread: row.unread,
+                        muted: row.muted,
+                        mention_count: row.mentions,
                         on_select_channel,
+                        on_context: move |ctx| channel_context.set(Some(ctx)),
                     }
                 }
             }
@@ -209,7 +441,7 @@ pub fn GuildChannelList(
                     "No voice channels"
                 }
             } else {
-                for (ch_id, ch_name, connected, gid) in voice_entries.into_iter() {
+                for (ch_id, ch_name, ch_icon, connected, gid, ch_can_connect, participants) in voice_entries.into_iter() {
                     button {
                         class: "anim-btn",
                         style: "
@@ -219,13 +451,22 @@ pub fn GuildChannelList(
                             color: #9ca3af; font-size: 0.9rem; cursor: pointer;
                             border-radius: 0.25rem; margin: 0 0.25rem;
                         ",
-                        span { style: "opacity: 0.8;", "🔊 {ch_name}" }
+                        span {
+                            style: "opacity: 0.8;",
+                            if ch_can_connect { "{ch_icon} {ch_name}" } else { "🔒 {ch_name}" }
+                        }
                         if connected {
                             span {
                                 style: "font-size: 0.75rem; color: #22c55e; cursor: pointer;",
                                 onclick: move |_| on_leave_voice.call(()),
                                 "Leave"
                             }
+                        } else if !ch_can_connect {
+                            span {
+                                style: "font-size: 0.75rem; color: #6b7280;",
+                                title: "You don't have permission to connect",
+                                "Locked"
+                            }
                         } else {
                             span {
                                 style: "font-size: 0.75rem; color: #00fff5; cursor: pointer;",
@@ -234,6 +475,13 @@ pub fn GuildChannelList(
                             }
                         }
                     }
+                    for participant in participants.into_iter() {
+                        VoiceParticipantRow {
+                            key: "{participant.user_id}",
+                            participant,
+                            on_context: move |ctx| voice_user_context.set(Some(ctx)),
+                        }
+                    }
                 }
             }
         }
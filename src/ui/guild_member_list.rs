@@ -1,6 +1,9 @@
 use dioxus::prelude::*;
 
-use crate::http::{DiscordUser, GuildMember};
+use crate::http::{role_color, ApiGuild, DiscordUser, GuildMember, Role};
+use crate::member_list::{MemberList, MemberListItem, RANGE_SIZE};
+use crate::state::{activity_line, Activity, PresenceStatus};
+use crate::ui::{ProfileCache, UserCard};
 
 fn avatar_url(user: &DiscordUser) -> Option<String> {
     user.avatar.as_ref().map(|hash| {
@@ -18,30 +21,58 @@ fn display_name(user: &DiscordUser) -> &str {
         .unwrap_or(user.username.as_str())
 }
 
-fn member_display_name(m: &GuildMember) -> String {
-    m.nick
-        .clone()
-        .filter(|n| !n.is_empty())
-        .unwrap_or_else(|| {
-            m.user
-                .as_ref()
-                .map(|u| display_name(u).to_string())
-                .unwrap_or_else(|| "Unknown".to_string())
-        })
+/// Every row (group header or member) has the same height so the visible range follows from scrollTop.
+const ROW_HEIGHT_PX: f64 = 36.0;
+/// Rows rendered above and below the viewport.
+const OVERSCAN_ROWS: usize = 20;
+
+fn status_dot_color(s: PresenceStatus) -> &'static str {
+    match s {
+        PresenceStatus::Online => "#22c55e",
+        PresenceStatus::Idle => "#f59e0b",
+        PresenceStatus::DoNotDisturb => "#ef4444",
+        PresenceStatus::Invisible => "#6b7280",
+    }
 }
 
-/// Precomputed row for one member to avoid .id in rsx.
-struct MemberRow {
-    name: String,
-    avatar_url: Option<String>,
-    fallback_char: char,
+fn group_label(id: &str, roles: &[Role]) -> String {
+    match id {
+        "online" => "Online".to_string(),
+        "offline" => "Offline".to_string(),
+        _ => roles
+            .iter()
+            .find(|r| r.id == id)
+            .map(|r| r.name.clone())
+            .unwrap_or_else(|| "Role".to_string()),
+    }
+}
+
+/// Precomputed row to avoid .id in rsx. `Placeholder` rows are outside the synced ranges.
+enum ListRow {
+    Header { label: String, count: u32 },
+    Member {
+        name: String,
+        color: Option<String>,
+        avatar_url: Option<String>,
+        fallback_char: char,
+        status: PresenceStatus,
+        activities: Vec<Activity>,
+        member: Box<GuildMember>,
+    },
+    Placeholder,
 }
 
 #[component]
 fn MemberRowView(
     name: String,
+    /// CSS color of the member's highest colored role.
+    color: Option<String>,
     avatar_url: Option<String>,
     fallback_char: char,
+    status: PresenceStatus,
+    /// Custom status or current game under the name.
+    activity: Option<String>,
+    on_click: EventHandler<(f64, f64)>,
 ) -> Element {
     let avatar_block = avatar_url.map(|url| {
         rsx! {
@@ -69,82 +100,173 @@ fn MemberRowView(
         }
     });
 
+    let dot_color = status_dot_color(status);
+    let name_color = color.as_deref().unwrap_or("#e5e7eb");
+    let opacity = if status == PresenceStatus::Invisible { "0.5" } else { "1" };
+
     rsx! {
         div {
             style: "
                 display: flex; align-items: center; gap: 0.5rem;
-                padding: 0.35rem 0.75rem;
-                border-radius: 0.25rem; margin: 0 0.25rem;
+                height: {ROW_HEIGHT_PX}px; box-sizing: border-box; flex-shrink: 0;
+                padding: 0 0.75rem; opacity: {opacity};
+                border-radius: 0.25rem; margin: 0 0.25rem; cursor: pointer;
             ",
-            {avatar_block}
-            span {
-                style: "
-                    font-size: 0.875rem; color: #e5e7eb;
-                    overflow: hidden; text-overflow: ellipsis;
-                    white-space: nowrap;
-                ",
-                title: "{name}",
-                "{name}"
+            onclick: move |evt| {
+                let coords = evt.client_coordinates();
+                on_click.call((coords.x, coords.y));
+            },
+            div {
+                style: "position: relative; width: 1.75rem; height: 1.75rem; flex-shrink: 0;",
+                {avatar_block}
+                div {
+                    style: "
+                        position: absolute; right: -1px; bottom: -1px;
+                        width: 0.55rem; height: 0.55rem; border-radius: 999px;
+                        border: 2px solid #0b1120; background: {dot_color};
+                    ",
+                }
+            }
+            div {
+                style: "display: flex; flex-direction: column; min-width: 0;",
+                span {
+                    style: "
+                        font-size: 0.875rem; color: {name_color};
+                        overflow: hidden; text-overflow: ellipsis;
+                        white-space: nowrap;
+                    ",
+                    title: "{name}",
+                    "{name}"
+                }
+                if let Some(ref activity) = activity {
+                    span {
+                        style: "font-size: 0.7rem; color: #9ca3af; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
+                        title: "{activity}",
+                        "{activity}"
+                    }
+                }
             }
         }
     }
 }
 
+/// Member sidebar fed by the lazy member list. Only rows near the viewport are rendered;
+/// scrolling asks for the ranges covering the visible rows.
 #[component]
 pub fn GuildMemberList(
-    guild_members: Signal<Vec<GuildMember>>,
+    member_list: Signal<MemberList>,
+    guild_roles: Signal<Vec<Role>>,
+    guilds: Signal<Vec<ApiGuild>>,
+    user_profiles: Signal<ProfileCache>,
+    /// PRESENCE_UPDATE activities; used when the list row carries none.
+    activity_map: Signal<std::collections::HashMap<String, Vec<Activity>>>,
     current_user: Signal<Option<DiscordUser>>,
+    on_request_range: EventHandler<(u32, u32)>,
+    on_load_profile: EventHandler<(String, Option<String>)>,
+    on_open_dm: EventHandler<String>,
 ) -> Element {
-    let members = guild_members();
+    let mut user_card = use_signal(|| None::<(GuildMember, Vec<Activity>, f64, f64)>);
+    let mut scroll_top = use_signal(|| 0.0f64);
+    let mut viewport_height = use_signal(|| 600.0f64);
+    let mut requested = use_signal(|| (0u32, 0u32));
+    let list = member_list();
+    let roles = guild_roles();
+    let activities_by_user = activity_map();
+    let activities_of = |user_id: &str, listed: &[Activity]| -> Vec<Activity> {
+        if listed.is_empty() {
+            activities_by_user.get(user_id).cloned().unwrap_or_default()
+        } else {
+            listed.to_vec()
+        }
+    };
     let user = current_user();
 
-    let member_rows: Vec<MemberRow> = members
-        .iter()
-        .map(|m| {
-            let name = member_display_name(m);
-            let (avatar_url, fallback_char) = m
-                .user
-                .as_ref()
-                .map(|u| (avatar_url(u), display_name(u).chars().next().unwrap_or('?')))
-                .unwrap_or((None, '?'));
-            MemberRow {
-                name,
-                avatar_url,
-                fallback_char,
-            }
+    let total = list.items.len();
+    let first = (scroll_top() / ROW_HEIGHT_PX) as usize;
+    let last = ((scroll_top() + viewport_height()) / ROW_HEIGHT_PX) as usize;
+    let start = first.saturating_sub(OVERSCAN_ROWS).min(total);
+    let end = (last + OVERSCAN_ROWS + 1).min(total).max(start);
+    let rows: Vec<(usize, ListRow)> = (start..end)
+        .map(|i| {
+            let row = match &list.items[i] {
+                Some(MemberListItem::Group(g)) => ListRow::Header {
+                    label: group_label(&g.id, &roles),
+                    count: g.count,
+                },
+                Some(MemberListItem::Member(m)) => ListRow::Member {
+                    name: m.display_name().to_string(),
+                    color: role_color(&roles, &m.roles).map(|c| format!("#{:06x}", c)),
+                    avatar_url: avatar_url(&m.user),
+                    fallback_char: m.display_name().chars().next().unwrap_or('?'),
+                    status: m.status(),
+                    activities: activities_of(&m.user.id, m.activities()),
+                    member: Box::new(GuildMember {
+                        user: Some(m.user.clone()),
+                        nick: m.nick.clone(),
+                        roles: m.roles.clone(),
+                        avatar: None,
+                        joined_at: None,
+                    }),
+                },
+                None => ListRow::Placeholder,
+            };
+            (i, row)
         })
         .collect();
+    let top_spacer = start as f64 * ROW_HEIGHT_PX;
+    let bottom_spacer = (total - end) as f64 * ROW_HEIGHT_PX;
 
-    // When API returns empty (user token has no permission to list members), show at least "You".
-    let rows_to_show: Vec<MemberRow> = if member_rows.is_empty() {
-        user.as_ref()
-            .map(|u| {
-                let name = format!("{} (you)", display_name(u));
-                let (avatar_url, fallback_char) = (
-                    avatar_url(u),
-                    display_name(u).chars().next().unwrap_or('?'),
-                );
-                MemberRow {
-                    name,
-                    avatar_url,
-                    fallback_char,
-                }
-            })
-            .into_iter()
-            .collect()
-    } else {
-        member_rows
-    };
-
-    let show_api_note = members.is_empty() && user.is_some();
+    // Until the first SYNC arrives, show at least ourselves.
+    let own_row = user.as_ref().filter(|_| total == 0).map(|u| {
+        (
+            format!("{} (you)", display_name(u)),
+            avatar_url(u),
+            display_name(u).chars().next().unwrap_or('?'),
+            u.clone(),
+        )
+    });
+    let guild_id = (!list.guild_id.is_empty()).then(|| list.guild_id.clone());
+    let self_id = user.as_ref().map(|u| u.id.clone());
 
     rsx! {
+        for (u, member, activities, x, y) in user_card().into_iter().filter_map(|(m, a, x, y)| Some((m.user.clone()?, m, a, x, y))) {
+            UserCard {
+                key: "{u.id}",
+                x: x as i32,
+                y: y as i32,
+                anchor_right: true,
+                is_self: self_id.as_deref() == Some(u.id.as_str()),
+                guild_id: guild_id.clone(),
+                member: Some(member),
+                activities,
+                user: u,
+                guild_roles,
+                guilds,
+                user_profiles,
+                on_load_profile,
+                on_message: on_open_dm,
+                on_close: move |_| user_card.set(None),
+            }
+        }
         div {
             class: "custom-scroll",
             style: "
                 display: flex; flex-direction: column; flex: 1;
                 min-height: 0; overflow-y: auto; padding: 0.5rem 0;
             ",
+            onscroll: move |evt| {
+                let top = evt.scroll_top();
+                let height = evt.client_height() as f64;
+                scroll_top.set(top);
+                viewport_height.set(height);
+                let first = (top / ROW_HEIGHT_PX) as u32;
+                let last = ((top + height) / ROW_HEIGHT_PX) as u32;
+                let chunks = (first / RANGE_SIZE, last / RANGE_SIZE);
+                if chunks != requested() {
+                    requested.set(chunks);
+                    on_request_range.call((first, last));
+                }
+            },
             div {
                 style: "
                     padding: 0 0.75rem 0.5rem;
@@ -152,26 +274,72 @@ pub fn GuildMemberList(
                     color: #9ca3af; text-transform: uppercase;
                     letter-spacing: 0.05em;
                 ",
-                "Members — {rows_to_show.len()}"
-            }
-            if show_api_note {
-                p {
-                    style: "
-                        padding: 0 0.75rem 0.5rem;
-                        font-size: 0.7rem; color: #6b7280;
-                        margin: 0; line-height: 1.3;
-                    ",
-                    "Full list requires an iq < 1 developer"
+                if list.member_count > 0 {
+                    "Members — {list.online_count} online"
+                } else {
+                    "Members"
                 }
             }
-            for r in rows_to_show.iter() {
+            if let Some((name, avatar, fallback, u)) = own_row {
                 MemberRowView {
-                    name: r.name.clone(),
-                    avatar_url: r.avatar_url.clone(),
-                    fallback_char: r.fallback_char,
-
+                    name,
+                    avatar_url: avatar,
+                    fallback_char: fallback,
+                    status: PresenceStatus::Online,
+                    activity: None,
+                    on_click: move |(x, y): (f64, f64)| {
+                        let me = GuildMember {
+                            user: Some(u.clone()),
+                            nick: None,
+                            roles: Vec::new(),
+                            avatar: None,
+                            joined_at: None,
+                        };
+                        user_card.set(Some((me, Vec::new(), x, y)));
+                    },
+                }
+            }
+            div { style: "flex-shrink: 0; height: {top_spacer}px;" }
+            for (i, row) in rows.into_iter() {
+                match row {
+                    ListRow::Header { label, count } => rsx! {
+                        div {
+                            key: "{i}",
+                            style: "
+                                height: {ROW_HEIGHT_PX}px; box-sizing: border-box; flex-shrink: 0;
+                                display: flex; align-items: flex-end; padding: 0 0.75rem 0.35rem;
+                                font-size: 0.7rem; font-weight: 600; color: #6b7280;
+                                text-transform: uppercase; letter-spacing: 0.05em;
+                            ",
+                            "{label} — {count}"
+                        }
+                    },
+                    ListRow::Member { name, color, avatar_url, fallback_char, status, activities, member } => rsx! {
+                        MemberRowView {
+                            key: "{i}",
+                            name,
+                            color,
+                            avatar_url,
+                            fallback_char,
+                            status,
+                            activity: activity_line(&activities),
+                            on_click: move |(x, y): (f64, f64)| user_card.set(Some(((*member).clone(), activities.clone(), x, y))),
+                        }
+                    },
+                    ListRow::Placeholder => rsx! {
+                        div {
+                            key: "{i}",
+                            style: "
+                                height: {ROW_HEIGHT_PX}px; box-sizing: border-box; flex-shrink: 0;
+                                display: flex; align-items: center; gap: 0.5rem; padding: 0 0.75rem; margin: 0 0.25rem;
+                            ",
+                            div { style: "width: 1.75rem; height: 1.75rem; border-radius: 50%; background: rgba(255,255,255,0.06);" }
+                            div { style: "width: 6rem; height: 0.6rem; border-radius: 4px; background: rgba(255,255,255,0.06);" }
+                        }
+                    },
                 }
             }
+            div { style: "flex-shrink: 0; height: {bottom_spacer}px;" }
         }
     }
 }
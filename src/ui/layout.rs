@@ -1,17 +1,28 @@
 use dioxus::prelude::*;
 use std::collections::{HashMap, HashSet};
 
-use crate::http::{ApiGuild, DiscordUser, DmChannel, GuildChannel, GuildMember, Relationship};
-use crate::state::{AppSettings, Message, PresenceStatus};
+use crate::http::{
+    ApiGuild, DiscordUser, DmChannel, GroupDmEdit, GuildChannel, GuildMember, Relationship, RelationshipAction, Role,
+};
+use crate::member_list::MemberList;
+use crate::permissions::PermissionContext;
+use crate::gateway::VoiceStatus;
+use crate::voice_state::VoiceStates;
+use crate::state::{
+    Activity, AppSettings, Message, NotificationSettings, NotificationSettingsChange, PresenceStatus, ReadState,
+};
 
-use super::{ChannelList, GuildChannelList, GuildMemberList, MessageList, Sidebar};
+use super::{ChannelList, ForumView, FriendsPage, GroupDmPanel, GuildChannelList, GuildMemberList, MessageList, ProfileCache, Sidebar, ThreadPanel};
 
 #[component]
 pub fn Layout(
     guilds: Signal<Vec<ApiGuild>>,
     selected_guild_id: Signal<Option<String>>,
     guild_channels: Signal<Vec<GuildChannel>>,
-    guild_members: Signal<Vec<GuildMember>>,
+    member_list: Signal<MemberList>,
+    member_cache: Signal<HashMap<String, HashMap<String, GuildMember>>>,
+    guild_roles: Signal<Vec<Role>>,
+    user_profiles: Signal<ProfileCache>,
     friends: Signal<Vec<Relationship>>,
     dm_channels: Signal<Vec<DmChannel>>,
     messages: Signal<Vec<Message>>,
@@ -21,25 +32,95 @@ pub fn Layout(
     loading_older: Signal<bool>,
     loading_messages: Signal<bool>,
     settings: Signal<AppSettings>,
-    unread_counts: Signal<HashMap<String, u32>>,
+    read_states: Signal<HashMap<String, ReadState>>,
+    notification_settings: Signal<NotificationSettings>,
+    guild_permissions: Signal<Option<PermissionContext>>,
+    joined_threads: Signal<HashSet<String>>,
+    archived_threads: Signal<HashMap<String, Vec<GuildChannel>>>,
+    forum_previews: Signal<HashMap<String, Option<Message>>>,
     typing_users: Signal<HashMap<String, std::collections::HashMap<String, i64>>>,
+    unread_marker: Signal<Option<(String, String)>>,
     access_denied_channel_ids: Signal<HashSet<String>>,
     channel_error_display: Signal<Option<(String, String)>>,
     presence_map: Signal<HashMap<String, PresenceStatus>>,
+    activity_map: Signal<HashMap<String, Vec<Activity>>>,
     current_voice_channel_id: Signal<Option<String>>,
     current_voice_guild_id: Signal<Option<String>>,
+    voice_states: Signal<VoiceStates>,
+    speaking_users: Signal<HashSet<String>>,
+    voice_status: Signal<VoiceStatus>,
+    self_mute: Signal<bool>,
+    self_deaf: Signal<bool>,
     on_select_guild: EventHandler<Option<String>>,
     on_select_channel: EventHandler<Option<String>>,
+    on_open_thread: EventHandler<String>,
+    on_follow_thread: EventHandler<(String, bool)>,
+    on_load_archived_threads: EventHandler<String>,
+    on_create_thread: EventHandler<(String, Option<String>, String, bool)>,
+    on_load_forum_previews: EventHandler<Vec<String>>,
+    on_create_forum_post: EventHandler<(String, String, String, Vec<String>)>,
+    on_member_list_range: EventHandler<(u32, u32)>,
+    on_mark_read: EventHandler<String>,
+    on_mark_guild_read: EventHandler<String>,
+    on_update_notifications: EventHandler<NotificationSettingsChange>,
     on_join_voice: EventHandler<(Option<String>, String)>,
     on_leave_voice: EventHandler<()>,
+    /// (self_mute, self_deaf)
+    on_set_self_voice: EventHandler<(bool, bool)>,
     on_send_message: EventHandler<(String, String)>,
     on_load_older: EventHandler<(String, String)>,
     on_open_friend: EventHandler<String>,
+    on_relationship_action: EventHandler<(String, RelationshipAction)>,
+    on_send_friend_request: EventHandler<String>,
+    on_create_group_dm: EventHandler<Vec<String>>,
+    /// (channel ID, user ID, add)
+    on_group_recipient: EventHandler<(String, String, bool)>,
+    on_edit_group_dm: EventHandler<(String, GroupDmEdit)>,
+    on_leave_group_dm: EventHandler<String>,
+    /// A group DM change that failed before reaching the API (e.g. an unreadable icon file).
+    on_group_dm_error: EventHandler<String>,
+    /// (user ID, guild ID) whose profile the popout needs.
+    on_load_profile: EventHandler<(String, Option<String>)>,
     on_trigger_typing: EventHandler<String>,
     on_logout: EventHandler<()>,
     on_open_settings: EventHandler<()>,
 ) -> Element {
     let showing_dms = selected_guild_id().is_none();
+    // Parent channel whose threads are listed in the side panel, and the message a new thread starts from.
+    let mut thread_panel = use_signal(|| None::<String>);
+    let mut thread_draft = use_signal(|| None::<(String, String)>);
+    let mut group_panel_open = use_signal(|| false);
+    let group_panel_channel = selected_channel_id()
+        .filter(|_| showing_dms && group_panel_open())
+        .and_then(|sid| dm_channels().into_iter().find(|c| c.id == sid && c.is_group()));
+    let panel_channel = thread_panel()
+        .filter(|_| !showing_dms)
+        .and_then(|pid| guild_channels().into_iter().find(|c| c.id == pid));
+    let panel_key = format!(
+        "{}-{}",
+        panel_channel.as_ref().map(|c| c.id.as_str()).unwrap_or(""),
+        thread_draft().map(|(mid, _)| mid).unwrap_or_default()
+    );
+    let selected_forum = selected_channel_id().and_then(|sid| {
+        guild_channels()
+            .into_iter()
+            .find(|c| c.id == sid && c.r#type.is_forum())
+    });
+    let can_post_in_forum = selected_forum
+        .as_ref()
+        .and_then(|f| guild_permissions().map(|p| p.can_send(f, &guild_channels())))
+        .unwrap_or(true);
+    // Threads are listed for their parent, so toggling from inside a thread opens the parent's list.
+    let thread_parent_of_selected = move || -> Option<String> {
+        let sel = selected_channel_id()?;
+        let chs = guild_channels();
+        let ch = chs.iter().find(|c| c.id == sel)?;
+        if ch.r#type.is_thread() {
+            ch.parent_id.clone()
+        } else {
+            Some(ch.id.clone())
+        }
+    };
 
     rsx! {
         div {
@@ -47,8 +128,18 @@ pub fn Layout(
             Sidebar {
                 guilds,
                 selected_guild_id,
+                read_states,
+                notification_settings,
                 on_select_guild,
+                on_mark_guild_read,
+                on_update_notifications,
                 current_user,
+                current_voice_channel_id,
+                voice_status,
+                self_mute,
+                self_deaf,
+                on_set_self_voice,
+                on_leave_voice,
                 on_logout,
                 on_open_settings,
             }
@@ -60,15 +151,15 @@ pub fn Layout(
                         friends,
                         dm_channels,
                         selected_channel_id,
-                        unread_counts,
+                        read_states,
                         presence_map,
+                        activity_map,
                         on_select_channel,
                         on_open_friend,
-                        on_mark_read: move |id: String| {
-                            let mut counts = unread_counts();
-                            counts.insert(id, 0);
-                            unread_counts.set(counts);
-                        },
+                        on_create_group_dm,
+                        on_mark_read,
+                        notification_settings,
+                        on_update_notifications,
                     }
                 }
             } else {
@@ -86,8 +177,19 @@ pub fn Layout(
                         access_denied_channel_ids,
                         current_voice_channel_id,
                         current_voice_guild_id,
+                        voice_states,
+                        speaking_users,
+                        member_cache,
+                        settings,
+                        current_user,
                         selected_guild_id,
+                        read_states,
+                        notification_settings,
+                        guild_permissions,
+                        joined_threads,
                         on_select_channel,
+                        on_mark_read,
+                        on_update_notifications,
                         on_join_voice,
                         on_leave_voice,
                     }
@@ -95,13 +197,37 @@ pub fn Layout(
             }
             div {
                 style: "order: 2; flex: 1 1 0; min-width: 0; min-height: 0; overflow: hidden; display: flex; flex-direction: column;",
-                {if selected_channel_id().is_some() {
+                {if let Some(forum) = selected_forum {
+                    rsx! {
+                        // Keyed so each forum starts with its own sort, filter and draft.
+                        for forum in std::iter::once(forum) {
+                            ForumView {
+                                key: "{forum.id}",
+                                forum,
+                                guild_channels,
+                                archived_threads,
+                                forum_previews,
+                                can_post: can_post_in_forum,
+                                on_open_thread,
+                                on_load_archived: on_load_archived_threads,
+                                on_load_previews: on_load_forum_previews,
+                                on_create_post: on_create_forum_post,
+                            }
+                        }
+                    }
+                } else if selected_channel_id().is_some() {
                     rsx! {
                         MessageList {
                             messages,
                             selected_channel_id,
+                            selected_guild_id,
                             dm_channels,
                             guild_channels,
+                            member_cache,
+                            guild_roles,
+                            guilds,
+                            user_profiles,
+                            activity_map,
                             current_user,
                             current_voice_channel_id,
                             current_voice_guild_id,
@@ -109,13 +235,42 @@ pub fn Layout(
                             loading_older,
                             loading_messages,
                             typing_users,
+                            unread_marker,
                             access_denied_channel_ids,
                             channel_error_display,
+                            guild_permissions,
                             on_join_voice,
                             on_leave_voice,
                             on_send_message,
                             on_load_older,
                             on_trigger_typing,
+                            on_load_profile,
+                            on_open_dm: on_open_friend,
+                            on_toggle_threads: move |_| {
+                                let target = thread_parent_of_selected();
+                                if thread_panel() == target {
+                                    thread_panel.set(None);
+                                } else {
+                                    thread_panel.set(target);
+                                }
+                                thread_draft.set(None);
+                            },
+                            on_start_thread: move |draft: (String, String)| {
+                                thread_panel.set(thread_parent_of_selected());
+                                thread_draft.set(Some(draft));
+                            },
+                            on_toggle_group_panel: move |_| group_panel_open.set(!group_panel_open()),
+                        }
+                    }
+                } else if showing_dms {
+                    rsx! {
+                        FriendsPage {
+                            friends,
+                            presence_map,
+                            activity_map,
+                            on_open_friend,
+                            on_relationship_action,
+                            on_send_friend_request,
                         }
                     }
                 } else {
@@ -154,6 +309,61 @@ pub fn Layout(
                     }
                 }}
             }
+            for ch in group_panel_channel.into_iter() {
+                div {
+                    key: "group-{ch.id}",
+                    class: "glass-panel",
+                    style: "
+                        order: 2;
+                        flex: 0 0 16rem; display: flex; flex-direction: column;
+                        min-height: 0; overflow: hidden;
+                        border-left: 1px solid rgba(255,255,255,0.1);
+                    ",
+                    GroupDmPanel {
+                        channel: ch,
+                        current_user,
+                        friends,
+                        on_group_recipient,
+                        on_edit_group_dm,
+                        on_leave_group_dm,
+                        on_error: on_group_dm_error,
+                        on_close: move |_| group_panel_open.set(false),
+                    }
+                }
+            }
+            // Keyed so that picking another channel or message resets the panel's form.
+            for ch in panel_channel.into_iter() {
+                div {
+                    key: "{panel_key}",
+                    class: "glass-panel",
+                    style: "
+                        order: 2;
+                        flex: 0 0 18rem; display: flex; flex-direction: column;
+                        min-height: 0; overflow: hidden;
+                        border-left: 1px solid rgba(255,255,255,0.1);
+                    ",
+                    ThreadPanel {
+                        channel_id: ch.id.clone(),
+                        channel_name: ch.name.clone(),
+                        guild_channels,
+                        archived_threads,
+                        joined_threads,
+                        selected_channel_id,
+                        draft_from_message: thread_draft(),
+                        on_open_thread,
+                        on_follow_thread,
+                        on_load_archived: on_load_archived_threads,
+                        on_create_thread: move |req: (String, Option<String>, String, bool)| {
+                            thread_draft.set(None);
+                            on_create_thread.call(req);
+                        },
+                        on_close: move |_| {
+                            thread_panel.set(None);
+                            thread_draft.set(None);
+                        },
+                    }
+                }
+            }
             if !showing_dms {
                 div {
                     class: "glass-panel",
@@ -164,8 +374,15 @@ pub fn Layout(
                         border-right: 1px solid rgba(255,255,255,0.1);
                     ",
                     GuildMemberList {
-                        guild_members,
+                        member_list,
+                        guild_roles,
+                        guilds,
+                        user_profiles,
+                        activity_map,
                         current_user,
+                        on_request_range: on_member_list_range,
+                        on_load_profile,
+                        on_open_dm: on_open_friend,
                     }
                 }
             }
@@ -1,18 +1,23 @@
 use chrono::{DateTime, Datelike, Utc};
 use dioxus::prelude::*;
 
-use crate::http::{DiscordUser, DmChannel, GuildChannel};
-use crate::state::Message;
-use crate::ui::{MessageContextMenu, UserCard};
+use crate::http::{role_color, ApiGuild, ChannelType, DiscordUser, DmChannel, GuildChannel, GuildMember, Role};
+use crate::permissions::PermissionContext;
+use crate::state::{is_snowflake, snowflake_cmp, Activity, Message};
+use crate::ui::{MessageContextMenu, ProfileCache, UserCard};
+
+/// Consecutive messages by one author within this window collapse into compact rows.
+const GROUP_WINDOW_MINUTES: i64 = 7;
+
+fn parse_message_time(ts: Option<&str>) -> Option<DateTime<Utc>> {
+    let dt = DateTime::parse_from_rfc3339(ts?).ok()?;
+    Some(dt.with_timezone(&Utc))
+}
 
 fn format_message_time(ts: Option<&str>) -> String {
-    let Some(s) = ts else {
+    let Some(dt_utc) = parse_message_time(ts) else {
         return String::new();
     };
-    let Ok(dt) = DateTime::parse_from_rfc3339(s) else {
-        return String::new();
-    };
-    let dt_utc: DateTime<Utc> = dt.with_timezone(&Utc);
     let now = Utc::now();
     let same_day = dt_utc.date_naive() == now.date_naive();
     if same_day {
@@ -26,15 +31,100 @@ fn format_message_time(ts: Option<&str>) -> String {
     }
 }
 
-fn dm_channel_title(ch: &DmChannel) -> String {
-    if ch.recipients.is_empty() {
-        ch.name.as_deref().unwrap_or("DM").to_string()
+/// Label for the divider between days: "Today", "Yesterday" or a full date.
+fn format_day_label(dt: DateTime<Utc>) -> String {
+    let today = Utc::now().date_naive();
+    let day = dt.date_naive();
+    if day == today {
+        "Today".to_string()
+    } else if today.pred_opt() == Some(day) {
+        "Yesterday".to_string()
     } else {
-        ch.recipients
+        dt.format("%B %-d, %Y").to_string()
+    }
+}
+
+/// One rendered message plus the dividers that precede it.
+struct MessageEntry {
+    msg: Message,
+    compact: bool,
+    day_label: Option<String>,
+    show_new_divider: bool,
+}
+
+/// Slowmode interval as Discord shows it: "10s", "5m", "2h".
+fn format_slowmode(secs: u32) -> String {
+    if secs >= 3600 && secs % 3600 == 0 {
+        format!("{}h", secs / 3600)
+    } else if secs >= 60 && secs % 60 == 0 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// Group messages into compact runs and place day and "New" dividers.
+/// `last_read_id` is the read marker captured when the channel was opened.
+fn build_message_entries(
+    list: &[Message],
+    last_read_id: Option<&str>,
+    current_user_id: Option<&str>,
+) -> Vec<MessageEntry> {
+    let mut entries = Vec::with_capacity(list.len());
+    let mut new_divider_placed = false;
+    let mut prev: Option<(&Message, Option<DateTime<Utc>>)> = None;
+    for msg in list {
+        // Pending sends have no timestamp yet; treat them as "now".
+        let ts = parse_message_time(msg.timestamp.as_deref())
+            .or_else(|| msg.sending.then(Utc::now));
+        let day_label = match (prev.and_then(|(_, t)| t), ts) {
+            (Some(p), Some(t)) if p.date_naive() == t.date_naive() => None,
+            (_, Some(t)) => Some(format_day_label(t)),
+            _ => None,
+        };
+        let show_new_divider = !new_divider_placed
+            && !msg.sending
+            && current_user_id != Some(msg.author_id.as_str())
+            && last_read_id
+                .map(|read| snowflake_cmp(&msg.id, read).is_gt())
+                .unwrap_or(false);
+        if show_new_divider {
+            new_divider_placed = true;
+        }
+        let compact = day_label.is_none()
+            && !show_new_divider
+            && prev
+                .map(|(p, pt)| {
+                    p.author_id == msg.author_id
+                        && match (pt, ts) {
+                            (Some(pt), Some(t)) => {
+                                (t - pt).num_minutes().abs() < GROUP_WINDOW_MINUTES
+                            }
+                            _ => false,
+                        }
+                })
+                .unwrap_or(false);
+        entries.push(MessageEntry {
+            msg: msg.clone(),
+            compact,
+            day_label,
+            show_new_divider,
+        });
+        prev = Some((msg, ts));
+    }
+    entries
+}
+
+fn dm_channel_title(ch: &DmChannel) -> String {
+    match ch.name.as_deref().filter(|n| !n.is_empty()) {
+        Some(name) => name.to_string(),
+        None if ch.recipients.is_empty() => "DM".to_string(),
+        None => ch
+            .recipients
             .iter()
             .map(|u| u.global_name.as_deref().unwrap_or(u.username.as_str()).to_string())
             .collect::<Vec<_>>()
-            .join(", ")
+            .join(", "),
     }
 }
 
@@ -42,14 +132,24 @@ fn dm_channel_title(ch: &DmChannel) -> String {
 fn MessageRow(
     msg: Message,
     is_mine: bool,
+    compact: bool,
+    day_label: Option<String>,
+    show_new_divider: bool,
     author_user: Option<DiscordUser>,
+    /// Guild nickname, role color (CSS) and per-guild avatar URL of the author, once their member is cached.
+    author_nick: Option<String>,
+    author_color: Option<String>,
+    guild_avatar_url: Option<String>,
+    /// (user ID, display name) of the mentioned users we could resolve.
+    mention_names: Vec<(String, String)>,
     on_avatar_click: Option<EventHandler<(DiscordUser, f64, f64, bool)>>,
-    on_context_menu: Option<EventHandler<(f64, f64, String)>>,
+    on_context_menu: Option<EventHandler<(f64, f64, Message)>>,
 ) -> Element {
-    let author = msg
-        .author_username
+    let author = author_nick
         .as_deref()
+        .or(msg.author_username.as_deref())
         .unwrap_or(msg.author_id.as_str());
+    let author_color = author_color.as_deref().unwrap_or("#00fff5");
     let (bubble_style, bubble_margin) = if is_mine {
         (
             "max-width: 75%; margin-left: auto; margin-right: 0; padding: 0.5rem 0.75rem; \
@@ -66,23 +166,34 @@ fn MessageRow(
         )
     };
     let time_str = format_message_time(msg.timestamp.as_deref());
-    let row_style = if is_mine {
-        "display: flex; flex-direction: row-reverse; padding: 0.25rem 1rem; margin-bottom: 0.25rem;"
-    } else {
-        "display: flex; padding: 0.25rem 1rem; margin-bottom: 0.25rem;"
+    let row_style = match (is_mine, compact) {
+        (true, false) => "display: flex; flex-direction: row-reverse; padding: 0.25rem 1rem; margin-bottom: 0.25rem;",
+        (true, true) => "display: flex; flex-direction: row-reverse; padding: 0 1rem; margin-bottom: 0.15rem;",
+        (false, false) => "display: flex; padding: 0.25rem 1rem; margin-bottom: 0.25rem;",
+        (false, true) => "display: flex; padding: 0 1rem; margin-bottom: 0.15rem;",
+    };
+    // Grouped rows sit flush against the previous bubble, so square off the joining corner.
+    let bubble_radius = match (is_mine, compact) {
+        (true, true) => "border-top-right-radius: 4px;",
+        (false, true) => "border-top-left-radius: 4px;",
+        _ => "",
     };
     let content_html = if msg.sending {
         "Sending…".to_string()
     } else {
-        crate::ui::markdown::discord_markdown_to_html(&msg.content)
+        crate::ui::markdown::discord_markdown_to_html(&replace_user_mentions(&msg.content, &mention_names))
     };
-    let avatar_el = if let Some(ref u) = author_user {
-        let url = u.avatar.as_ref().map(|hash| {
-            let ext = if hash.starts_with("a_") { "gif" } else { "png" };
-            format!(
-                "https://cdn.discordapp.com/avatars/{}/{}.{}",
-                u.id, hash, ext
-            )
+    let avatar_el = if compact {
+        rsx! { div { style: "width: 2rem; flex-shrink: 0;" } }
+    } else if let Some(ref u) = author_user {
+        let url = guild_avatar_url.clone().or_else(|| {
+            u.avatar.as_ref().map(|hash| {
+                let ext = if hash.starts_with("a_") { "gif" } else { "png" };
+                format!(
+                    "https://cdn.discordapp.com/avatars/{}/{}.{}",
+                    u.id, hash, ext
+                )
+            })
         });
         let handler = on_avatar_click.clone();
         let user = u.clone();
@@ -123,6 +234,30 @@ fn MessageRow(
         rsx! { div { style: "width: 2rem; height: 2rem; flex-shrink: 0;" } }
     };
     rsx! {
+        if let Some(ref label) = day_label {
+            div {
+                style: "
+                    display: flex; align-items: center; gap: 0.75rem;
+                    padding: 0.75rem 1rem 0.5rem;
+                    font-size: 0.7rem; font-weight: 600; color: #6b7280;
+                ",
+                div { style: "flex: 1; height: 1px; background: rgba(255,255,255,0.08);" }
+                span { "{label}" }
+                div { style: "flex: 1; height: 1px; background: rgba(255,255,255,0.08);" }
+            }
+        }
+        if show_new_divider {
+            div {
+                style: "
+                    display: flex; align-items: center; gap: 0.5rem;
+                    padding: 0.25rem 1rem;
+                    font-size: 0.65rem; font-weight: 700; color: #f43f5e;
+                    text-transform: uppercase; letter-spacing: 0.05em;
+                ",
+                div { style: "flex: 1; height: 1px; background: rgba(244,63,94,0.6);" }
+                span { "New" }
+            }
+        }
         div {
             class: "anim-message-row",
             style: "{row_style}",
@@ -130,22 +265,25 @@ fn MessageRow(
                 evt.prevent_default();
                 if let Some(ref h) = on_context_menu {
                     let coords = evt.client_coordinates();
-                    h.call((coords.x, coords.y, msg.content.clone()));
+                    h.call((coords.x, coords.y, msg.clone()));
                 }
             },
             {avatar_el}
             div {
-                style: "{bubble_style} {bubble_margin}",
-                div {
-                    style: "display: flex; align-items: baseline; gap: 0.5rem; margin-bottom: 0.2rem;",
-                    span {
-                        style: "color: #00fff5; font-size: 0.75rem;",
-                        "{author}"
-                    }
-                    if !time_str.is_empty() {
+                style: "{bubble_style} {bubble_margin} {bubble_radius}",
+                title: if compact { "{time_str}" } else { "" },
+                if !compact {
+                    div {
+                        style: "display: flex; align-items: baseline; gap: 0.5rem; margin-bottom: 0.2rem;",
                         span {
-                            style: "color: #6b7280; font-size: 0.65rem;",
-                            "{time_str}"
+                            style: "color: {author_color}; font-size: 0.75rem;",
+                            "{author}"
+                        }
+                        if !time_str.is_empty() {
+                            span {
+                                style: "color: #6b7280; font-size: 0.65rem;",
+                                "{time_str}"
+                            }
                         }
                     }
                 }
@@ -163,25 +301,60 @@ fn resolve_author(
     current_user: &Option<DiscordUser>,
     channels: &[DmChannel],
     selected_id: &Option<String>,
+    member: Option<&GuildMember>,
 ) -> Option<DiscordUser> {
     if current_user.as_ref().map(|u| u.id.as_str()) == Some(msg.author_id.as_str()) {
         return current_user.clone();
     }
-    let ch = selected_id
-        .as_ref()
-        .and_then(|id| channels.iter().find(|c| c.id == *id))?;
-    ch.recipients
-        .iter()
-        .find(|u| u.id == msg.author_id)
-        .cloned()
+    if let Some(user) = member.and_then(|m| m.user.clone()) {
+        return Some(user);
+    }
+    match selected_id.as_ref().and_then(|id| channels.iter().find(|c| c.id == *id)) {
+        Some(ch) => ch.recipients.iter().find(|u| u.id == msg.author_id).cloned(),
+        // Guild message whose member has not arrived yet: the message itself names the author.
+        None => is_snowflake(&msg.author_id).then(|| DiscordUser {
+            id: msg.author_id.clone(),
+            username: msg.author_username.clone().unwrap_or_default(),
+            global_name: None,
+            discriminator: None,
+            avatar: msg.author_avatar.clone(),
+        }),
+    }
+}
+
+fn member_display_name(member: &GuildMember) -> Option<String> {
+    let user = member.user.as_ref();
+    member
+        .nick
+        .clone()
+        .filter(|n| !n.is_empty())
+        .or_else(|| user.and_then(|u| u.global_name.clone()))
+        .or_else(|| user.map(|u| u.username.clone()))
+}
+
+/// Show `<@id>` / `<@!id>` mentions as `@name` for the users in `names`.
+fn replace_user_mentions(content: &str, names: &[(String, String)]) -> String {
+    let mut out = content.to_string();
+    for (id, name) in names {
+        out = out
+            .replace(&format!("<@{}>", id), &format!("@{}", name))
+            .replace(&format!("<@!{}>", id), &format!("@{}", name));
+    }
+    out
 }
 
 #[component]
 pub fn MessageList(
     messages: Signal<Vec<Message>>,
     selected_channel_id: Signal<Option<String>>,
+    selected_guild_id: Signal<Option<String>>,
     dm_channels: Signal<Vec<DmChannel>>,
     guild_channels: Signal<Vec<GuildChannel>>,
+    member_cache: Signal<std::collections::HashMap<String, std::collections::HashMap<String, GuildMember>>>,
+    guild_roles: Signal<Vec<Role>>,
+    guilds: Signal<Vec<ApiGuild>>,
+    user_profiles: Signal<ProfileCache>,
+    activity_map: Signal<std::collections::HashMap<String, Vec<Activity>>>,
     current_user: Signal<Option<DiscordUser>>,
     current_voice_channel_id: Signal<Option<String>>,
     current_voice_guild_id: Signal<Option<String>>,
@@ -189,18 +362,25 @@ pub fn MessageList(
     loading_older: Signal<bool>,
     loading_messages: Signal<bool>,
     typing_users: Signal<std::collections::HashMap<String, std::collections::HashMap<String, i64>>>,
+    unread_marker: Signal<Option<(String, String)>>,
     access_denied_channel_ids: Signal<std::collections::HashSet<String>>,
     channel_error_display: Signal<Option<(String, String)>>,
+    guild_permissions: Signal<Option<PermissionContext>>,
     on_join_voice: EventHandler<(Option<String>, String)>,
     on_leave_voice: EventHandler<()>,
     on_send_message: EventHandler<(String, String)>,
     on_load_older: EventHandler<(String, String)>,
     on_trigger_typing: EventHandler<String>,
+    on_load_profile: EventHandler<(String, Option<String>)>,
+    on_open_dm: EventHandler<String>,
+    on_toggle_threads: EventHandler<()>,
+    on_toggle_group_panel: EventHandler<()>,
+    /// (message_id, content) to start a thread from.
+    on_start_thread: EventHandler<(String, String)>,
 ) -> Element {
     let mut user_card = use_signal(|| None::<(DiscordUser, f64, f64, bool)>);
-    let mut context_menu = use_signal(|| None::<(f64, f64, String)>);
+    let mut context_menu = use_signal(|| None::<(f64, f64, Message)>);
     let mut last_typing_trigger = use_signal(|| 0i64);
-    const GUILD_PRIVATE_THREAD: i32 = 12;
 
     let list = messages();
     let selected = selected_channel_id();
@@ -209,18 +389,31 @@ pub fn MessageList(
     let access_denied = access_denied_channel_ids();
     let channel_error = channel_error_display();
     let current_user_id: Option<String> = current_user().as_ref().map(|u| u.id.clone());
+    let guild_id = selected_guild_id();
+    let cache = member_cache();
+    let members = guild_id.as_ref().and_then(|gid| cache.get(gid));
+    let roles = guild_roles();
 
-    let is_private_channel = selected.as_ref().and_then(|sid| {
-        guild_chs.iter().find(|c| c.id == *sid).map(|c| {
-            c.r#type == GUILD_PRIVATE_THREAD || access_denied.contains(sid)
+    let perms = guild_permissions();
+    let selected_guild_channel = selected
+        .as_ref()
+        .and_then(|sid| guild_chs.iter().find(|c| c.id == *sid));
+    let is_private_channel = selected_guild_channel
+        .map(|c| {
+            access_denied.contains(&c.id)
+                || perms.as_ref().map(|p| !p.can_view(c, &guild_chs)).unwrap_or(false)
         })
-    }).unwrap_or(false);
+        .unwrap_or(false);
+    // DMs and channels whose permissions are not loaded yet stay writable; the API has the final word.
+    let can_post = selected_guild_channel
+        .and_then(|c| perms.as_ref().map(|p| p.can_send(c, &guild_chs)))
+        .unwrap_or(true);
 
     let private_debug: Option<(String, String, i32, String)> = (is_private_channel && selected.is_some()).then(|| {
         let sid = selected.as_ref().unwrap();
         let ch = guild_chs.iter().find(|c| c.id == *sid);
         let (name, ch_type) = ch
-            .map(|c| (c.name.clone(), c.r#type))
+            .map(|c| (c.name.clone(), c.r#type.to_api()))
             .unwrap_or_else(|| ("?".to_string(), 0));
         let err = channel_error
             .as_ref()
@@ -230,12 +423,32 @@ pub fn MessageList(
         (sid.clone(), name, ch_type, err)
     });
 
-    let header_icon = if is_private_channel { "🔒" } else { "💬" };
+    let header_icon = if is_private_channel {
+        "🔒"
+    } else {
+        selected_guild_channel.map(|c| c.r#type.icon()).unwrap_or("💬")
+    };
+    let header_topic = selected_guild_channel
+        .and_then(|c| c.topic.clone())
+        .filter(|t| !t.trim().is_empty());
+    let header_nsfw = selected_guild_channel.map(|c| c.nsfw).unwrap_or(false);
+    let slowmode = selected_guild_channel.map(|c| c.rate_limit_per_user).unwrap_or(0);
+    // Threads hang off text and announcement channels; inside a thread the button lists its siblings.
+    let shows_threads = selected_guild_channel
+        .map(|c| matches!(c.r#type, ChannelType::Text | ChannelType::Announcement) || c.r#type.is_thread())
+        .unwrap_or(false)
+        && !is_private_channel;
+    let can_start_threads = shows_threads && selected_guild_channel.map(|c| !c.r#type.is_thread()).unwrap_or(false);
+    let thread_parent_name = selected_guild_channel
+        .filter(|c| c.r#type.is_thread())
+        .and_then(|c| c.parent_id.as_ref())
+        .and_then(|pid| guild_chs.iter().find(|c| &c.id == pid))
+        .map(|p| p.name.clone());
     let header_title = if let Some(sel_id) = selected.as_ref() {
         if let Some(dm) = channels.iter().find(|c| c.id == *sel_id) {
             dm_channel_title(dm)
         } else if let Some(gc) = guild_chs.iter().find(|c| c.id == *sel_id) {
-            format!("#{}", gc.name)
+            gc.name.clone()
         } else {
             "Select a chat".to_string()
         }
@@ -247,14 +460,30 @@ pub fn MessageList(
         .as_ref()
         .and_then(|sel_id| channels.iter().find(|c| c.id == *sel_id))
         .is_some();
+    let is_group_selected = selected
+        .as_ref()
+        .and_then(|sel_id| channels.iter().find(|c| c.id == *sel_id))
+        .map(|c| c.is_group())
+        .unwrap_or(false);
     let dm_call_connected = is_dm_selected
         && selected.as_ref() == current_voice_channel_id().as_ref()
         && current_voice_guild_id().is_none();
 
     let mut draft = use_signal(|| String::new());
-    let can_send = selected.is_some() && !draft().trim().is_empty();
+    let can_send = can_post && selected.is_some() && !draft().trim().is_empty();
+    let composer_placeholder = if !can_post {
+        "You do not have permission to send messages in this channel".to_string()
+    } else if slowmode > 0 {
+        format!("Message (slowmode: {})", format_slowmode(slowmode))
+    } else {
+        "Message".to_string()
+    };
 
     let load_older_visible = has_more_older() && !list.is_empty() && selected.is_some();
+    let last_read_id = unread_marker()
+        .filter(|(cid, _)| selected.as_ref() == Some(cid))
+        .map(|(_, mid)| mid);
+    let entries = build_message_entries(&list, last_read_id.as_deref(), current_user_id.as_deref());
     let loading = loading_older();
     let loading_msgs = loading_messages();
     let typing = selected
@@ -267,6 +496,9 @@ pub fn MessageList(
         .filter(|(_, &expiry)| expiry > now)
         .filter(|(uid, _)| Some(uid.as_str()) != current_user_id.as_deref())
         .filter_map(|(uid, _)| {
+            if let Some(name) = members.and_then(|m| m.get(uid)).and_then(member_display_name) {
+                return Some(name);
+            }
             let ch = selected
                 .as_ref()
                 .and_then(|cid| channels.iter().find(|c| c.id == *cid))?;
@@ -403,17 +635,34 @@ pub fn MessageList(
                     }
                 }
             }
-            for msg in list.iter() {
+            for (entry, member) in entries.iter().map(|e| (e, members.and_then(|m| m.get(&e.msg.author_id)))) {
                 MessageRow {
-                    key: "{msg.id}",
-                    msg: msg.clone(),
-                    is_mine: current_user_id.as_deref() == Some(msg.author_id.as_str()),
-                    author_user: resolve_author(msg, &current_user(), &channels, &selected),
+                    key: "{entry.msg.id}",
+                    msg: entry.msg.clone(),
+                    is_mine: current_user_id.as_deref() == Some(entry.msg.author_id.as_str()),
+                    compact: entry.compact,
+                    day_label: entry.day_label.clone(),
+                    show_new_divider: entry.show_new_divider,
+                    author_user: resolve_author(&entry.msg, &current_user(), &channels, &selected, member),
+                    author_nick: member.and_then(|m| m.nick.clone()).filter(|n| !n.is_empty()),
+                    author_color: member
+                        .and_then(|m| role_color(&roles, &m.roles))
+                        .map(|c| format!("#{:06x}", c)),
+                    guild_avatar_url: member.zip(guild_id.as_ref()).and_then(|(m, gid)| m.guild_avatar_url(gid)),
+                    mention_names: entry
+                        .msg
+                        .mention_ids
+                        .iter()
+                        .filter_map(|id| {
+                            let name = members.and_then(|m| m.get(id)).and_then(member_display_name)?;
+                            Some((id.clone(), name))
+                        })
+                        .collect::<Vec<_>>(),
                     on_avatar_click: Some(EventHandler::new(move |(u, x, y, is_mine): (DiscordUser, f64, f64, bool)| {
                         user_card.set(Some((u.clone(), x, y, is_mine)));
                     })),
-                    on_context_menu: Some(EventHandler::new(move |(x, y, content): (f64, f64, String)| {
-                        context_menu.set(Some((x, y, content)));
+                    on_context_menu: Some(EventHandler::new(move |(x, y, msg): (f64, f64, Message)| {
+                        context_menu.set(Some((x, y, msg)));
                     })),
                 }
             }
@@ -421,20 +670,34 @@ pub fn MessageList(
     };
 
     rsx! {
-        if let Some((ref u, x, y, anchor_right)) = user_card() {
+        for (u, x, y, anchor_right) in user_card().into_iter() {
             UserCard {
-                user: u.clone(),
+                key: "{u.id}",
                 x: x as i32,
                 y: y as i32,
                 anchor_right,
+                is_self: current_user_id.as_deref() == Some(u.id.as_str()),
+                guild_id: guild_id.clone(),
+                member: members.and_then(|m| m.get(&u.id)).cloned(),
+                activities: activity_map().get(&u.id).cloned().unwrap_or_default(),
+                user: u,
+                guild_roles,
+                guilds,
+                user_profiles,
+                on_load_profile,
+                on_message: on_open_dm,
                 on_close: move |_| user_card.set(None),
             }
         }
-        if let Some((x, y, ref content)) = context_menu() {
+        if let Some((x, y, ref msg)) = context_menu() {
             MessageContextMenu {
                 x,
                 y,
-                content: content.clone(),
+                content: msg.content.clone(),
+                on_create_thread: (can_start_threads && is_snowflake(&msg.id)).then(|| {
+                    let (id, content) = (msg.id.clone(), msg.content.clone());
+                    EventHandler::new(move |_| on_start_thread.call((id.clone(), content.clone())))
+                }),
                 on_close: move |_| context_menu.set(None),
             }
         }
@@ -443,9 +706,68 @@ pub fn MessageList(
             header {
                 style: "flex-shrink: 0; padding: 0.75rem 1rem; border-bottom: 1px solid rgba(255,255,255,0.1); display: flex; align-items: center; gap: 0.5rem;",
                 span { style: "color: #00fff5;", "{header_icon}" }
-                span { style: "font-weight: 500;", "{header_title}" }
+                if let Some(ref parent) = thread_parent_name {
+                    span { style: "color: #6b7280; flex-shrink: 0;", "{parent} ›" }
+                }
+                span { style: "font-weight: 500; flex-shrink: 0;", "{header_title}" }
+                if header_nsfw {
+                    span {
+                        style: "
+                            font-size: 0.65rem; font-weight: 700; color: #fca5a5;
+                            border: 1px solid rgba(239,68,68,0.5); border-radius: 4px;
+                            padding: 0.05rem 0.3rem; flex-shrink: 0;
+                        ",
+                        "NSFW"
+                    }
+                }
+                if let Some(ref topic) = header_topic {
+                    span { style: "width: 1px; height: 1.25rem; background: rgba(255,255,255,0.15); flex-shrink: 0;" }
+                    span {
+                        style: "
+                            font-size: 0.85rem; color: #9ca3af; min-width: 0;
+                            overflow: hidden; text-overflow: ellipsis; white-space: nowrap;
+                        ",
+                        title: "{topic}",
+                        "{topic}"
+                    }
+                }
+                if shows_threads {
+                    button {
+                        class: "anim-btn",
+                        style: "
+                            margin-left: auto; flex-shrink: 0;
+                            padding: 0.35rem 0.65rem;
+                            border-radius: 8px;
+                            border: 1px solid rgba(255,255,255,0.12);
+                            background: rgba(255,255,255,0.06);
+                            color: #e5e7eb;
+                            font-size: 0.85rem;
+                            cursor: pointer;
+                        ",
+                        title: "Threads",
+                        onclick: move |_| on_toggle_threads.call(()),
+                        "🧵 Threads"
+                    }
+                }
                 if is_dm_selected {
                     div { style: "margin-left: auto; display: flex; align-items: center; gap: 0.5rem;",
+                        if is_group_selected {
+                            button {
+                                class: "anim-btn",
+                                style: "
+                                    padding: 0.35rem 0.65rem;
+                                    border-radius: 8px;
+                                    border: 1px solid rgba(255,255,255,0.12);
+                                    background: rgba(255,255,255,0.06);
+                                    color: #e5e7eb;
+                                    font-size: 0.85rem;
+                                    cursor: pointer;
+                                ",
+                                title: "Group settings and members",
+                                onclick: move |_| on_toggle_group_panel.call(()),
+                                "👥 Members"
+                            }
+                        }
                         button {
                             class: "anim-btn",
                             style: "
@@ -502,7 +824,8 @@ pub fn MessageList(
                             background: rgba(255,255,255,0.06); border: 1px solid rgba(255,255,255,0.12);
                             border-radius: 8px; color: #e5e7eb; outline: none;
                         ",
-                        placeholder: "Message",
+                        placeholder: "{composer_placeholder}",
+                        disabled: !can_post,
                         value: "{draft()}",
                         oninput: move |evt| {
                             draft.set(evt.value());
@@ -515,7 +838,7 @@ pub fn MessageList(
                             }
                         },
                         onkeydown: move |evt| {
-                            if evt.key() == Key::Enter {
+                            if evt.key() == Key::Enter && can_post {
                                 evt.prevent_default();
                                 if let Some(cid) = selected_channel_id().as_ref() {
                                     let text = draft();
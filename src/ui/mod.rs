@@ -1,5 +1,8 @@
 mod channel_list;
 mod context_menu;
+mod forum_view;
+mod friends_page;
+mod group_dm;
 mod guild_channel_list;
 mod guild_member_list;
 mod layout;
@@ -9,20 +12,29 @@ mod message_list;
 mod metrics_overlay;
 mod settings_modal;
 mod sidebar;
+mod thread_panel;
 mod toast;
 mod user_card;
+#[cfg(feature = "voice")]
+mod voice_settings;
 mod welcome_modal;
 
 pub use channel_list::ChannelList;
+pub use group_dm::{GroupDmPanel, NewGroupDmPicker};
 pub use guild_channel_list::GuildChannelList;
 pub use guild_member_list::GuildMemberList;
-pub use context_menu::{ChannelContextMenu, MessageContextMenu};
+pub use forum_view::ForumView;
+pub use friends_page::FriendsPage;
+pub use context_menu::{ChannelContextMenu, GuildContextMenu, MessageContextMenu, VoiceUserContextMenu};
 pub use layout::Layout;
 pub use login_form::LoginForm;
 pub use message_list::MessageList;
 pub use metrics_overlay::MetricsOverlay;
 pub use settings_modal::SettingsModal;
 pub use sidebar::Sidebar;
+pub use thread_panel::ThreadPanel;
 pub use toast::ToastContainer;
-pub use user_card::UserCard;
+pub use user_card::{ProfileCache, UserCard};
+#[cfg(feature = "voice")]
+pub use voice_settings::VoiceInputSettings;
 pub use welcome_modal::WelcomeModal;
@@ -1,10 +1,14 @@
 use dioxus::prelude::*;
+use std::collections::BTreeMap;
 
 use crate::http::DiscordUser;
-use crate::state::{save_settings, AppSettings, PresenceStatus};
+use crate::rich_presence::IpcClient;
+use crate::state::{save_settings, AppSettings, CustomStatus, OwnPresence, PresenceStatus};
 use crate::updater;
 #[cfg(feature = "voice")]
 use crate::voice_audio;
+#[cfg(feature = "voice")]
+use crate::ui::VoiceInputSettings;
 
 fn avatar_url(user: &DiscordUser) -> Option<String> {
     user.avatar.as_ref().map(|hash| {
@@ -25,20 +29,47 @@ fn display_name(user: &DiscordUser) -> &str {
 #[derive(Clone, Copy, PartialEq)]
 enum SettingsTab {
     General,
+    Notifications,
     #[cfg(feature = "voice")]
     Voice,
     Appearance,
     About,
 }
 
+/// Custom status expiry choices: (value, label). "today" means local midnight.
+const STATUS_EXPIRY_OPTIONS: [(&str, &str); 5] = [
+    ("never", "Don't clear"),
+    ("30m", "30 minutes"),
+    ("1h", "1 hour"),
+    ("4h", "4 hours"),
+    ("today", "Today"),
+];
+
+fn status_expiry_timestamp(choice: &str) -> Option<i64> {
+    let now = chrono::Local::now();
+    let secs = match choice {
+        "30m" => 30 * 60,
+        "1h" => 60 * 60,
+        "4h" => 4 * 60 * 60,
+        "today" => {
+            let midnight = now.date_naive().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            return midnight.and_local_timezone(chrono::Local).single().map(|t| t.timestamp());
+        }
+        _ => return None,
+    };
+    Some(now.timestamp() + secs)
+}
+
 #[component]
 pub fn SettingsModal(
     open: Signal<bool>,
     settings: Signal<AppSettings>,
     current_user: Signal<Option<DiscordUser>>,
+    /// Applications connected to the Rich Presence socket.
+    ipc_clients: Signal<BTreeMap<u64, IpcClient>>,
     on_close: EventHandler<()>,
     on_show_toast: EventHandler<String>,
-    on_change_presence: EventHandler<PresenceStatus>,
+    on_change_presence: EventHandler<OwnPresence>,
 ) -> Element {
     let mut closing = use_signal(|| false);
 
@@ -56,6 +87,10 @@ pub fn SettingsModal(
 
     let mut active_tab = use_signal(|| SettingsTab::General);
     let mut update_available = use_signal(|| None::<String>);
+    let saved_status = settings.peek().custom_status.clone().unwrap_or_default();
+    let mut status_emoji = use_signal(|| saved_status.emoji.clone().unwrap_or_default());
+    let mut status_text = use_signal(|| saved_status.text.clone());
+    let mut status_expiry = use_signal(|| "never".to_string());
     #[cfg(feature = "voice")]
     let mut input_devices = use_signal(|| Vec::<String>::new());
     #[cfg(feature = "voice")]
@@ -73,6 +108,19 @@ pub fn SettingsModal(
     });
     let user = current_user();
     let is_closing = closing();
+    let rpc_apps: Vec<(u64, String, Option<String>, Option<String>)> = ipc_clients()
+        .iter()
+        .map(|(conn, c)| {
+            let activity = c.presence_activity().map(|a| a.summary());
+            (*conn, c.display_name().to_string(), c.icon_url(), activity)
+        })
+        .collect();
+    let status_clears_at = s
+        .custom_status
+        .as_ref()
+        .and_then(|c| c.expires_at)
+        .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+        .map(|t| t.with_timezone(&chrono::Local).format("%H:%M").to_string());
 
     #[cfg(feature = "voice")]
     let voice_tab_btn = rsx! {
@@ -220,6 +268,26 @@ pub fn SettingsModal(
                             onclick: move |_| active_tab.set(SettingsTab::General),
                             "General"
                         }
+                        button {
+                            class: "settings-tab anim-btn",
+                            style: if active_tab() == SettingsTab::Notifications {
+                                "
+                                    width: 100%; padding: 0.5rem 1rem; text-align: left;
+                                    background: rgba(0,255,245,0.1); color: #00fff5;
+                                    border: none; font-size: 0.9375rem; cursor: pointer;
+                                    border-left: 2px solid #00fff5;
+                                "
+                            } else {
+                                "
+                                    width: 100%; padding: 0.5rem 1rem; text-align: left;
+                                    background: transparent; color: #9ca3af;
+                                    border: none; font-size: 0.9375rem; cursor: pointer;
+                                    border-left: 2px solid transparent;
+                                "
+                            },
+                            onclick: move |_| active_tab.set(SettingsTab::Notifications),
+                            "Notifications"
+                        }
                         button {
                             class: "settings-tab anim-btn",
                             style: if active_tab() == SettingsTab::Appearance {
@@ -359,7 +427,7 @@ pub fn SettingsModal(
                                                     s.presence = PresenceStatus::Online;
                                                     settings.set(s.clone());
                                                     let _ = save_settings(&s);
-                                                    on_change_presence.call(PresenceStatus::Online);
+                                                    on_change_presence.call(s.own_presence());
                                                 },
                                                 "Online"
                                             }
@@ -389,7 +457,7 @@ pub fn SettingsModal(
                                                     s.presence = PresenceStatus::Idle;
                                                     settings.set(s.clone());
                                                     let _ = save_settings(&s);
-                                                    on_change_presence.call(PresenceStatus::Idle);
+                                                    on_change_presence.call(s.own_presence());
                                                 },
                                                 "Idle"
                                             }
@@ -419,7 +487,7 @@ pub fn SettingsModal(
                                                     s.presence = PresenceStatus::DoNotDisturb;
                                                     settings.set(s.clone());
                                                     let _ = save_settings(&s);
-                                                    on_change_presence.call(PresenceStatus::DoNotDisturb);
+                                                    on_change_presence.call(s.own_presence());
                                                 },
                                                 "Do Not Disturb"
                                             }
@@ -449,11 +517,134 @@ pub fn SettingsModal(
                                                     s.presence = PresenceStatus::Invisible;
                                                     settings.set(s.clone());
                                                     let _ = save_settings(&s);
-                                                    on_change_presence.call(PresenceStatus::Invisible);
+                                                    on_change_presence.call(s.own_presence());
                                                 },
                                                 "Invisible"
                                             }
                                         }
+                                        span {
+                                            style: "color: #9ca3af; font-size: 0.875rem; margin-top: 0.5rem;",
+                                            "Custom status"
+                                        }
+                                        div {
+                                            style: "display: flex; flex-wrap: wrap; align-items: center; gap: 0.5rem;",
+                                            input {
+                                                style: "
+                                                    width: 3rem; padding: 0.4rem; text-align: center; font-size: 0.9375rem;
+                                                    background: rgba(0,0,0,0.3); border: 1px solid rgba(255,255,255,0.15);
+                                                    border-radius: 6px; color: #e5e7eb;
+                                                ",
+                                                placeholder: "🙂",
+                                                value: "{status_emoji}",
+                                                oninput: move |evt| status_emoji.set(evt.value()),
+                                            }
+                                            input {
+                                                style: "
+                                                    flex: 1; min-width: 10rem; padding: 0.4rem 0.6rem; font-size: 0.9375rem;
+                                                    background: rgba(0,0,0,0.3); border: 1px solid rgba(255,255,255,0.15);
+                                                    border-radius: 6px; color: #e5e7eb;
+                                                ",
+                                                maxlength: "128",
+                                                placeholder: "What's happening?",
+                                                value: "{status_text}",
+                                                oninput: move |evt| status_text.set(evt.value()),
+                                            }
+                                            select {
+                                                style: "
+                                                    padding: 0.4rem 0.6rem; font-size: 0.875rem;
+                                                    background: rgba(0,0,0,0.3); border: 1px solid rgba(255,255,255,0.15);
+                                                    border-radius: 6px; color: #e5e7eb;
+                                                ",
+                                                value: "{status_expiry}",
+                                                oninput: move |evt| status_expiry.set(evt.value()),
+                                                for (value, label) in STATUS_EXPIRY_OPTIONS {
+                                                    option { value: "{value}", "{label}" }
+                                                }
+                                            }
+                                        }
+                                        div {
+                                            style: "display: flex; align-items: center; gap: 0.5rem;",
+                                            button {
+                                                class: "anim-btn",
+                                                style: "
+                                                    padding: 0.25rem 0.75rem; font-size: 0.8125rem;
+                                                    border-radius: 6px; cursor: pointer;
+                                                    border: 1px solid rgba(0,255,245,0.4);
+                                                    background: rgba(0,255,245,0.12); color: #00fff5;
+                                                ",
+                                                disabled: status_text().trim().is_empty() && status_emoji().trim().is_empty(),
+                                                onclick: move |_| {
+                                                    let emoji = status_emoji().trim().to_string();
+                                                    let mut s = settings();
+                                                    s.custom_status = Some(CustomStatus {
+                                                        text: status_text().trim().to_string(),
+                                                        emoji: (!emoji.is_empty()).then_some(emoji),
+                                                        expires_at: status_expiry_timestamp(&status_expiry()),
+                                                    });
+                                                    settings.set(s.clone());
+                                                    let _ = save_settings(&s);
+                                                    on_change_presence.call(s.own_presence());
+                                                    on_show_toast.call("Custom status set".to_string());
+                                                },
+                                                "Set status"
+                                            }
+                                            if s.custom_status.is_some() {
+                                                button {
+                                                    class: "anim-btn",
+                                                    style: "
+                                                        padding: 0.25rem 0.75rem; font-size: 0.8125rem;
+                                                        border-radius: 6px; cursor: pointer;
+                                                        border: 1px solid rgba(255,255,255,0.12);
+                                                        background: transparent; color: #9ca3af;
+                                                    ",
+                                                    onclick: move |_| {
+                                                        let mut s = settings();
+                                                        s.custom_status = None;
+                                                        settings.set(s.clone());
+                                                        let _ = save_settings(&s);
+                                                        status_emoji.set(String::new());
+                                                        status_text.set(String::new());
+                                                        on_change_presence.call(s.own_presence());
+                                                    },
+                                                    "Clear"
+                                                }
+                                            }
+                                            if let Some(ref at) = status_clears_at {
+                                                span { style: "color: #6b7280; font-size: 0.8125rem;", "Clears at {at}" }
+                                            }
+                                        }
+                                        span {
+                                            style: "color: #9ca3af; font-size: 0.875rem; margin-top: 0.5rem;",
+                                            "Rich Presence"
+                                        }
+                                        if rpc_apps.is_empty() {
+                                            span {
+                                                style: "color: #6b7280; font-size: 0.8125rem;",
+                                                "No applications connected. Games and tools that support Discord Rich Presence show up here while running."
+                                            }
+                                        }
+                                        for (conn, name, icon, activity) in rpc_apps.into_iter() {
+                                            div {
+                                                key: "{conn}",
+                                                style: "display: flex; align-items: center; gap: 0.6rem;",
+                                                if let Some(url) = icon {
+                                                    img { src: "{url}", alt: "", style: "width: 1.75rem; height: 1.75rem; border-radius: 6px;" }
+                                                } else {
+                                                    div {
+                                                        style: "width: 1.75rem; height: 1.75rem; border-radius: 6px; background: rgba(0,255,245,0.15); display: flex; align-items: center; justify-content: center;",
+                                                        "🎮"
+                                                    }
+                                                }
+                                                div {
+                                                    style: "display: flex; flex-direction: column; min-width: 0;",
+                                                    span { style: "color: #e5e7eb; font-size: 0.875rem;", "{name}" }
+                                                    span {
+                                                        style: "color: #6b7280; font-size: 0.75rem; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
+                                                        {activity.unwrap_or_else(|| "Connected, no activity".to_string())}
+                                                    }
+                                                }
+                                            }
+                                        }
                                         label {
                                             style: "display: flex; align-items: center; gap: 0.75rem; cursor: pointer;",
                                             input {
@@ -557,6 +748,68 @@ pub fn SettingsModal(
                                 }
                             }
                         },
+                        SettingsTab::Notifications => {
+                            let keywords = s.notification_keywords.join(", ");
+                            rsx! {
+                                div {
+                                    style: "padding: 1.5rem;",
+                                    h3 {
+                                        style: "margin: 0 0 1rem 0; font-size: 1rem; color: #9ca3af;",
+                                        "Desktop notifications"
+                                    }
+                                    div {
+                                        style: "display: flex; flex-direction: column; gap: 1rem;",
+                                        label {
+                                            style: "display: flex; align-items: center; gap: 0.75rem; cursor: pointer;",
+                                            input {
+                                                r#type: "checkbox",
+                                                checked: "{s.notifications_enabled}",
+                                                oninput: move |evt| {
+                                                    let mut s = settings();
+                                                    s.notifications_enabled = evt.checked();
+                                                    settings.set(s.clone());
+                                                    let _ = save_settings(&s);
+                                                },
+                                            }
+                                            span { style: "color: #e5e7eb; font-size: 0.9375rem;", "Enable desktop notifications" }
+                                        }
+                                        p {
+                                            style: "margin: 0; color: #6b7280; font-size: 0.8125rem; line-height: 1.4;",
+                                            "Follows each server's notification settings and mutes. Nothing is shown while your status is Do Not Disturb or while you are reading the channel."
+                                        }
+                                        div {
+                                            style: "display: flex; flex-direction: column; gap: 0.5rem;",
+                                            label {
+                                                style: "color: #9ca3af; font-size: 0.875rem;",
+                                                "Keywords (comma separated, notify like a mention)"
+                                            }
+                                            input {
+                                                r#type: "text",
+                                                style: "
+                                                    padding: 0.5rem 0.75rem; font-size: 0.9375rem;
+                                                    background: rgba(0,0,0,0.3); border: 1px solid rgba(255,255,255,0.15);
+                                                    border-radius: 6px; color: #e5e7eb;
+                                                    max-width: 20rem;
+                                                ",
+                                                placeholder: "e.g. release, deploy",
+                                                value: "{keywords}",
+                                                onchange: move |evt| {
+                                                    let mut s = settings();
+                                                    s.notification_keywords = evt
+                                                        .value()
+                                                        .split(',')
+                                                        .map(|k| k.trim().to_string())
+                                                        .filter(|k| !k.is_empty())
+                                                        .collect();
+                                                    settings.set(s.clone());
+                                                    let _ = save_settings(&s);
+                                                },
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        },
                         #[cfg(feature = "voice")]
                         SettingsTab::Voice => {
                             let input_devs = input_devices();
@@ -640,7 +893,30 @@ pub fn SettingsModal(
                                                 }
                                             }
                                         }
+                                        button {
+                                            class: "anim-btn",
+                                            style: "
+                                                align-self: flex-start;
+                                                padding: 0.375rem 0.75rem; font-size: 0.875rem;
+                                                background: rgba(0,255,245,0.15); border: 1px solid rgba(0,255,245,0.4);
+                                                border-radius: 6px; color: #00fff5; cursor: pointer;
+                                            ",
+                                            onclick: move |_| {
+                                                spawn(async move {
+                                                    let lists = tokio::task::spawn_blocking(|| {
+                                                        (voice_audio::list_input_devices(), voice_audio::list_output_devices())
+                                                    })
+                                                    .await;
+                                                    if let Ok((input, output)) = lists {
+                                                        input_devices.set(input);
+                                                        output_devices.set(output);
+                                                    }
+                                                });
+                                            },
+                                            "Refresh devices"
+                                        }
                                     }
+                                    VoiceInputSettings { settings }
                                 }
                             }
                         },
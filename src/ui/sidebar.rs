@@ -1,6 +1,9 @@
 use dioxus::prelude::*;
 
+use crate::gateway::VoiceStatus;
 use crate::http::{ApiGuild, DiscordUser};
+use crate::state::{NotificationSettings, NotificationSettingsChange, ReadState};
+use crate::ui::GuildContextMenu;
 
 /// Logo as base64 data URL — works with both cargo run and dx serve
 fn logo_src() -> &'static str {
@@ -46,6 +49,9 @@ struct GuildButtonEntry {
     border: &'static str,
     letter: char,
     icon_url: Option<String>,
+    unread: bool,
+    muted: bool,
+    mention_count: u32,
 }
 
 #[component]
@@ -56,8 +62,14 @@ fn GuildButton(
     border: &'static str,
     letter: char,
     icon_url: Option<String>,
+    unread: bool,
+    muted: bool,
+    mention_count: u32,
     on_select_guild: EventHandler<Option<String>>,
+    on_context: EventHandler<(f64, f64, String)>,
 ) -> Element {
+    let opacity = if muted { "0.5" } else { "1" };
+    let context_id = gid.clone();
     let content = icon_url
         .as_ref()
         .map(|url| {
@@ -91,10 +103,130 @@ fn GuildButton(
                 border: 1px solid {border};
                 color: #d1d5db; cursor: pointer; font-size: 0.75rem;
                 display: flex; align-items: center; justify-content: center;
+                position: relative; opacity: {opacity};
             ",
             title: "{name}",
             onclick: move |_| on_select_guild.call(Some(gid.clone())),
+            oncontextmenu: move |evt| {
+                evt.prevent_default();
+                let coords = evt.client_coordinates();
+                on_context.call((coords.x, coords.y, context_id.clone()));
+            },
             {content}
+            if unread {
+                span {
+                    style: "
+                        position: absolute; left: -0.7rem; top: 50%;
+                        width: 4px; height: 8px; margin-top: -4px;
+                        border-radius: 0 4px 4px 0; background: #f3f4f6;
+                    ",
+                }
+            }
+            if mention_count > 0 {
+                MentionBadge { count: mention_count }
+            }
+        }
+    }
+}
+
+/// Red mention count in the bottom-right corner of a round sidebar button.
+#[component]
+fn MentionBadge(count: u32) -> Element {
+    rsx! {
+        span {
+            style: "
+                position: absolute; right: -0.2rem; bottom: -0.2rem;
+                min-width: 1rem; height: 1rem; padding: 0 0.25rem;
+                border-radius: 9999px; background: #ef4444; color: #fff;
+                border: 2px solid #0b0f14;
+                font-size: 0.6rem; font-weight: 700;
+                display: flex; align-items: center; justify-content: center;
+            ",
+            "{count.min(99)}"
+        }
+    }
+}
+
+/// Mute / deafen toggles, plus connection state and disconnect while in a call.
+#[component]
+fn VoicePanel(
+    in_call: bool,
+    status: VoiceStatus,
+    self_mute: bool,
+    self_deaf: bool,
+    on_set_self_voice: EventHandler<(bool, bool)>,
+    on_leave_voice: EventHandler<()>,
+) -> Element {
+    let button_style = |active: bool| {
+        format!(
+            "
+                width: 2.25rem; height: 2.25rem; border-radius: 50%;
+                border: 1px solid {};
+                background: {};
+                color: #e5e7eb; cursor: pointer; font-size: 0.9rem;
+                display: flex; align-items: center; justify-content: center;
+            ",
+            if active { "rgba(239,68,68,0.6)" } else { "rgba(255,255,255,0.15)" },
+            if active { "rgba(239,68,68,0.2)" } else { "rgba(255,255,255,0.06)" },
+        )
+    };
+    // Deafen implies mute; un-muting while deafened also undeafens.
+    let muted = self_mute || self_deaf;
+    let (status_label, status_color, status_title) = match status {
+        VoiceStatus::Connecting => ("Connecting".to_string(), "#facc15", "Connecting to voice…".to_string()),
+        VoiceStatus::Connected { latency_ms: Some(ms) } => (format!("{ms} ms"), "#22c55e", format!("Voice connected · latency to voice server {ms} ms")),
+        VoiceStatus::Connected { latency_ms: None } => ("Voice".to_string(), "#22c55e", "Voice connected".to_string()),
+        VoiceStatus::Disconnected => ("Offline".to_string(), "#ef4444", "Voice disconnected".to_string()),
+    };
+
+    rsx! {
+        div {
+            style: "
+                display: flex; flex-direction: column; align-items: center; gap: 0.35rem;
+                padding: 0.35rem 0; border-top: 1px solid rgba(255,255,255,0.08);
+            ",
+            if in_call {
+                span {
+                    style: "font-size: 0.55rem; font-weight: 600; color: {status_color}; text-transform: uppercase; white-space: nowrap;",
+                    title: "{status_title}",
+                    "{status_label}"
+                }
+            }
+            button {
+                class: "anim-btn",
+                style: "{button_style(muted)}",
+                title: if muted { "Unmute" } else { "Mute" },
+                onclick: move |_| {
+                    if self_deaf {
+                        on_set_self_voice.call((false, false));
+                    } else {
+                        on_set_self_voice.call((!self_mute, false));
+                    }
+                },
+                if muted { "🔇" } else { "🎙" }
+            }
+            button {
+                class: "anim-btn",
+                style: "{button_style(self_deaf)}",
+                title: if self_deaf { "Undeafen" } else { "Deafen" },
+                onclick: move |_| on_set_self_voice.call((self_mute, !self_deaf)),
+                if self_deaf { "🔕" } else { "🎧" }
+            }
+            if in_call {
+                button {
+                    class: "anim-btn",
+                    style: "
+                        width: 2.25rem; height: 2.25rem; border-radius: 50%;
+                        border: 1px solid rgba(239,68,68,0.6);
+                        background: rgba(239,68,68,0.8);
+                        color: #fff; cursor: pointer; font-size: 0.9rem;
+                        display: flex; align-items: center; justify-content: center;
+                    ",
+                    title: "Disconnect",
+                    onclick: move |_| on_leave_voice.call(()),
+                    "📞"
+                }
+            }
         }
     }
 }
@@ -103,8 +235,19 @@ fn GuildButton(
 pub fn Sidebar(
     guilds: Signal<Vec<ApiGuild>>,
     selected_guild_id: Signal<Option<String>>,
+    read_states: Signal<std::collections::HashMap<String, ReadState>>,
+    notification_settings: Signal<NotificationSettings>,
     on_select_guild: EventHandler<Option<String>>,
+    on_mark_guild_read: EventHandler<String>,
+    on_update_notifications: EventHandler<NotificationSettingsChange>,
     current_user: Signal<Option<DiscordUser>>,
+    current_voice_channel_id: Signal<Option<String>>,
+    voice_status: Signal<VoiceStatus>,
+    self_mute: Signal<bool>,
+    self_deaf: Signal<bool>,
+    /// (self_mute, self_deaf)
+    on_set_self_voice: EventHandler<(bool, bool)>,
+    on_leave_voice: EventHandler<()>,
     on_logout: EventHandler<()>,
     on_open_settings: EventHandler<()>,
 ) -> Element {
@@ -112,6 +255,14 @@ pub fn Sidebar(
     let selected = selected_guild_id();
     let user = current_user();
     let logo = logo_src();
+    let reads = read_states();
+    let notif = notification_settings();
+    let mut guild_context = use_signal(|| None::<(f64, f64, String)>);
+    let dm_mentions: u32 = reads
+        .values()
+        .filter(|r| r.guild_id.is_none())
+        .map(|r| r.mention_count)
+        .sum();
 
     let guild_buttons: Vec<GuildButtonEntry> = list
         .iter()
@@ -127,6 +278,17 @@ pub fn Sidebar(
             };
             let letter = name.chars().next().unwrap_or('?');
              let icon_url = guild_icon_url(g);
+            let guild_reads = reads
+                .iter()
+                .filter(|(_, r)| r.guild_id.as_deref() == Some(g.id.as_str()));
+            let muted = notif.is_guild_muted(&g.id);
+            let mut unread = false;
+            let mut mention_count = 0;
+            for (cid, r) in guild_reads {
+                // Muted channels and guilds still count mentions, but never show as unread.
+                unread |= !muted && !notif.is_channel_muted(Some(&g.id), cid) && r.is_unread();
+                mention_count += r.mention_count;
+            }
             GuildButtonEntry {
                 gid,
                 name,
@@ -134,11 +296,25 @@ pub fn Sidebar(
                 border,
                 letter,
                 icon_url,
+                unread: unread && !is_sel,
+                muted,
+                mention_count,
             }
         })
         .collect();
 
     rsx! {
+        if let Some((x, y, ref gid)) = guild_context() {
+            GuildContextMenu {
+                x,
+                y,
+                guild_id: gid.clone(),
+                notification_settings,
+                on_mark_read: on_mark_guild_read,
+                on_update_notifications,
+                on_close: move |_| guild_context.set(None),
+            }
+        }
         aside {
             class: "glass-panel sidebar",
             style: "width: 4rem; flex-shrink: 0; display: flex; flex-direction: column; align-items: center; padding: 0.5rem 0; gap: 0.5rem;",
@@ -178,10 +354,14 @@ pub fn Sidebar(
                             border: 1px solid {dm_border};
                             color: #d1d5db; cursor: pointer; font-size: 1rem;
                             display: flex; align-items: center; justify-content: center;
+                            position: relative;
                         ",
                         title: "Direct Messages",
                         onclick: move |_| on_select_guild.call(None),
                         "💬"
+                        if dm_mentions > 0 {
+                            MentionBadge { count: dm_mentions }
+                        }
                     }
                 }
             }}
@@ -193,7 +373,11 @@ pub fn Sidebar(
                     border: g_ent.border,
                     letter: g_ent.letter,
                     icon_url: g_ent.icon_url.clone(),
+                    unread: g_ent.unread,
+                    muted: g_ent.muted,
+                    mention_count: g_ent.mention_count,
                     on_select_guild,
+                    on_context: move |ctx| guild_context.set(Some(ctx)),
                 }
             }
             button {
@@ -210,6 +394,14 @@ pub fn Sidebar(
                 "⚙"
             }
             div { style: "flex: 1; min-height: 0.5rem;" }
+            VoicePanel {
+                in_call: current_voice_channel_id().is_some(),
+                status: voice_status(),
+                self_mute: self_mute(),
+                self_deaf: self_deaf(),
+                on_set_self_voice,
+                on_leave_voice,
+            }
             {user.as_ref().map(|u| {
                 let avatar_block = avatar_url(u).map(|url| {
                     rsx! {
@@ -0,0 +1,265 @@
+use dioxus::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+use crate::http::GuildChannel;
+use crate::state::snowflake_cmp;
+
+/// Thread names are limited to 100 characters by the API.
+const THREAD_NAME_MAX: usize = 100;
+
+/// Default name for a thread started from a message: its first line, shortened.
+fn thread_name_from(content: &str) -> String {
+    let first = content.lines().find(|l| !l.trim().is_empty()).unwrap_or("").trim();
+    let mut name: String = first.chars().take(40).collect();
+    if first.chars().count() > 40 {
+        name.push('…');
+    }
+    if name.is_empty() {
+        "New thread".to_string()
+    } else {
+        name
+    }
+}
+
+#[component]
+fn ThreadRow(
+    thread: GuildChannel,
+    is_selected: bool,
+    joined: bool,
+    on_open_thread: EventHandler<String>,
+    on_follow_thread: EventHandler<(String, bool)>,
+) -> Element {
+    let open_id = thread.id.clone();
+    let follow_id = thread.id.clone();
+    let locked = thread.thread_metadata.as_ref().map(|m| m.locked).unwrap_or(false);
+    let count = thread.message_count.unwrap_or(0);
+    let background = if is_selected { "rgba(0,255,245,0.1)" } else { "rgba(255,255,255,0.03)" };
+    rsx! {
+        div {
+            class: "anim-channel-item",
+            style: "
+                display: flex; align-items: center; gap: 0.5rem;
+                padding: 0.5rem 0.6rem; border-radius: 6px; cursor: pointer;
+                background: {background};
+            ",
+            onclick: move |_| on_open_thread.call(open_id.clone()),
+            span { style: "opacity: 0.8;", if locked { "🔒" } else { "🧵" } }
+            div {
+                style: "flex: 1; min-width: 0; display: flex; flex-direction: column;",
+                span {
+                    style: "font-size: 0.875rem; color: #e5e7eb; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
+                    title: "{thread.name}",
+                    "{thread.name}"
+                }
+                span {
+                    style: "font-size: 0.7rem; color: #6b7280;",
+                    if count == 1 { "1 message" } else { "{count} messages" }
+                }
+            }
+            button {
+                class: "anim-btn",
+                style: "
+                    padding: 0.2rem 0.5rem; font-size: 0.7rem; border-radius: 6px; cursor: pointer;
+                    border: 1px solid rgba(255,255,255,0.12); background: transparent;
+                    color: #9ca3af;
+                ",
+                onclick: move |evt| {
+                    evt.stop_propagation();
+                    on_follow_thread.call((follow_id.clone(), !joined));
+                },
+                if joined { "Unfollow" } else { "Follow" }
+            }
+        }
+    }
+}
+
+/// Side panel listing the threads of one channel, with a form to start a new one.
+/// `draft_from_message` is (message_id, content) when started from a message's context menu.
+#[component]
+pub fn ThreadPanel(
+    channel_id: String,
+    channel_name: String,
+    guild_channels: Signal<Vec<GuildChannel>>,
+    archived_threads: Signal<HashMap<String, Vec<GuildChannel>>>,
+    joined_threads: Signal<HashSet<String>>,
+    selected_channel_id: Signal<Option<String>>,
+    draft_from_message: Option<(String, String)>,
+    on_open_thread: EventHandler<String>,
+    on_follow_thread: EventHandler<(String, bool)>,
+    on_load_archived: EventHandler<String>,
+    on_create_thread: EventHandler<(String, Option<String>, String, bool)>,
+    on_close: EventHandler<()>,
+) -> Element {
+    // The layout keys this panel by channel and message, so a new draft starts with fresh state.
+    let initial_name = draft_from_message
+        .as_ref()
+        .map(|(_, content)| thread_name_from(content))
+        .unwrap_or_default();
+    let mut name = use_signal(move || initial_name);
+    let mut private = use_signal(|| false);
+    let mut show_archived = use_signal(|| false);
+    let draft_id = draft_from_message.as_ref().map(|(id, _)| id.clone());
+
+    let joined = joined_threads();
+    let selected = selected_channel_id();
+    let mut active: Vec<GuildChannel> = guild_channels()
+        .into_iter()
+        .filter(|c| c.r#type.is_thread() && c.parent_id.as_deref() == Some(channel_id.as_str()))
+        .filter(|c| !c.is_archived())
+        .collect();
+    active.sort_by(|a, b| snowflake_cmp(&b.id, &a.id));
+    let archived: Vec<GuildChannel> = archived_threads().get(&channel_id).cloned().unwrap_or_default();
+
+    let trimmed = name().trim().to_string();
+    let can_create = !trimmed.is_empty() && trimmed.chars().count() <= THREAD_NAME_MAX;
+    let submit = {
+        let channel_id = channel_id.clone();
+        let message_id = draft_id.clone();
+        move || {
+            let n = name().trim().to_string();
+            if n.is_empty() || n.chars().count() > THREAD_NAME_MAX {
+                return;
+            }
+            on_create_thread.call((channel_id.clone(), message_id.clone(), n, private()));
+            let mut name = name;
+            name.set(String::new());
+        }
+    };
+    let load_id = channel_id.clone();
+
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; height: 100%; min-height: 0;",
+            header {
+                style: "
+                    flex-shrink: 0; padding: 0.75rem; display: flex; align-items: center; gap: 0.5rem;
+                    border-bottom: 1px solid rgba(255,255,255,0.1);
+                ",
+                span { style: "color: #00fff5;", "🧵" }
+                span {
+                    style: "flex: 1; font-weight: 500; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
+                    "Threads · {channel_name}"
+                }
+                button {
+                    class: "anim-btn",
+                    style: "background: transparent; border: none; color: #9ca3af; cursor: pointer; font-size: 1rem;",
+                    onclick: move |_| on_close.call(()),
+                    "✕"
+                }
+            }
+            div {
+                style: "
+                    flex-shrink: 0; padding: 0.75rem; display: flex; flex-direction: column; gap: 0.5rem;
+                    border-bottom: 1px solid rgba(255,255,255,0.1);
+                ",
+                if let Some((_, ref content)) = draft_from_message {
+                    div {
+                        style: "
+                            font-size: 0.75rem; color: #9ca3af; padding: 0.4rem 0.5rem;
+                            border-left: 2px solid rgba(0,255,245,0.4); background: rgba(255,255,255,0.03);
+                            overflow: hidden; text-overflow: ellipsis; white-space: nowrap;
+                        ",
+                        "{content}"
+                    }
+                }
+                input {
+                    style: "
+                        padding: 0.45rem 0.6rem; font-size: 0.875rem;
+                        background: rgba(255,255,255,0.06); border: 1px solid rgba(255,255,255,0.12);
+                        border-radius: 8px; color: #e5e7eb; outline: none;
+                    ",
+                    placeholder: "New thread name",
+                    maxlength: "{THREAD_NAME_MAX}",
+                    value: "{name()}",
+                    oninput: move |evt| name.set(evt.value()),
+                    onkeydown: {
+                        let submit = submit.clone();
+                        move |evt: KeyboardEvent| {
+                            if evt.key() == Key::Enter {
+                                evt.prevent_default();
+                                submit();
+                            }
+                        }
+                    },
+                }
+                div {
+                    style: "display: flex; align-items: center; gap: 0.5rem;",
+                    if draft_from_message.is_none() {
+                        label {
+                            style: "display: flex; align-items: center; gap: 0.35rem; font-size: 0.75rem; color: #9ca3af; cursor: pointer;",
+                            input {
+                                r#type: "checkbox",
+                                checked: private(),
+                                onchange: move |evt| private.set(evt.checked()),
+                            }
+                            "Private"
+                        }
+                    }
+                    button {
+                        class: "anim-btn",
+                        style: "
+                            margin-left: auto; padding: 0.35rem 0.75rem; font-size: 0.8rem;
+                            background: rgba(0,255,245,0.2); border: 1px solid rgba(0,255,245,0.4);
+                            border-radius: 8px; color: #00fff5; cursor: pointer;
+                        ",
+                        disabled: !can_create,
+                        onclick: move |_| submit(),
+                        "Create thread"
+                    }
+                }
+            }
+            div {
+                class: "custom-scroll",
+                style: "flex: 1; min-height: 0; overflow-y: auto; padding: 0.5rem; display: flex; flex-direction: column; gap: 0.35rem;",
+                div {
+                    style: "padding: 0.25rem; font-size: 0.65rem; text-transform: uppercase; letter-spacing: 0.05em; color: #6b7280;",
+                    "Active"
+                }
+                if active.is_empty() {
+                    div { style: "padding: 0.25rem; font-size: 0.8rem; color: #6b7280;", "No active threads." }
+                }
+                for t in active.into_iter() {
+                    ThreadRow {
+                        key: "{t.id}",
+                        is_selected: selected.as_deref() == Some(t.id.as_str()),
+                        joined: joined.contains(&t.id),
+                        thread: t,
+                        on_open_thread,
+                        on_follow_thread,
+                    }
+                }
+                button {
+                    class: "anim-btn",
+                    style: "
+                        margin-top: 0.5rem; padding: 0.35rem; font-size: 0.75rem;
+                        background: transparent; border: none; color: #9ca3af;
+                        cursor: pointer; text-align: left;
+                    ",
+                    onclick: move |_| {
+                        let open = !show_archived();
+                        show_archived.set(open);
+                        if open {
+                            on_load_archived.call(load_id.clone());
+                        }
+                    },
+                    if show_archived() { "▾ Archived" } else { "▸ Archived" }
+                }
+                if show_archived() {
+                    if archived.is_empty() {
+                        div { style: "padding: 0.25rem; font-size: 0.8rem; color: #6b7280;", "No archived threads." }
+                    }
+                    for t in archived.into_iter() {
+                        ThreadRow {
+                            key: "archived-{t.id}",
+                            is_selected: selected.as_deref() == Some(t.id.as_str()),
+                            joined: joined.contains(&t.id),
+                            thread: t,
+                            on_open_thread,
+                            on_follow_thread,
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,9 @@
+use chrono::DateTime;
 use dioxus::prelude::*;
+use std::collections::HashMap;
 
-use crate::http::DiscordUser;
+use crate::http::{ApiGuild, DiscordUser, GuildMember, Role, UserProfile};
+use crate::state::{snowflake_time, Activity, ActivityKind};
 
 fn avatar_url(user: &DiscordUser) -> Option<String> {
     user.avatar.as_ref().map(|hash| {
@@ -18,6 +21,15 @@ fn display_name(user: &DiscordUser) -> &str {
         .unwrap_or(user.username.as_str())
 }
 
+fn format_date(dt: DateTime<chrono::Utc>) -> String {
+    dt.format("%d %b %Y").to_string()
+}
+
+/// Profiles loaded for the popout, keyed by (user ID, guild ID). None while loading or when the fetch failed.
+pub type ProfileCache = HashMap<(String, Option<String>), Option<UserProfile>>;
+
+/// Profile popout: banner, avatar, names, bio, roles in `guild_id`, dates and mutual servers.
+/// `member` is what we already know (member cache / member list) until the profile arrives.
 /// anchor_right: when true (for "my" messages on the right), position card to the left of (x,y)
 #[component]
 pub fn UserCard(
@@ -25,18 +37,113 @@ pub fn UserCard(
     x: i32,
     y: i32,
     anchor_right: bool,
+    is_self: bool,
+    guild_id: Option<String>,
+    member: Option<GuildMember>,
+    /// Current presence activities (custom status first line, games/music below).
+    activities: Vec<Activity>,
+    guild_roles: Signal<Vec<Role>>,
+    guilds: Signal<Vec<ApiGuild>>,
+    user_profiles: Signal<ProfileCache>,
+    on_load_profile: EventHandler<(String, Option<String>)>,
+    on_message: EventHandler<String>,
     on_close: EventHandler<()>,
 ) -> Element {
-    let avatar = avatar_url(&user);
-    let name = display_name(&user);
+    // Callers key the popout by user, so this runs once per opened profile.
+    let key = (user.id.clone(), guild_id.clone());
+    use_hook({
+        let key = key.clone();
+        move || {
+            if !user_profiles.peek().contains_key(&key) {
+                on_load_profile.call(key);
+            }
+        }
+    });
+    let profile = user_profiles().get(&key).cloned().flatten();
+    let member = profile.as_ref().and_then(|p| p.guild_member.clone()).or(member);
+    let gid = guild_id.as_deref();
+
+    let name = member
+        .as_ref()
+        .and_then(|m| m.nick.clone())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| display_name(&user).to_string());
+    let avatar = member
+        .as_ref()
+        .zip(gid)
+        .and_then(|(m, g)| m.guild_avatar_url(g))
+        .or_else(|| avatar_url(&user));
+    let banner_style = match profile.as_ref().and_then(|p| p.banner_url(gid)) {
+        Some(url) => format!("background: url('{url}') center / cover;"),
+        None => "background: linear-gradient(135deg, rgba(0,255,245,0.25), rgba(139,92,246,0.25));".to_string(),
+    };
+    let custom_status = activities
+        .iter()
+        .find(|a| a.kind == ActivityKind::Custom)
+        .map(Activity::summary)
+        .filter(|s| !s.is_empty());
+    let activity_rows: Vec<(usize, String, Option<String>)> = activities
+        .iter()
+        .filter(|a| a.kind != ActivityKind::Custom)
+        .enumerate()
+        .map(|(i, a)| {
+            // Streaming already names `details` in the summary.
+            let detail = match a.kind {
+                ActivityKind::Streaming => a.state.clone(),
+                _ => a.details.clone().or_else(|| a.state.clone()),
+            };
+            (i, a.summary(), detail)
+        })
+        .collect();
+    let bio = profile.as_ref().and_then(|p| p.bio()).map(str::to_string);
+    let member_since = snowflake_time(&user.id).map(format_date);
+    let joined_server = member
+        .as_ref()
+        .and_then(|m| m.joined_at.as_deref())
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|dt| format_date(dt.with_timezone(&chrono::Utc)));
+
+    let all_roles = guild_roles();
+    let mut roles: Vec<&Role> = member
+        .as_ref()
+        .map(|m| {
+            all_roles
+                .iter()
+                .filter(|r| Some(r.id.as_str()) != gid && m.roles.contains(&r.id))
+                .collect()
+        })
+        .unwrap_or_default();
+    roles.sort_by_key(|r| std::cmp::Reverse(r.position));
+    let role_badges: Vec<(String, String, String)> = roles
+        .iter()
+        .map(|r| {
+            let color = if r.color == 0 { "#9ca3af".to_string() } else { format!("#{:06x}", r.color) };
+            (r.id.clone(), r.name.clone(), color)
+        })
+        .collect();
+
+    let known_guilds = guilds();
+    let mutual_guilds: Vec<(String, String)> = profile
+        .as_ref()
+        .map(|p| {
+            p.mutual_guilds
+                .iter()
+                .filter_map(|mg| known_guilds.iter().find(|g| g.id == mg.id))
+                .map(|g| (g.id.clone(), g.name.clone()))
+                .collect()
+        })
+        .unwrap_or_default();
+
     let pos_style = if anchor_right {
         format!(
-            "position: fixed; left: {}px; top: {}px; transform: translateX(-100%);",
+            "position: fixed; left: {}px; top: min({}px, calc(100vh - 26rem)); transform: translateX(-100%);",
             x, y
         )
     } else {
-        format!("position: fixed; left: {}px; top: {}px;", x, y)
+        format!("position: fixed; left: {}px; top: min({}px, calc(100vh - 26rem));", x, y)
     };
+    let section_label = "font-size: 0.65rem; font-weight: 700; color: #9ca3af; text-transform: uppercase; letter-spacing: 0.05em; margin-bottom: 0.3rem;";
+    let dm_user_id = user.id.clone();
 
     rsx! {
         div {
@@ -48,32 +155,33 @@ pub fn UserCard(
             },
         }
         div {
-            class: "anim-modal-content",
+            class: "anim-modal-content custom-scroll",
             style: "
                 {pos_style}
                 background: #12121a;
                 border: 1px solid rgba(255,255,255,0.12);
                 border-radius: 12px;
-                padding: 1rem;
-                min-width: 12rem;
+                width: 18rem; max-height: 26rem; overflow-y: auto;
                 box-shadow: 0 8px 24px rgba(0,0,0,0.4);
                 z-index: 1500;
             ",
+            div { style: "height: 4rem; border-radius: 12px 12px 0 0; {banner_style}" }
             div {
-                style: "display: flex; align-items: center; gap: 0.75rem;",
+                style: "padding: 0 1rem 1rem; margin-top: -1.75rem; display: flex; flex-direction: column; gap: 0.75rem;",
                 {match avatar {
                     Some(url) => rsx! {
                         img {
                             src: "{url}",
                             alt: "",
-                            style: "width: 3rem; height: 3rem; border-radius: 50%; object-fit: cover;",
+                            style: "width: 3.5rem; height: 3.5rem; border-radius: 50%; object-fit: cover; border: 4px solid #12121a;",
                         }
                     },
                     None => rsx! {
                         div {
                             style: "
-                                width: 3rem; height: 3rem; border-radius: 50%;
-                                background: rgba(0,255,245,0.2);
+                                width: 3.5rem; height: 3.5rem; border-radius: 50%;
+                                border: 4px solid #12121a;
+                                background: #0f3b3d;
                                 display: flex; align-items: center; justify-content: center;
                                 font-size: 1.25rem; font-weight: 600; color: #00fff5;
                             ",
@@ -82,15 +190,113 @@ pub fn UserCard(
                     },
                 }}
                 div {
-                    style: "flex: 1; min-width: 0;",
                     div {
-                        style: "font-weight: 600; color: #e5e7eb; font-size: 0.9375rem;",
+                        style: "font-weight: 600; color: #e5e7eb; font-size: 1rem;",
                         "{name}"
                     }
                     div {
                         style: "font-size: 0.75rem; color: #6b7280;",
                         "@{user.username}"
                     }
+                    if let Some(ref status) = custom_status {
+                        div {
+                            style: "font-size: 0.8rem; color: #d1d5db; margin-top: 0.3rem; word-break: break-word;",
+                            "{status}"
+                        }
+                    }
+                }
+                if !activity_rows.is_empty() {
+                    div {
+                        div { style: "{section_label}", "Activity" }
+                        div {
+                            style: "display: flex; flex-direction: column; gap: 0.3rem;",
+                            for (i, summary, detail) in activity_rows.into_iter() {
+                                div {
+                                    key: "{i}",
+                                    div { style: "font-size: 0.8rem; color: #e5e7eb;", "{summary}" }
+                                    if let Some(detail) = detail {
+                                        div { style: "font-size: 0.75rem; color: #9ca3af;", "{detail}" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                if let Some(ref bio) = bio {
+                    div {
+                        div { style: "{section_label}", "About me" }
+                        div {
+                            style: "font-size: 0.8rem; color: #d1d5db; white-space: pre-wrap; word-break: break-word;",
+                            "{bio}"
+                        }
+                    }
+                }
+                if member_since.is_some() || joined_server.is_some() {
+                    div {
+                        style: "display: flex; gap: 1rem;",
+                        if let Some(ref since) = member_since {
+                            div {
+                                div { style: "{section_label}", "Member since" }
+                                div { style: "font-size: 0.8rem; color: #d1d5db;", "{since}" }
+                            }
+                        }
+                        if let Some(ref joined) = joined_server {
+                            div {
+                                div { style: "{section_label}", "Joined server" }
+                                div { style: "font-size: 0.8rem; color: #d1d5db;", "{joined}" }
+                            }
+                        }
+                    }
+                }
+                if !role_badges.is_empty() {
+                    div {
+                        div { style: "{section_label}", "Roles" }
+                        div {
+                            style: "display: flex; flex-wrap: wrap; gap: 0.3rem;",
+                            for (id, role_name, color) in role_badges.into_iter() {
+                                span {
+                                    key: "{id}",
+                                    style: "
+                                        display: inline-flex; align-items: center; gap: 0.3rem;
+                                        padding: 0.1rem 0.45rem; border-radius: 4px;
+                                        background: rgba(255,255,255,0.06); font-size: 0.7rem; color: #e5e7eb;
+                                    ",
+                                    span { style: "width: 0.55rem; height: 0.55rem; border-radius: 50%; background: {color};" }
+                                    "{role_name}"
+                                }
+                            }
+                        }
+                    }
+                }
+                if !mutual_guilds.is_empty() {
+                    div {
+                        div { style: "{section_label}", "Mutual servers — {mutual_guilds.len()}" }
+                        div {
+                            style: "display: flex; flex-direction: column; gap: 0.15rem;",
+                            for (id, guild_name) in mutual_guilds.into_iter() {
+                                span {
+                                    key: "{id}",
+                                    style: "font-size: 0.8rem; color: #d1d5db; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
+                                    "{guild_name}"
+                                }
+                            }
+                        }
+                    }
+                }
+                if !is_self {
+                    button {
+                        class: "anim-btn",
+                        style: "
+                            padding: 0.45rem; font-size: 0.8rem;
+                            background: rgba(0,255,245,0.2); border: 1px solid rgba(0,255,245,0.4);
+                            border-radius: 8px; color: #00fff5; cursor: pointer;
+                        ",
+                        onclick: move |_| {
+                            on_message.call(dm_user_id.clone());
+                            on_close.call(());
+                        },
+                        "Message"
+                    }
                 }
             }
         }
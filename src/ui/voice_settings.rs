@@ -0,0 +1,330 @@
+use dioxus::prelude::*;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::state::{save_settings, AppSettings, VoiceInputMode};
+use crate::voice_audio::{self, VoiceControls, LEVEL_FLOOR_DB};
+
+/// Key codes that only modify another key; a keybind needs a main key.
+const MODIFIER_CODES: [&str; 8] = [
+    "ShiftLeft", "ShiftRight", "ControlLeft", "ControlRight", "AltLeft", "AltRight", "MetaLeft", "MetaRight",
+];
+
+/// Keybind in the settings format ("shift+control+KeyT"), or None for a bare modifier press.
+fn keybind_from_event(evt: &KeyboardEvent) -> Option<String> {
+    let code = evt.code().to_string();
+    if MODIFIER_CODES.contains(&code.as_str()) {
+        return None;
+    }
+    let mods = evt.modifiers();
+    let mut parts: Vec<&str> = Vec::new();
+    if mods.contains(Modifiers::SHIFT) {
+        parts.push("shift");
+    }
+    if mods.contains(Modifiers::CONTROL) {
+        parts.push("control");
+    }
+    if mods.contains(Modifiers::ALT) {
+        parts.push("alt");
+    }
+    if mods.contains(Modifiers::META) {
+        parts.push("super");
+    }
+    parts.push(&code);
+    Some(parts.join("+"))
+}
+
+/// Writes one on/off setting.
+type SetToggle = fn(&mut AppSettings, bool);
+
+/// Meter position for a dBFS value, in percent of the bar.
+fn meter_percent(db: f32) -> f32 {
+    ((db - LEVEL_FLOOR_DB) / -LEVEL_FLOOR_DB * 100.0).clamp(0.0, 100.0)
+}
+
+/// Input mode section of the Voice tab: voice activity (threshold, hangover, live meter) or push-to-talk (keybind, release delay).
+#[component]
+pub fn VoiceInputSettings(settings: Signal<AppSettings>) -> Element {
+    let mut level_db = use_signal(|| LEVEL_FLOOR_DB);
+    let mut recording_key = use_signal(|| false);
+    // "Let's check" loopback running, and whether its devices failed to open.
+    let mut testing = use_signal(|| false);
+    let mut test_failed = use_signal(|| false);
+    // (buffered ms, target ms, underruns) of the call's speaker output.
+    let mut playback = use_signal(|| (0u32, 0u32, 0u32));
+
+    // Sensitivity meter: a separate capture of the selected mic, reopened when the device changes;
+    // during the mic test the loopback replaces it. Its controls are private so the meter never touches
+    // the call's gate state; they follow the gain and processing settings so the meter shows the level
+    // the gate would see.
+    use_hook(move || {
+        spawn(async move {
+            let controls = Arc::new(VoiceControls::default());
+            let mut opened: Option<(Option<String>, Option<String>, bool)> = None;
+            let mut _streams: Vec<cpal::Stream> = Vec::new();
+            loop {
+                let s = settings.peek().clone();
+                controls.configure(&s);
+                let test = *testing.peek();
+                let wanted = (s.voice_input_device.clone(), s.voice_output_device.clone(), test);
+                if opened.as_ref() != Some(&wanted) {
+                    _streams.clear();
+                    if test {
+                        match voice_audio::create_mic_test(wanted.0.as_deref(), wanted.1.as_deref(), controls.clone()) {
+                            Some((mic, speaker)) => _streams = vec![mic, speaker],
+                            None => {
+                                eprintln!("[voice] mic test: could not open devices");
+                                test_failed.set(true);
+                                testing.set(false);
+                            }
+                        }
+                    } else if let Some(stream) = voice_audio::create_input_monitor(wanted.0.as_deref(), controls.clone()) {
+                        _streams.push(stream);
+                    }
+                    opened = Some(wanted);
+                }
+                level_db.set(controls.input_level_db.load());
+                let call = voice_audio::shared_controls();
+                playback.set((
+                    call.playback_latency_ms.load(Ordering::Relaxed),
+                    call.jitter_target_ms.load(Ordering::Relaxed),
+                    call.playback_underruns.load(Ordering::Relaxed),
+                ));
+                tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+            }
+        });
+    });
+
+    let s = settings();
+    let level = level_db();
+    let level_pct = meter_percent(level);
+    let threshold_pct = meter_percent(s.vad_threshold_db);
+    let (buffered_ms, target_ms, underruns) = playback();
+    let meter_color = if level >= s.vad_threshold_db { "#22c55e" } else { "#6b7280" };
+    let keybind_label = if recording_key() {
+        "Press a key…".to_string()
+    } else {
+        s.ptt_key.clone().unwrap_or_else(|| "Not set".to_string())
+    };
+    let update = move |f: &dyn Fn(&mut AppSettings)| {
+        let mut settings = settings;
+        let mut s = settings();
+        f(&mut s);
+        settings.set(s.clone());
+        let _ = save_settings(&s);
+    };
+    let modes = [
+        (VoiceInputMode::VoiceActivity, "Voice Activity"),
+        (VoiceInputMode::PushToTalk, "Push to Talk"),
+    ];
+    let processing: [(&str, bool, SetToggle); 3] = [
+        ("Noise suppression", s.noise_suppression, |s, on| s.noise_suppression = on),
+        ("Echo cancellation", s.echo_cancellation, |s, on| s.echo_cancellation = on),
+        ("Automatic gain control", s.auto_gain_control, |s, on| s.auto_gain_control = on),
+    ];
+
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; gap: 1rem; margin-top: 1.5rem;",
+            h3 {
+                style: "margin: 0; font-size: 1rem; color: #9ca3af;",
+                "Input mode"
+            }
+            div {
+                style: "display: flex; gap: 0.5rem;",
+                for (mode, label) in modes.into_iter() {
+                    button {
+                        key: "{label}",
+                        class: "anim-btn",
+                        style: if s.voice_input_mode == mode {
+                            "padding: 0.4rem 0.85rem; font-size: 0.85rem; border-radius: 6px; cursor: pointer; border: 1px solid rgba(0,255,245,0.4); background: rgba(0,255,245,0.15); color: #00fff5;"
+                        } else {
+                            "padding: 0.4rem 0.85rem; font-size: 0.85rem; border-radius: 6px; cursor: pointer; border: 1px solid rgba(255,255,255,0.12); background: transparent; color: #9ca3af;"
+                        },
+                        onclick: move |_| update(&|s| s.voice_input_mode = mode),
+                        "{label}"
+                    }
+                }
+            }
+            div {
+                style: "display: flex; flex-direction: column; gap: 0.5rem; max-width: 20rem;",
+                label {
+                    style: "color: #9ca3af; font-size: 0.875rem;",
+                    "Input level"
+                }
+                div {
+                    style: "position: relative; height: 0.5rem; border-radius: 4px; background: rgba(255,255,255,0.08); overflow: hidden;",
+                    div { style: "height: 100%; width: {level_pct}%; background: {meter_color};" }
+                    if s.voice_input_mode == VoiceInputMode::VoiceActivity {
+                        div { style: "position: absolute; top: 0; bottom: 0; left: {threshold_pct}%; width: 2px; background: #facc15;" }
+                    }
+                }
+                label {
+                    style: "color: #9ca3af; font-size: 0.875rem;",
+                    "Input volume: {s.voice_input_gain}%"
+                }
+                input {
+                    r#type: "range",
+                    min: "0",
+                    max: "200",
+                    step: "5",
+                    value: "{s.voice_input_gain}",
+                    oninput: move |evt| {
+                        if let Ok(v) = evt.value().parse::<u16>() {
+                            update(&|s| s.voice_input_gain = v);
+                        }
+                    },
+                }
+                div {
+                    style: "display: flex; gap: 0.75rem; align-items: center;",
+                    button {
+                        class: "anim-btn",
+                        style: if testing() {
+                            "padding: 0.4rem 0.85rem; font-size: 0.85rem; border-radius: 6px; cursor: pointer; border: 1px solid rgba(239,68,68,0.5); background: rgba(239,68,68,0.15); color: #fca5a5;"
+                        } else {
+                            "padding: 0.4rem 0.85rem; font-size: 0.85rem; border-radius: 6px; cursor: pointer; border: 1px solid rgba(0,255,245,0.4); background: rgba(0,255,245,0.15); color: #00fff5;"
+                        },
+                        onclick: move |_| {
+                            test_failed.set(false);
+                            testing.set(!testing());
+                        },
+                        if testing() { "Stop Testing" } else { "Let's Check" }
+                    }
+                    span {
+                        style: "color: #6b7280; font-size: 0.8rem;",
+                        if test_failed() {
+                            "Could not open the selected microphone or speaker."
+                        } else if testing() {
+                            "Say something; you should hear yourself back."
+                        } else {
+                            "Plays your microphone back through the selected speaker."
+                        }
+                    }
+                }
+            }
+            if s.voice_input_mode == VoiceInputMode::VoiceActivity {
+                div {
+                    style: "display: flex; flex-direction: column; gap: 0.5rem; max-width: 20rem;",
+                    label {
+                        style: "color: #9ca3af; font-size: 0.875rem;",
+                        "Sensitivity threshold: {s.vad_threshold_db:.0} dB"
+                    }
+                    input {
+                        r#type: "range",
+                        min: "-100",
+                        max: "0",
+                        step: "1",
+                        value: "{s.vad_threshold_db}",
+                        oninput: move |evt| {
+                            if let Ok(v) = evt.value().parse::<f32>() {
+                                update(&|s| s.vad_threshold_db = v);
+                            }
+                        },
+                    }
+                    label {
+                        style: "color: #9ca3af; font-size: 0.875rem;",
+                        "Hangover: {s.vad_hangover_ms} ms"
+                    }
+                    input {
+                        r#type: "range",
+                        min: "0",
+                        max: "2000",
+                        step: "50",
+                        value: "{s.vad_hangover_ms}",
+                        oninput: move |evt| {
+                            if let Ok(v) = evt.value().parse::<u32>() {
+                                update(&|s| s.vad_hangover_ms = v);
+                            }
+                        },
+                    }
+                }
+            } else {
+                div {
+                    style: "display: flex; flex-direction: column; gap: 0.5rem; max-width: 20rem;",
+                    label {
+                        style: "color: #9ca3af; font-size: 0.875rem;",
+                        "Keybind"
+                    }
+                    div {
+                        style: "display: flex; gap: 0.5rem; align-items: center;",
+                        button {
+                            class: "anim-btn",
+                            style: "
+                                flex: 1; padding: 0.45rem 0.75rem; font-size: 0.875rem; text-align: left;
+                                background: rgba(0,0,0,0.3); border: 1px solid rgba(255,255,255,0.15);
+                                border-radius: 6px; color: #e5e7eb; cursor: pointer;
+                            ",
+                            onclick: move |_| recording_key.set(true),
+                            onblur: move |_| recording_key.set(false),
+                            onkeydown: move |evt: KeyboardEvent| {
+                                if !recording_key() {
+                                    return;
+                                }
+                                evt.prevent_default();
+                                if evt.key() == Key::Escape {
+                                    recording_key.set(false);
+                                    return;
+                                }
+                                if let Some(bind) = keybind_from_event(&evt) {
+                                    update(&|s| s.ptt_key = Some(bind.clone()));
+                                    recording_key.set(false);
+                                }
+                            },
+                            "{keybind_label}"
+                        }
+                        if s.ptt_key.is_some() {
+                            button {
+                                class: "anim-btn",
+                                style: "padding: 0.45rem 0.75rem; font-size: 0.8rem; border-radius: 6px; cursor: pointer; border: 1px solid rgba(255,255,255,0.12); background: transparent; color: #9ca3af;",
+                                onclick: move |_| update(&|s| s.ptt_key = None),
+                                "Clear"
+                            }
+                        }
+                    }
+                    label {
+                        style: "color: #9ca3af; font-size: 0.875rem;",
+                        "Release delay: {s.ptt_release_delay_ms} ms"
+                    }
+                    input {
+                        r#type: "range",
+                        min: "0",
+                        max: "1000",
+                        step: "20",
+                        value: "{s.ptt_release_delay_ms}",
+                        oninput: move |evt| {
+                            if let Ok(v) = evt.value().parse::<u32>() {
+                                update(&|s| s.ptt_release_delay_ms = v);
+                            }
+                        },
+                    }
+                }
+            }
+            h3 {
+                style: "margin: 0; font-size: 1rem; color: #9ca3af;",
+                "Voice processing"
+            }
+            for (label, checked, apply) in processing.into_iter() {
+                label {
+                    key: "{label}",
+                    style: "display: flex; align-items: center; gap: 0.75rem; cursor: pointer;",
+                    input {
+                        r#type: "checkbox",
+                        checked: "{checked}",
+                        oninput: move |evt| {
+                            let on = evt.checked();
+                            update(&|s| apply(s, on));
+                        },
+                    }
+                    span {
+                        style: "color: #e5e7eb; font-size: 0.9375rem;",
+                        "{label}"
+                    }
+                }
+            }
+            span {
+                style: "color: #6b7280; font-size: 0.8rem;",
+                "Playback buffer: {buffered_ms} ms (target {target_ms} ms) · {underruns} underruns"
+            }
+        }
+    }
+}
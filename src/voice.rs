@@ -1,13 +1,33 @@
 //! Voice connection via Songbird driver.
 //! Receives VoiceMessage from gateway and connects/disconnects the driver.
+//! During a call failed streams are reopened (on the default device when theirs is gone), the devices
+//! are listed now and then while on such a fallback to switch back, and the user is told through
+//! `VoiceEvent::Notice`.
+//! The driver's connect / reconnect / disconnect events drive `VoiceEvent::Status`; a lost connection is
+//! retried with backoff, and a server move (a new Connect while in a call) reuses the driver and devices.
+//! Once the retries run out the call is torn down as on Leave and `VoiceEvent::GaveUp` asks the UI to
+//! leave the channel on the gateway as well.
+//! The latency shown while connected is not the RTC ping Discord's client shows (the voice websocket's
+//! heartbeat round trip): songbird 0.5 does not expose that, so it is the TCP connect time to the voice
+//! server's host instead (see `spawn_latency_probe`).
 
 use std::num::NonZeroU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::gateway::{VoiceConnectionInfo, VoiceMessage};
-use crate::voice_audio;
+use crate::gateway::{VoiceConnectionInfo, VoiceEvent, VoiceMessage, VoiceStatus};
+use crate::voice_audio::{self, VoiceControls};
+use crate::voice_dsp;
+use async_trait::async_trait;
 use dioxus::prelude::spawn;
+use songbird::events::context_data::{DisconnectKind, DisconnectReason};
+use songbird::driver::Connect;
+use songbird::error::ConnectionResult;
 use songbird::{driver::{DecodeMode, Driver}, CoreEvent, id::*, Config, ConnectionInfo};
+use songbird::{Event, EventContext, EventHandler};
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 fn parse_id(s: &str) -> u64 {
     s.parse::<u64>().unwrap_or(0)
@@ -32,17 +52,418 @@ fn connection_info_from_ours(info: &VoiceConnectionInfo) -> Option<ConnectionInf
     })
 }
 
+/// How often the driver's mute state follows the input gate.
+const GATE_POLL: Duration = Duration::from_millis(20);
+/// How often a call checks its streams' error flags.
+const DEVICE_POLL: Duration = Duration::from_secs(1);
+/// While on a fallback device, how often the devices are listed to see whether the preferred one is
+/// back. On ALSA listing opens every PCM, so a healthy call never lists.
+const FALLBACK_POLL: Duration = Duration::from_secs(10);
+
+/// Reconnect attempts after a failed or dropped connection before giving up; the delay doubles each time.
+const RECONNECT_ATTEMPTS: u32 = 5;
+const RECONNECT_FIRST_DELAY: Duration = Duration::from_secs(1);
+/// How often the latency to the voice server is measured.
+const LATENCY_INTERVAL: Duration = Duration::from_secs(5);
+const LATENCY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// What the driver's events and the latency probe tell the voice task.
+enum DriverUpdate {
+    Connected,
+    /// `lost`: dropped by a failure rather than on request; worth reconnecting.
+    Disconnected { lost: bool },
+    Latency(u32),
+}
+
+/// Forwards the driver's connection events to the voice task.
+struct DriverEvents(mpsc::UnboundedSender<DriverUpdate>);
+
+#[async_trait]
+impl EventHandler for DriverEvents {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        let update = match ctx {
+            EventContext::DriverConnect(_) | EventContext::DriverReconnect(_) => DriverUpdate::Connected,
+            // A failed connect is handled where `connect` is awaited.
+            EventContext::DriverDisconnect(data) if data.kind != DisconnectKind::Connect => {
+                eprintln!("[voice] driver disconnected ({:?}, reason {:?})", data.kind, data.reason);
+                let lost = !matches!(
+                    data.reason,
+                    None | Some(DisconnectReason::Requested) | Some(DisconnectReason::AttemptDiscarded)
+                );
+                DriverUpdate::Disconnected { lost }
+            }
+            _ => return None,
+        };
+        let _ = self.0.send(update);
+        None
+    }
+}
+
+/// Latency to the voice server: every few seconds, the time a TCP handshake with the endpoint takes
+/// (port 443 unless the endpoint names one), i.e. one network round trip to that host. Songbird keeps
+/// its websocket heartbeat round trip to itself, and this is not the UDP media latency either.
+fn spawn_latency_probe(endpoint: String, tx: mpsc::UnboundedSender<DriverUpdate>) -> JoinHandle<()> {
+    let host = if endpoint.contains(':') { endpoint } else { format!("{endpoint}:443") };
+    tokio::spawn(async move {
+        let mut addr = None;
+        let mut interval = tokio::time::interval(LATENCY_INTERVAL);
+        loop {
+            interval.tick().await;
+            if addr.is_none() {
+                addr = tokio::net::lookup_host(&host).await.ok().and_then(|mut a| a.next());
+            }
+            let Some(addr) = addr else {
+                continue;
+            };
+            let start = Instant::now();
+            if let Ok(Ok(_)) = tokio::time::timeout(LATENCY_TIMEOUT, tokio::net::TcpStream::connect(addr)).await {
+                let ms = start.elapsed().as_millis().min(u32::MAX as u128) as u32;
+                if tx.send(DriverUpdate::Latency(ms)).is_err() {
+                    break;
+                }
+            }
+        }
+    })
+}
+
+/// Retries of a connection that failed or dropped.
+#[derive(Default)]
+struct Reconnect {
+    attempts: u32,
+    at: Option<tokio::time::Instant>,
+}
+
+impl Reconnect {
+    /// Schedule the next attempt; false once they are used up.
+    fn schedule(&mut self) -> bool {
+        if self.attempts >= RECONNECT_ATTEMPTS {
+            self.at = None;
+            return false;
+        }
+        let delay = RECONNECT_FIRST_DELAY * 2u32.pow(self.attempts);
+        self.attempts += 1;
+        eprintln!("[voice] reconnect attempt {} in {:?}", self.attempts, delay);
+        self.at = Some(tokio::time::Instant::now() + delay);
+        true
+    }
+}
+
+/// The driver's pending (re)connect; never resolves while there is none.
+async fn pending_connect(connecting: &mut Option<Connect>) -> ConnectionResult<()> {
+    match connecting {
+        Some(connect) => connect.await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Send `new` to the UI when it differs from what was sent last.
+fn report_status(status: &mut VoiceStatus, new: VoiceStatus, tx_events: &mpsc::UnboundedSender<VoiceEvent>) {
+    if *status != new {
+        *status = new;
+        let _ = tx_events.send(VoiceEvent::Status(new));
+    }
+}
+
+/// Input and output device names, enumerated off the UI thread (slow on some backends).
+async fn list_devices() -> (Vec<String>, Vec<String>) {
+    tokio::task::spawn_blocking(|| (voice_audio::list_input_devices(), voice_audio::list_output_devices()))
+        .await
+        .unwrap_or_default()
+}
+
+/// The device to open: the configured one while it is plugged in, else the default (None).
+fn resolve_device(wanted: Option<&str>, available: &[String]) -> Option<String> {
+    wanted
+        .filter(|name| *name != "(Default)" && available.iter().any(|n| n == name))
+        .map(str::to_string)
+}
+
+/// Why one side of the call's audio has to be reopened, as told to the user; None keeps the stream.
+/// `kind` is "Microphone" or "Speaker"; `open` / `target` are device names, None for the default.
+fn device_change(
+    kind: &str,
+    wanted: Option<&str>,
+    open: Option<&str>,
+    target: Option<&str>,
+    failed: bool,
+    stream_missing: bool,
+) -> Option<String> {
+    if !failed && !stream_missing && open == target {
+        return None;
+    }
+    let wanted = wanted.filter(|name| *name != "(Default)");
+    Some(match (wanted, target) {
+        (Some(name), None) => fallback_notice(kind, name),
+        (Some(name), Some(_)) if open != target => format!("Switched back to {kind} \"{name}\""),
+        _ => format!("{kind} was reconnected"),
+    })
+}
+
+fn fallback_notice(kind: &str, name: &str) -> String {
+    format!("{kind} \"{name}\" is unavailable, using the default device")
+}
+
+/// Audio devices of the current call.
+struct CallAudio {
+    /// Devices picked in settings (None = default).
+    input_wanted: Option<String>,
+    output_wanted: Option<String>,
+    /// Devices open now (None = default).
+    input_open: Option<String>,
+    output_open: Option<String>,
+    playback: voice_audio::VoicePlayback,
+    mic: Option<cpal::Stream>,
+    speaker: Option<cpal::Stream>,
+    /// Last notice sent, so a device that keeps failing does not repeat the same toast.
+    last_notice: Option<String>,
+    last_listing: Instant,
+}
+
+impl CallAudio {
+    /// (Re)open the speaker on `output` and the mic on `input` (None = default); the mic becomes the driver's only input.
+    fn open(&mut self, d: &mut Driver, controls: &Arc<VoiceControls>, input: Option<String>, output: Option<String>) {
+        // Release the old devices first; some backends allow only one stream per device.
+        self.mic = None;
+        self.speaker = None;
+        self.playback.set_output(None);
+        controls.input_failed.store(false, Ordering::Relaxed);
+        controls.output_failed.store(false, Ordering::Relaxed);
+        let (echo_producer, echo_consumer) = voice_dsp::echo_reference();
+        match voice_audio::create_speaker_output(output.as_deref(), controls.clone(), Some(echo_producer)) {
+            Some((stream, producer)) => {
+                self.playback.set_output(Some(producer));
+                self.speaker = Some(stream);
+                eprintln!("[voice] speaker output created");
+            }
+            None => eprintln!("[voice] no output device available"),
+        }
+        // Send the mic as a live raw PCM source.
+        match voice_audio::create_mic_input(input.as_deref(), controls.clone(), Some(echo_consumer)) {
+            Some((stream, source)) => {
+                d.play_only_input(source);
+                self.mic = Some(stream);
+                eprintln!("[voice] microphone input started");
+            }
+            None => {
+                d.stop();
+                eprintln!("[voice] no microphone device available");
+            }
+        }
+        self.input_open = input;
+        self.output_open = output;
+    }
+
+    /// A stream failed, or a side runs on the default (or nothing) instead of the device picked in settings.
+    fn needs_listing(&self, controls: &VoiceControls) -> bool {
+        let failed = controls.input_failed.load(Ordering::Relaxed) || controls.output_failed.load(Ordering::Relaxed);
+        let preferred = |wanted: &Option<String>| wanted.clone().filter(|name| name != "(Default)");
+        let on_fallback = self.mic.is_none()
+            || self.speaker.is_none()
+            || self.input_open != preferred(&self.input_wanted)
+            || self.output_open != preferred(&self.output_wanted);
+        failed || (on_fallback && self.last_listing.elapsed() >= FALLBACK_POLL)
+    }
+
+    /// Reopen whatever failed or should move to another device; returns notices for the user.
+    fn check_devices(
+        &mut self,
+        d: &mut Driver,
+        controls: &Arc<VoiceControls>,
+        inputs: &[String],
+        outputs: &[String],
+    ) -> Vec<String> {
+        self.last_listing = Instant::now();
+        let input_failed = controls.input_failed.load(Ordering::Relaxed);
+        let output_failed = controls.output_failed.load(Ordering::Relaxed);
+        // A device this call holds can be missing from the list (ALSA lists by opening each PCM), so a
+        // working stream on the preferred device stays put; only its error callback moves it.
+        let input_target = if !input_failed && self.mic.is_some() && self.input_open.is_some() && self.input_open == self.input_wanted {
+            self.input_open.clone()
+        } else {
+            resolve_device(self.input_wanted.as_deref(), inputs)
+        };
+        let output_target = if !output_failed && self.speaker.is_some() && self.output_open.is_some() && self.output_open == self.output_wanted {
+            self.output_open.clone()
+        } else {
+            resolve_device(self.output_wanted.as_deref(), outputs)
+        };
+        // The lists always start with "(Default)"; a real device is there only when there is more.
+        let input_change = device_change(
+            "Microphone",
+            self.input_wanted.as_deref(),
+            self.input_open.as_deref(),
+            input_target.as_deref(),
+            input_failed,
+            self.mic.is_none() && inputs.len() > 1,
+        );
+        let output_change = device_change(
+            "Speaker",
+            self.output_wanted.as_deref(),
+            self.output_open.as_deref(),
+            output_target.as_deref(),
+            output_failed,
+            self.speaker.is_none() && outputs.len() > 1,
+        );
+        if input_change.is_none() && output_change.is_none() {
+            return Vec::new();
+        }
+        eprintln!("[voice] reopening audio devices (input={:?}, output={:?})", input_target, output_target);
+        self.open(d, controls, input_target, output_target);
+        let mut notices: Vec<String> = input_change.into_iter().chain(output_change).collect();
+        if self.mic.is_none() {
+            notices.push("No microphone available".to_string());
+        }
+        if self.speaker.is_none() {
+            notices.push("No speaker available".to_string());
+        }
+        let notice = notices.join(". ");
+        if self.last_notice.as_ref() == Some(&notice) {
+            return Vec::new();
+        }
+        self.last_notice = Some(notice.clone());
+        vec![notice]
+    }
+}
+
 /// Spawn the voice task: receives Connect(info) or Leave and runs the Songbird driver.
-pub fn spawn_voice_task(mut rx: mpsc::UnboundedReceiver<VoiceMessage>) {
+/// Speaking changes (ours and the other participants') and the connection status are reported on `tx_events`.
+pub fn spawn_voice_task(mut rx: mpsc::UnboundedReceiver<VoiceMessage>, tx_events: mpsc::UnboundedSender<VoiceEvent>) {
     spawn(async move {
         eprintln!("[voice] task started, waiting for Connect/Leave");
         let mut driver: Option<Driver> = None;
-        let mut mic_stream: Option<cpal::Stream> = None;
-        let mut speaker_stream: Option<cpal::Stream> = None;
-        while let Some(msg) = rx.recv().await {
-            eprintln!("[voice] received {}", if matches!(&msg, VoiceMessage::Connect { .. }) { "Connect" } else { "Leave" });
+        let mut audio: Option<CallAudio> = None;
+        let controls = voice_audio::shared_controls();
+        // Our user ID in the current call, for our own speaking indicator.
+        let mut self_id: Option<String> = None;
+        let mut gate_poll = tokio::time::interval(GATE_POLL);
+        let mut device_poll = tokio::time::interval(DEVICE_POLL);
+        let (driver_tx, mut driver_rx) = mpsc::unbounded_channel::<DriverUpdate>();
+        // Where the current call connects (kept for reconnects).
+        let mut conn_info: Option<ConnectionInfo> = None;
+        let mut reconnect = Reconnect::default();
+        // Awaited as a select branch, so Leave and mute changes are handled while it is in flight.
+        let mut connecting: Option<Connect> = None;
+        let mut latency_probe: Option<JoinHandle<()>> = None;
+        let mut status = VoiceStatus::Disconnected;
+        loop {
+            let msg = tokio::select! {
+                msg = rx.recv() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+                Some(update) = driver_rx.recv() => {
+                    // Updates can trail a call that already ended.
+                    if driver.is_none() {
+                        continue;
+                    }
+                    let mut gave_up = false;
+                    match update {
+                        DriverUpdate::Connected => {
+                            reconnect = Reconnect::default();
+                            if !matches!(status, VoiceStatus::Connected { .. }) {
+                                report_status(&mut status, VoiceStatus::Connected { latency_ms: None }, &tx_events);
+                            }
+                        }
+                        // A connect in flight reports its own outcome.
+                        DriverUpdate::Disconnected { lost: false } => {}
+                        DriverUpdate::Disconnected { lost: true } if connecting.is_some() => {}
+                        DriverUpdate::Disconnected { lost: true } => {
+                            if reconnect.at.is_none() && !reconnect.schedule() {
+                                eprintln!("[voice] giving up on the voice connection");
+                                report_status(&mut status, VoiceStatus::Disconnected, &tx_events);
+                                let _ = tx_events.send(VoiceEvent::Notice("Lost connection to the voice server".to_string()));
+                                gave_up = true;
+                            } else {
+                                report_status(&mut status, VoiceStatus::Connecting, &tx_events);
+                            }
+                        }
+                        DriverUpdate::Latency(ms) => {
+                            if matches!(status, VoiceStatus::Connected { .. }) {
+                                report_status(&mut status, VoiceStatus::Connected { latency_ms: Some(ms) }, &tx_events);
+                            }
+                        }
+                    }
+                    if !gave_up {
+                        continue;
+                    }
+                    // Tear the call down like a Leave; the UI has the gateway leave the channel too.
+                    let _ = tx_events.send(VoiceEvent::GaveUp);
+                    VoiceMessage::Leave
+                }
+                _ = tokio::time::sleep_until(reconnect.at.unwrap_or_else(tokio::time::Instant::now)), if reconnect.at.is_some() => {
+                    reconnect.at = None;
+                    if let (Some(d), Some(conn)) = (driver.as_mut(), conn_info.clone()) {
+                        eprintln!("[voice] reconnecting to {}", conn.endpoint);
+                        connecting = Some(d.connect(conn));
+                    }
+                    continue;
+                }
+                result = pending_connect(&mut connecting) => {
+                    connecting = None;
+                    let mut gave_up = false;
+                    match result {
+                        Ok(()) => {
+                            eprintln!("[voice] driver connected");
+                            reconnect = Reconnect::default();
+                            report_status(&mut status, VoiceStatus::Connected { latency_ms: None }, &tx_events);
+                            if let Some(endpoint) = conn_info.as_ref().map(|c| c.endpoint.clone()) {
+                                if let Some(handle) = latency_probe.replace(spawn_latency_probe(endpoint, driver_tx.clone())) {
+                                    handle.abort();
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("[voice] connect error: {}", e);
+                            // A failed first connect is retried like a dropped connection.
+                            if reconnect.schedule() {
+                                report_status(&mut status, VoiceStatus::Connecting, &tx_events);
+                            } else {
+                                eprintln!("[voice] giving up on the voice connection");
+                                report_status(&mut status, VoiceStatus::Disconnected, &tx_events);
+                                let _ = tx_events.send(VoiceEvent::Notice("Could not connect to voice".to_string()));
+                                gave_up = true;
+                            }
+                        }
+                    }
+                    if !gave_up {
+                        continue;
+                    }
+                    let _ = tx_events.send(VoiceEvent::GaveUp);
+                    VoiceMessage::Leave
+                }
+                _ = gate_poll.tick() => {
+                    // Muting the driver stops the speaking flag while the gate is closed (or self-muted).
+                    if let Some(d) = driver.as_mut() {
+                        let mute = !controls.transmitting.load(Ordering::Relaxed);
+                        if d.is_mute() != mute {
+                            d.mute(mute);
+                            if let Some(ref user_id) = self_id {
+                                let _ = tx_events.send(VoiceEvent::Speaking { user_id: user_id.clone(), speaking: !mute });
+                            }
+                        }
+                    }
+                    continue;
+                }
+                _ = device_poll.tick() => {
+                    if let (Some(d), Some(a)) = (driver.as_mut(), audio.as_mut()) {
+                        if a.needs_listing(&controls) {
+                            let (inputs, outputs) = list_devices().await;
+                            for notice in a.check_devices(d, &controls, &inputs, &outputs) {
+                                let _ = tx_events.send(VoiceEvent::Notice(notice));
+                            }
+                        }
+                    }
+                    continue;
+                }
+            };
+            eprintln!("[voice] received {}", match &msg {
+                VoiceMessage::Connect { .. } => "Connect",
+                VoiceMessage::SetSelfState { .. } => "SetSelfState",
+                VoiceMessage::Leave => "Leave",
+            });
             match msg {
-                VoiceMessage::Connect { info, input_device, output_device } => {
+                VoiceMessage::Connect { info, input_device, output_device, self_mute, self_deaf } => {
+                    controls.set(self_mute, self_deaf);
                     eprintln!("[voice] connecting to channel {} (input_device={:?}, output_device={:?})", info.channel_id, input_device, output_device);
                     let conn = match connection_info_from_ours(&info) {
                         Some(c) => c,
@@ -51,47 +472,89 @@ pub fn spawn_voice_task(mut rx: mpsc::UnboundedReceiver<VoiceMessage>) {
                             continue;
                         }
                     };
-                    // Create speaker and register VoiceTick handler *before* connect so ticks are handled from first packet.
-                    let (speaker_stream_opt, queue_opt) = match voice_audio::create_speaker_output(output_device.as_deref()) {
-                        Some((stream, queue)) => {
-                            eprintln!("[voice] speaker output created");
-                            (Some(stream), Some(queue))
-                        }
-                        None => {
-                            eprintln!("[voice] no output device available");
-                            (None, None)
-                        }
-                    };
-                    let mut d = Driver::new(Config::default().decode_mode(DecodeMode::Decode));
-                    if let Some(ref queue) = queue_opt {
-                        let handler = voice_audio::VoicePlayback::new(queue.clone());
-                        d.add_global_event(CoreEvent::VoiceTick.into(), handler);
-                        eprintln!("[voice] VoiceTick handler registered");
+                    report_status(&mut status, VoiceStatus::Connecting, &tx_events);
+                    reconnect = Reconnect::default();
+                    if let Some(handle) = latency_probe.take() {
+                        handle.abort();
                     }
-                    if let Err(e) = d.connect(conn).await {
-                        eprintln!("[voice] connect error: {}", e);
+                    conn_info = Some(conn.clone());
+                    if let (Some(d), Some(a)) = (driver.as_mut(), audio.as_mut()) {
+                        // Server move or another channel: keep the driver and reopen only devices that changed.
+                        a.input_wanted = input_device;
+                        a.output_wanted = output_device;
+                        let (inputs, outputs) = list_devices().await;
+                        for notice in a.check_devices(d, &controls, &inputs, &outputs) {
+                            let _ = tx_events.send(VoiceEvent::Notice(notice));
+                        }
+                        // Replaces any connect still in flight; songbird discards the older attempt.
+                        connecting = Some(d.connect(conn));
+                        self_id = Some(info.user_id.clone());
                         continue;
                     }
-                    eprintln!("[voice] driver connected");
-                    if let Some(stream) = speaker_stream_opt {
-                        speaker_stream = Some(stream);
+                    // Open the devices and register the VoiceTick handler *before* connect so ticks are handled from first packet.
+                    let mut d = Driver::new(Config::default().decode_mode(DecodeMode::Decode));
+                    let ssrc_users = voice_audio::SsrcUsers::default();
+                    let playback = voice_audio::VoicePlayback::new(controls.clone(), ssrc_users.clone());
+                    d.add_global_event(CoreEvent::VoiceTick.into(), playback.clone());
+                    eprintln!("[voice] VoiceTick handler registered");
+                    // A configured device that is not plugged in falls back to the default until it returns.
+                    let (inputs, outputs) = list_devices().await;
+                    let mut a = CallAudio {
+                        input_wanted: input_device,
+                        output_wanted: output_device,
+                        input_open: None,
+                        output_open: None,
+                        playback,
+                        mic: None,
+                        speaker: None,
+                        last_notice: None,
+                        last_listing: Instant::now(),
+                    };
+                    let input_target = resolve_device(a.input_wanted.as_deref(), &inputs);
+                    let output_target = resolve_device(a.output_wanted.as_deref(), &outputs);
+                    a.open(&mut d, &controls, input_target, output_target);
+                    let speaking = voice_audio::SpeakingTracker::new(ssrc_users, tx_events.clone());
+                    d.add_global_event(CoreEvent::SpeakingStateUpdate.into(), speaking.clone());
+                    d.add_global_event(CoreEvent::VoiceTick.into(), speaking.clone());
+                    d.add_global_event(CoreEvent::ClientDisconnect.into(), speaking);
+                    for event in [CoreEvent::DriverConnect, CoreEvent::DriverReconnect, CoreEvent::DriverDisconnect] {
+                        d.add_global_event(event.into(), DriverEvents(driver_tx.clone()));
                     }
-                    // Start microphone capture and send it as a live raw PCM source.
-                    if let Some((stream, input)) = voice_audio::create_mic_input(input_device.as_deref()) {
-                        d.play_input(input);
-                        mic_stream = Some(stream);
-                        eprintln!("[voice] microphone input started");
-                    } else {
-                        eprintln!("[voice] no microphone device available");
+                    // Stays muted until the input gate opens.
+                    d.mute(true);
+                    connecting = Some(d.connect(conn));
+                    for (kind, wanted, open) in [
+                        ("Microphone", &a.input_wanted, &a.input_open),
+                        ("Speaker", &a.output_wanted, &a.output_open),
+                    ] {
+                        if let Some(name) = wanted.as_deref().filter(|n| *n != "(Default)" && open.is_none()) {
+                            let _ = tx_events.send(VoiceEvent::Notice(fallback_notice(kind, name)));
+                        }
                     }
+                    audio = Some(a);
                     driver = Some(d);
+                    self_id = Some(info.user_id.clone());
+                }
+                VoiceMessage::SetSelfState { self_mute, self_deaf } => {
+                    controls.set(self_mute, self_deaf);
                 }
                 VoiceMessage::Leave => {
                     if let Some(mut d) = driver.take() {
                         d.leave();
                     }
-                    mic_stream = None;
-                    speaker_stream = None;
+                    // Dropping the pending connect cancels it; leave() tells the driver to stop trying.
+                    connecting = None;
+                    if let Some(handle) = latency_probe.take() {
+                        handle.abort();
+                    }
+                    conn_info = None;
+                    reconnect = Reconnect::default();
+                    report_status(&mut status, VoiceStatus::Disconnected, &tx_events);
+                    if let Some(user_id) = self_id.take() {
+                        let _ = tx_events.send(VoiceEvent::Speaking { user_id, speaking: false });
+                    }
+                    audio = None;
+                    controls.transmitting.store(false, Ordering::Relaxed);
                 }
             }
         }
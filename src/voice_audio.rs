@@ -1,17 +1,371 @@
 //! Microphone capture for Songbird driver (voice feature).
+//! The mic passes an input gate (voice activity or push-to-talk) before reaching the driver;
+//! while the gate is closed the driver gets silence and the voice task mutes it.
+//!
+//! Devices run in whatever format they support best (`pick_config`); the mic is downmixed to mono and
+//! the speaker fed from stereo, resampled from / to the driver's 48 kHz when the device rate differs.
+//!
+//! Received voice reaches the speaker through a lock-free ring and an adaptive jitter buffer
+//! (`JitterBuffer`) that the output callback owns, so the real-time thread never takes a lock.
 
 use async_trait::async_trait;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use ringbuf::{traits::*, HeapRb};
+use cpal::{FromSample, Sample, SampleFormat, SizedSample};
+use rubato::{FftFixedInOut, Resampler as _};
+use ringbuf::{traits::*, HeapCons, HeapProd, HeapRb};
 use songbird::input::RawAdapter;
 use std::io::{Read, Seek, SeekFrom};
-use std::collections::VecDeque;
-use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::gateway::VoiceEvent;
+use crate::state::{AppSettings, VoiceInputMode};
+use crate::voice_dsp::MicDsp;
 
 use songbird::{Event, EventContext, EventHandler};
+use tokio::sync::mpsc;
 
+/// Driver rate; mic input is mono, playback stereo.
 const SAMPLE_RATE: u32 = 48_000;
 const CHANNELS: u32 = 1;
+const PLAYBACK_CHANNELS: usize = 2;
+
+/// Supported config closest to what the driver wants: the device rate nearest 48 kHz (no resampling
+/// when offered), then `channels` over more over fewer, then f32 / i16 over other sample formats.
+fn pick_config<I>(ranges: I, channels: u16) -> Option<cpal::SupportedStreamConfig>
+where
+    I: Iterator<Item = cpal::SupportedStreamConfigRange>,
+{
+    ranges
+        .filter_map(|r| {
+            let format_rank = match r.sample_format() {
+                SampleFormat::F32 => 0,
+                SampleFormat::I16 => 1,
+                SampleFormat::I32 | SampleFormat::F64 => 2,
+                SampleFormat::U16 | SampleFormat::I8 | SampleFormat::U8 => 3,
+                SampleFormat::I64 | SampleFormat::U32 | SampleFormat::U64 => 4,
+                _ => return None,
+            };
+            let channel_rank = match r.channels() {
+                c if c == channels => 0,
+                c if c > channels => 1,
+                _ => 2,
+            };
+            let rate = SAMPLE_RATE.clamp(r.min_sample_rate().0, r.max_sample_rate().0);
+            let key = (rate.abs_diff(SAMPLE_RATE), channel_rank, format_rank);
+            Some((key, r.with_sample_rate(cpal::SampleRate(rate))))
+        })
+        .min_by_key(|(key, _)| *key)
+        .map(|(_, config)| config)
+}
+
+/// Fixed-ratio FFT resampler over interleaved samples; input is buffered until a full chunk is there.
+struct Resampler {
+    inner: FftFixedInOut<f32>,
+    channels: usize,
+    /// Pending input per channel.
+    input: Vec<Vec<f32>>,
+    output: Vec<Vec<f32>>,
+}
+
+impl Resampler {
+    /// None when no conversion is needed (or the ratio is unsupported, logged).
+    fn new(from: u32, to: u32, channels: usize) -> Option<Self> {
+        if from == to {
+            return None;
+        }
+        // ~10 ms chunks keep the added latency low.
+        let inner = FftFixedInOut::<f32>::new(from as usize, to as usize, (from / 100) as usize, channels)
+            .map_err(|e| eprintln!("[voice] resampler {} -> {} Hz: {}", from, to, e))
+            .ok()?;
+        let output = inner.output_buffer_allocate(true);
+        Some(Self {
+            inner,
+            channels,
+            input: vec![Vec::new(); channels],
+            output,
+        })
+    }
+
+    /// Input frames consumed per chunk.
+    fn chunk_frames(&self) -> usize {
+        self.inner.input_frames_next()
+    }
+
+    /// Resample interleaved `samples`; every completed chunk is appended (interleaved) to `out`.
+    fn process<E: Extend<f32>>(&mut self, samples: &[f32], out: &mut E) {
+        for frame in samples.chunks_exact(self.channels) {
+            for (pending, &s) in self.input.iter_mut().zip(frame) {
+                pending.push(s);
+            }
+        }
+        while self.input[0].len() >= self.inner.input_frames_next() {
+            match self.inner.process_into_buffer(&self.input, &mut self.output, None) {
+                Ok((used, produced)) => {
+                    for pending in self.input.iter_mut() {
+                        pending.drain(..used);
+                    }
+                    let output = &self.output;
+                    out.extend((0..produced).flat_map(|i| output.iter().map(move |ch| ch[i])));
+                }
+                Err(e) => {
+                    eprintln!("[voice] resample error: {}", e);
+                    self.input.iter_mut().for_each(Vec::clear);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Open `device` for input in its best format and call `on_frames` with 48 kHz mono f32 per block.
+/// A stream error sets `controls.input_failed`.
+fn build_input_stream<F>(device: &cpal::Device, controls: Arc<VoiceControls>, on_frames: F) -> Option<cpal::Stream>
+where
+    F: FnMut(&[f32]) + Send + 'static,
+{
+    let supported = device
+        .supported_input_configs()
+        .ok()
+        .and_then(|ranges| pick_config(ranges, CHANNELS as u16))
+        .or_else(|| device.default_input_config().ok())?;
+    eprintln!(
+        "[voice] mic format: {} Hz, {} ch, {:?}",
+        supported.sample_rate().0,
+        supported.channels(),
+        supported.sample_format()
+    );
+    let config: cpal::StreamConfig = supported.config();
+    match supported.sample_format() {
+        SampleFormat::F32 => input_stream::<f32, F>(device, &config, controls, on_frames),
+        SampleFormat::I16 => input_stream::<i16, F>(device, &config, controls, on_frames),
+        SampleFormat::I32 => input_stream::<i32, F>(device, &config, controls, on_frames),
+        SampleFormat::F64 => input_stream::<f64, F>(device, &config, controls, on_frames),
+        SampleFormat::U16 => input_stream::<u16, F>(device, &config, controls, on_frames),
+        SampleFormat::I8 => input_stream::<i8, F>(device, &config, controls, on_frames),
+        SampleFormat::U8 => input_stream::<u8, F>(device, &config, controls, on_frames),
+        SampleFormat::I64 => input_stream::<i64, F>(device, &config, controls, on_frames),
+        SampleFormat::U32 => input_stream::<u32, F>(device, &config, controls, on_frames),
+        SampleFormat::U64 => input_stream::<u64, F>(device, &config, controls, on_frames),
+        other => {
+            eprintln!("[voice] unsupported mic sample format {:?}", other);
+            None
+        }
+    }
+}
+
+/// Capture in `T`, downmix to mono f32 and resample to 48 kHz before `on_frames`.
+fn input_stream<T, F>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    controls: Arc<VoiceControls>,
+    mut on_frames: F,
+) -> Option<cpal::Stream>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+    F: FnMut(&[f32]) + Send + 'static,
+{
+    let channels = config.channels.max(1) as usize;
+    let mut resampler = Resampler::new(config.sample_rate.0, SAMPLE_RATE, 1);
+    let mut mono: Vec<f32> = Vec::new();
+    let mut resampled: Vec<f32> = Vec::new();
+    let stream = device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                mono.clear();
+                mono.extend(
+                    data.chunks_exact(channels)
+                        .map(|frame| frame.iter().map(|&s| f32::from_sample(s)).sum::<f32>() / channels as f32),
+                );
+                match resampler.as_mut() {
+                    Some(r) => {
+                        resampled.clear();
+                        r.process(&mono, &mut resampled);
+                        on_frames(&resampled);
+                    }
+                    None => on_frames(&mono),
+                }
+            },
+            move |err| {
+                eprintln!("[voice] mic stream error: {}", err);
+                controls.input_failed.store(true, Ordering::Relaxed);
+            },
+            None,
+        )
+        .map_err(|e| eprintln!("[voice] mic stream: {}", e))
+        .ok()?;
+    stream.play().ok()?;
+    Some(stream)
+}
+
+/// f32 stored as bits so audio callbacks can share it without locking.
+#[derive(Debug, Default)]
+pub struct AtomicF32(AtomicU32);
+
+impl AtomicF32 {
+    pub fn new(v: f32) -> Self {
+        Self(AtomicU32::new(v.to_bits()))
+    }
+
+    pub fn load(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn store(&self, v: f32) {
+        self.0.store(v.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Lowest level reported by the input meter (dBFS).
+pub const LEVEL_FLOOR_DB: f32 = -100.0;
+
+/// Call controls shared by the voice task, the cpal callbacks and the VoiceTick handler.
+#[derive(Debug)]
+pub struct VoiceControls {
+    /// Self mute (or deafen): the mic sends silence.
+    pub muted: AtomicBool,
+    /// Self deafen: nothing is queued for or played on the speaker.
+    pub deafened: AtomicBool,
+    /// Gate mode: push-to-talk instead of voice activity.
+    pub push_to_talk: AtomicBool,
+    /// Push-to-talk key is held (set by the global hotkey handler).
+    pub ptt_pressed: AtomicBool,
+    pub vad_threshold_db: AtomicF32,
+    pub vad_hangover_ms: AtomicU32,
+    pub ptt_release_delay_ms: AtomicU32,
+    /// Linear mic gain from the input volume setting.
+    pub input_gain: AtomicF32,
+    /// Last captured block's level (dBFS), for the sensitivity meter.
+    pub input_level_db: AtomicF32,
+    /// Gate open and not muted: the voice task unmutes the driver (speaking flag) only then.
+    pub transmitting: AtomicBool,
+    /// User ID -> playback gain for users with a custom volume or local mute (others play at 1.0).
+    pub user_gains: Mutex<HashMap<String, f32>>,
+    /// Received audio waiting in the jitter buffer (ms).
+    pub playback_latency_ms: AtomicU32,
+    /// Buffer level the jitter buffer currently aims for (ms).
+    pub jitter_target_ms: AtomicU32,
+    /// Times the speaker ran dry since the output was opened.
+    pub playback_underruns: AtomicU32,
+    /// Set by the cpal error callbacks (device unplugged, backend failure); the voice task reopens the device.
+    pub input_failed: AtomicBool,
+    pub output_failed: AtomicBool,
+    /// Mic processing stages (`voice_dsp::MicDsp`).
+    pub noise_suppression: AtomicBool,
+    pub echo_cancellation: AtomicBool,
+    pub auto_gain: AtomicBool,
+}
+
+impl Default for VoiceControls {
+    fn default() -> Self {
+        let defaults = AppSettings::default();
+        let controls = Self {
+            muted: AtomicBool::new(false),
+            deafened: AtomicBool::new(false),
+            push_to_talk: AtomicBool::new(false),
+            ptt_pressed: AtomicBool::new(false),
+            vad_threshold_db: AtomicF32::new(defaults.vad_threshold_db),
+            vad_hangover_ms: AtomicU32::new(defaults.vad_hangover_ms),
+            ptt_release_delay_ms: AtomicU32::new(defaults.ptt_release_delay_ms),
+            input_gain: AtomicF32::new(1.0),
+            input_level_db: AtomicF32::new(LEVEL_FLOOR_DB),
+            transmitting: AtomicBool::new(false),
+            user_gains: Mutex::new(HashMap::new()),
+            playback_latency_ms: AtomicU32::new(0),
+            jitter_target_ms: AtomicU32::new(JITTER_START_MS),
+            playback_underruns: AtomicU32::new(0),
+            input_failed: AtomicBool::new(false),
+            output_failed: AtomicBool::new(false),
+            noise_suppression: AtomicBool::new(defaults.noise_suppression),
+            echo_cancellation: AtomicBool::new(defaults.echo_cancellation),
+            auto_gain: AtomicBool::new(defaults.auto_gain_control),
+        };
+        controls.configure(&defaults);
+        controls
+    }
+}
+
+impl VoiceControls {
+    pub fn set(&self, self_mute: bool, self_deaf: bool) {
+        // Deafening always mutes, as in the official client.
+        self.muted.store(self_mute || self_deaf, Ordering::Relaxed);
+        self.deafened.store(self_deaf, Ordering::Relaxed);
+    }
+
+    /// Apply the input gate and processing settings; takes effect on the next captured block.
+    pub fn configure(&self, s: &AppSettings) {
+        self.push_to_talk
+            .store(s.voice_input_mode == VoiceInputMode::PushToTalk, Ordering::Relaxed);
+        self.vad_threshold_db.store(s.vad_threshold_db);
+        self.vad_hangover_ms.store(s.vad_hangover_ms, Ordering::Relaxed);
+        self.ptt_release_delay_ms.store(s.ptt_release_delay_ms, Ordering::Relaxed);
+        self.input_gain.store(s.voice_input_gain as f32 / 100.0);
+        self.noise_suppression.store(s.noise_suppression, Ordering::Relaxed);
+        self.echo_cancellation.store(s.echo_cancellation, Ordering::Relaxed);
+        self.auto_gain.store(s.auto_gain_control, Ordering::Relaxed);
+        let gains = s.voice_users.iter().map(|(id, u)| (id.clone(), u.gain())).collect();
+        *self.user_gains.lock().unwrap_or_else(|p| p.into_inner()) = gains;
+    }
+
+    fn user_gain(&self, user_id: &str) -> f32 {
+        self.user_gains
+            .lock()
+            .map(|g| g.get(user_id).copied().unwrap_or(1.0))
+            .unwrap_or(1.0)
+    }
+}
+
+/// Controls of the call, shared with the settings UI (gate configuration, push-to-talk key).
+pub fn shared_controls() -> Arc<VoiceControls> {
+    static CONTROLS: OnceLock<Arc<VoiceControls>> = OnceLock::new();
+    CONTROLS.get_or_init(|| Arc::new(VoiceControls::default())).clone()
+}
+
+/// RMS level of a block in dBFS, clamped to `LEVEL_FLOOR_DB`.
+fn level_db(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return LEVEL_FLOOR_DB;
+    }
+    let mean_square = samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32;
+    (10.0 * mean_square.max(1e-10).log10()).max(LEVEL_FLOOR_DB)
+}
+
+/// Opens for voice activity or push-to-talk and stays open for the hangover / release delay.
+#[derive(Default)]
+struct InputGate {
+    open_until: Option<Instant>,
+}
+
+impl InputGate {
+    /// Update the meter and return whether this block should be transmitted.
+    fn process(&mut self, samples: &[f32], controls: &VoiceControls) -> bool {
+        let level = level_db(samples);
+        controls.input_level_db.store(level);
+        let (active, hold_ms) = if controls.push_to_talk.load(Ordering::Relaxed) {
+            (
+                controls.ptt_pressed.load(Ordering::Relaxed),
+                controls.ptt_release_delay_ms.load(Ordering::Relaxed),
+            )
+        } else {
+            (
+                level >= controls.vad_threshold_db.load(),
+                controls.vad_hangover_ms.load(Ordering::Relaxed),
+            )
+        };
+        let now = Instant::now();
+        if active {
+            self.open_until = Some(now + Duration::from_millis(hold_ms as u64));
+        }
+        let open = active || self.open_until.is_some_and(|t| now < t);
+        let transmitting = open && !controls.muted.load(Ordering::Relaxed);
+        controls.transmitting.store(transmitting, Ordering::Relaxed);
+        transmitting
+    }
+}
 
 /// Reader that yields f32 PCM as little-endian bytes from a ring buffer.
 /// Implements Read + Seek (seek unsupported) for Songbird RawAdapter.
@@ -118,7 +472,13 @@ pub fn list_output_devices() -> Vec<String> {
 
 /// Create mic capture and Songbird Input. Returns (stream_handle, Input).
 /// device_name: None or "(Default)" = default device; otherwise match by name.
-pub fn create_mic_input(device_name: Option<&str>) -> Option<(cpal::Stream, songbird::input::Input)> {
+/// Blocks the input gate keeps closed (or that arrive while muted) are replaced by silence.
+/// `echo_reference` is what the speaker plays (`voice_dsp::echo_reference`), for echo cancellation.
+pub fn create_mic_input(
+    device_name: Option<&str>,
+    controls: Arc<VoiceControls>,
+    echo_reference: Option<HeapCons<f32>>,
+) -> Option<(cpal::Stream, songbird::input::Input)> {
     let rb = HeapRb::<f32>::new(SAMPLE_RATE as usize * 2);
     let (mut producer, consumer) = rb.split();
     let reader = MicReader {
@@ -126,6 +486,56 @@ pub fn create_mic_input(device_name: Option<&str>) -> Option<(cpal::Stream, song
         pending: [0u8; 4],
         pending_len: 0,
     };
+    let dsp = MicDsp::new(controls.clone(), echo_reference);
+    let stream = build_mic_stream(device_name, controls, Some(dsp), move |data: &[f32], open: bool| {
+        for &s in data {
+            let _ = producer.try_push(if open { s } else { 0.0 });
+        }
+    })?;
+    let input: songbird::input::Input = RawAdapter::new(reader, SAMPLE_RATE, CHANNELS).into();
+    Some((stream, input))
+}
+
+/// Mic stream that only feeds the level meter and gate state (settings preview outside a call).
+/// The meter reads the processed signal, as the gate does in a call (without echo cancellation).
+pub fn create_input_monitor(device_name: Option<&str>, controls: Arc<VoiceControls>) -> Option<cpal::Stream> {
+    let dsp = MicDsp::new(controls.clone(), None);
+    build_mic_stream(device_name, controls, Some(dsp), |_: &[f32], _: bool| {})
+}
+
+/// "Let's check" loopback: the selected mic, processed as in a call, played back on the selected speaker
+/// after `MIC_TEST_DELAY`. Use private controls so the call's gate and playback counters are untouched.
+/// Returns (mic stream, speaker stream); dropping them ends the test.
+pub fn create_mic_test(
+    input_device: Option<&str>,
+    output_device: Option<&str>,
+    controls: Arc<VoiceControls>,
+) -> Option<(cpal::Stream, cpal::Stream)> {
+    let (echo_producer, echo_consumer) = crate::voice_dsp::echo_reference();
+    let (speaker, mut producer) = create_speaker_output(output_device, controls.clone(), Some(echo_producer))?;
+    // The jitter buffer keeps this lead, so you hear yourself a moment later rather than over your own voice.
+    producer.push_slice(&vec![0i16; ms_to_samples(MIC_TEST_DELAY_MS)]);
+    let dsp = MicDsp::new(controls.clone(), Some(echo_consumer));
+    let mic = build_mic_stream(input_device, controls, Some(dsp), move |data: &[f32], _open: bool| {
+        for &s in data {
+            let s = i16::from_sample(s);
+            let _ = producer.try_push(s);
+            let _ = producer.try_push(s);
+        }
+    })?;
+    Some((mic, speaker))
+}
+
+/// Open the input device and run every captured block through `dsp` and the input gate before `sink(block, open)`.
+fn build_mic_stream<F>(
+    device_name: Option<&str>,
+    controls: Arc<VoiceControls>,
+    mut dsp: Option<MicDsp>,
+    mut sink: F,
+) -> Option<cpal::Stream>
+where
+    F: FnMut(&[f32], bool) + Send + 'static,
+{
     let host = cpal::default_host();
     let device = match device_name {
         None | Some("") | Some("(Default)") => host.default_input_device()?,
@@ -137,33 +547,163 @@ pub fn create_mic_input(device_name: Option<&str>) -> Option<(cpal::Stream, song
     };
     let dev_name = device.name().unwrap_or_else(|_| "?".into());
     eprintln!("[voice] mic device: {}", dev_name);
-    let config = cpal::StreamConfig {
-        channels: 1,
-        sample_rate: cpal::SampleRate(SAMPLE_RATE),
-        buffer_size: cpal::BufferSize::Default,
-    };
-    let stream = device
-        .build_input_stream(
-            &config,
-            move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                for &s in data {
-                    let _ = producer.try_push(s);
-                }
-            },
-            move |err| {
-                eprintln!("[voice] mic stream error: {}", err);
-            },
-            None,
-        )
-        .ok()?;
-    stream.play().ok()?;
-    let input: songbird::input::Input = RawAdapter::new(reader, SAMPLE_RATE, CHANNELS).into();
-    Some((stream, input))
+    let mut gate = InputGate::default();
+    let mut block: Vec<f32> = Vec::new();
+    build_input_stream(&device, controls.clone(), move |data: &[f32]| {
+        // The resampler only emits whole chunks; skip callbacks that completed none.
+        if data.is_empty() {
+            return;
+        }
+        let gain = controls.input_gain.load();
+        block.clear();
+        block.extend(data.iter().map(|s| (s * gain).clamp(-1.0, 1.0)));
+        if let Some(dsp) = dsp.as_mut() {
+            dsp.process(&mut block);
+        }
+        let open = gate.process(&block, &controls);
+        sink(&block, open);
+    })
+}
+
+/// Ring between the VoiceTick handler and the speaker: 500 ms of 48 kHz stereo.
+const PLAYBACK_RING_SAMPLES: usize = SAMPLE_RATE as usize * PLAYBACK_CHANNELS / 2;
+/// Jitter buffer target bounds and starting point (ms).
+const JITTER_MIN_MS: u32 = 40;
+const JITTER_MAX_MS: u32 = 200;
+const JITTER_START_MS: u32 = 60;
+/// Target increase after an underrun, and decrease after `JITTER_RELAX_AFTER` without one.
+const JITTER_GROW_MS: u32 = 20;
+const JITTER_SHRINK_MS: u32 = 10;
+const JITTER_RELAX_AFTER: Duration = Duration::from_secs(10);
+/// Buffered audio this far past the target is dropped (clock drift, bursts after a stall).
+const JITTER_EXCESS_MS: u32 = 100;
+/// Playback delay of the mic test (ms).
+const MIC_TEST_DELAY_MS: u32 = 100;
+/// Length of the fade out on underrun and the fade in when playback resumes (frames, 5 ms).
+const FADE_FRAMES: usize = SAMPLE_RATE as usize / 200;
+
+fn ms_to_samples(ms: u32) -> usize {
+    SAMPLE_RATE as usize / 1000 * ms as usize * PLAYBACK_CHANNELS
+}
+
+fn samples_to_ms(samples: usize) -> u32 {
+    (samples / PLAYBACK_CHANNELS * 1000 / SAMPLE_RATE as usize) as u32
+}
+
+/// Adaptive jitter buffer on the consumer side of the playback ring, owned by the output callback.
+/// Playback starts once the target is buffered; on underrun the last frame fades out, the target grows
+/// and the buffer refills before fading back in. The target shrinks again while playback stays smooth.
+struct JitterBuffer {
+    cons: HeapCons<i16>,
+    controls: Arc<VoiceControls>,
+    /// Receives the mono mix of every frame played, as the mic's echo cancellation reference.
+    echo_reference: Option<HeapProd<f32>>,
+    /// False while (re)filling up to the target.
+    playing: bool,
+    target_ms: u32,
+    /// Last frame played, faded out while concealing an underrun.
+    last: [f32; 2],
+    fade_out: usize,
+    fade_in: usize,
+    /// Frames played since the last underrun or target change.
+    smooth_frames: usize,
+}
+
+impl JitterBuffer {
+    fn new(cons: HeapCons<i16>, controls: Arc<VoiceControls>, echo_reference: Option<HeapProd<f32>>) -> Self {
+        controls.jitter_target_ms.store(JITTER_START_MS, Ordering::Relaxed);
+        controls.playback_latency_ms.store(0, Ordering::Relaxed);
+        controls.playback_underruns.store(0, Ordering::Relaxed);
+        Self {
+            cons,
+            controls,
+            echo_reference,
+            playing: false,
+            target_ms: JITTER_START_MS,
+            last: [0.0; 2],
+            fade_out: 0,
+            fade_in: 0,
+            smooth_frames: 0,
+        }
+    }
+
+    fn set_target(&mut self, ms: u32) {
+        self.target_ms = ms.clamp(JITTER_MIN_MS, JITTER_MAX_MS);
+        self.smooth_frames = 0;
+        self.controls.jitter_target_ms.store(self.target_ms, Ordering::Relaxed);
+    }
+
+    /// Once per callback: drop everything while deafened, trim excess, relax the target, publish the level.
+    fn update(&mut self, deafened: bool) {
+        if deafened {
+            self.cons.skip(self.cons.occupied_len());
+            self.playing = false;
+            self.fade_out = 0;
+        }
+        let limit = ms_to_samples(self.target_ms + JITTER_EXCESS_MS);
+        let occupied = self.cons.occupied_len();
+        if occupied > limit {
+            // Keep whole frames so channels stay aligned.
+            let excess = occupied - ms_to_samples(self.target_ms);
+            self.cons.skip(excess - excess % PLAYBACK_CHANNELS);
+        }
+        if self.smooth_frames >= JITTER_RELAX_AFTER.as_millis() as usize * SAMPLE_RATE as usize / 1000 {
+            self.set_target(self.target_ms.saturating_sub(JITTER_SHRINK_MS));
+        }
+        self.controls
+            .playback_latency_ms
+            .store(samples_to_ms(self.cons.occupied_len()), Ordering::Relaxed);
+    }
+
+    /// Next 48 kHz stereo frame, also handed to the echo reference.
+    fn next_frame(&mut self) -> [f32; 2] {
+        let frame = self.take_frame();
+        if let Some(reference) = self.echo_reference.as_mut() {
+            let _ = reference.try_push((frame[0] + frame[1]) * 0.5);
+        }
+        frame
+    }
+
+    /// Buffered audio, or a fade to silence while (re)filling.
+    fn take_frame(&mut self) -> [f32; 2] {
+        if !self.playing && self.cons.occupied_len() >= ms_to_samples(self.target_ms) {
+            self.playing = true;
+            self.fade_in = FADE_FRAMES;
+        }
+        if self.playing {
+            if self.cons.occupied_len() >= PLAYBACK_CHANNELS {
+                let l = self.cons.try_pop().map_or(0.0, f32::from_sample);
+                let r = self.cons.try_pop().map_or(0.0, f32::from_sample);
+                let gain = 1.0 - self.fade_in as f32 / FADE_FRAMES as f32;
+                self.fade_in = self.fade_in.saturating_sub(1);
+                self.last = [l * gain, r * gain];
+                self.smooth_frames += 1;
+                return self.last;
+            }
+            // Underrun: conceal it, wait for more and buffer deeper from now on.
+            self.playing = false;
+            self.fade_out = FADE_FRAMES;
+            self.controls.playback_underruns.fetch_add(1, Ordering::Relaxed);
+            self.set_target(self.target_ms + JITTER_GROW_MS);
+        }
+        if self.fade_out == 0 {
+            return [0.0; 2];
+        }
+        let gain = self.fade_out as f32 / FADE_FRAMES as f32;
+        self.fade_out -= 1;
+        [self.last[0] * gain, self.last[1] * gain]
+    }
 }
 
-/// Create a speaker output stream (48kHz stereo i16) and a shared queue for samples.
+/// Create a speaker output stream fed with 48 kHz stereo i16 samples through the returned ring producer.
 /// device_name: None or "(Default)" = default device; otherwise match by name.
-pub fn create_speaker_output(device_name: Option<&str>) -> Option<(cpal::Stream, Arc<Mutex<VecDeque<i16>>>)> {
+/// While `controls.deafened` is set, buffered audio is dropped and silence is played.
+/// Everything played is also pushed to `echo_reference` (see `create_mic_input`).
+pub fn create_speaker_output(
+    device_name: Option<&str>,
+    controls: Arc<VoiceControls>,
+    echo_reference: Option<HeapProd<f32>>,
+) -> Option<(cpal::Stream, HeapProd<i16>)> {
     let host = cpal::default_host();
     let device = match device_name {
         None | Some("") | Some("(Default)") => host.default_output_device()?,
@@ -175,50 +715,142 @@ pub fn create_speaker_output(device_name: Option<&str>) -> Option<(cpal::Stream,
     };
     let dev_name = device.name().unwrap_or_else(|_| "?".into());
     eprintln!("[voice] speaker device: {}", dev_name);
-    let config = cpal::StreamConfig {
-        channels: 2,
-        sample_rate: cpal::SampleRate(SAMPLE_RATE),
-        buffer_size: cpal::BufferSize::Default,
-    };
-    let queue: Arc<Mutex<VecDeque<i16>>> = Arc::new(Mutex::new(VecDeque::new()));
-    let q = queue.clone();
+    let supported = device
+        .supported_output_configs()
+        .ok()
+        .and_then(|ranges| pick_config(ranges, PLAYBACK_CHANNELS as u16))
+        .or_else(|| device.default_output_config().ok())?;
+    eprintln!(
+        "[voice] speaker format: {} Hz, {} ch, {:?}",
+        supported.sample_rate().0,
+        supported.channels(),
+        supported.sample_format()
+    );
+    let config: cpal::StreamConfig = supported.config();
+    let (prod, cons) = HeapRb::<i16>::new(PLAYBACK_RING_SAMPLES).split();
+    let jb = JitterBuffer::new(cons, controls, echo_reference);
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => output_stream::<f32>(&device, &config, jb),
+        SampleFormat::I16 => output_stream::<i16>(&device, &config, jb),
+        SampleFormat::I32 => output_stream::<i32>(&device, &config, jb),
+        SampleFormat::F64 => output_stream::<f64>(&device, &config, jb),
+        SampleFormat::U16 => output_stream::<u16>(&device, &config, jb),
+        SampleFormat::I8 => output_stream::<i8>(&device, &config, jb),
+        SampleFormat::U8 => output_stream::<u8>(&device, &config, jb),
+        SampleFormat::I64 => output_stream::<i64>(&device, &config, jb),
+        SampleFormat::U32 => output_stream::<u32>(&device, &config, jb),
+        SampleFormat::U64 => output_stream::<u64>(&device, &config, jb),
+        other => {
+            eprintln!("[voice] unsupported speaker sample format {:?}", other);
+            None
+        }
+    }?;
+    Some((stream, prod))
+}
+
+/// Play the jitter buffer's 48 kHz stereo on a device running in `T` at its own rate and channel count.
+/// Mono devices get the average of both channels; channels past the second stay silent.
+fn output_stream<T>(device: &cpal::Device, config: &cpal::StreamConfig, mut jb: JitterBuffer) -> Option<cpal::Stream>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels.max(1) as usize;
+    let mut resampler = Resampler::new(SAMPLE_RATE, config.sample_rate.0, PLAYBACK_CHANNELS);
+    // Stereo f32 at the device rate, waiting to be written.
+    let mut pending: VecDeque<f32> = VecDeque::new();
+    let mut chunk: Vec<f32> = Vec::new();
+    let errors = jb.controls.clone();
     let stream = device
         .build_output_stream(
-            &config,
-            move |data: &mut [i16], _: &cpal::OutputCallbackInfo| {
-                let mut guard = match q.lock() {
-                    Ok(g) => g,
-                    Err(poisoned) => poisoned.into_inner(),
-                };
-                for s in data.iter_mut() {
-                    *s = guard.pop_front().unwrap_or(0);
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                let deafened = jb.controls.deafened.load(Ordering::Relaxed);
+                jb.update(deafened);
+                if deafened {
+                    pending.clear();
+                    data.fill(T::EQUILIBRIUM);
+                    return;
+                }
+                let needed = data.len() / channels * PLAYBACK_CHANNELS;
+                while pending.len() < needed {
+                    match resampler.as_mut() {
+                        Some(r) => {
+                            chunk.clear();
+                            for _ in 0..r.chunk_frames() {
+                                chunk.extend(jb.next_frame());
+                            }
+                            r.process(&chunk, &mut pending);
+                        }
+                        None => pending.extend(jb.next_frame()),
+                    }
                 }
-                // Prevent unbounded growth if producer outruns consumer.
-                const MAX_SAMPLES: usize = (SAMPLE_RATE as usize) * 2 * 2; // ~2s stereo
-                if guard.len() > MAX_SAMPLES {
-                    let drain = guard.len() - MAX_SAMPLES;
-                    for _ in 0..drain {
-                        let _ = guard.pop_front();
+                for frame in data.chunks_exact_mut(channels) {
+                    let l = pending.pop_front().unwrap_or(0.0);
+                    let r = pending.pop_front().unwrap_or(0.0);
+                    match frame {
+                        [mono] => *mono = T::from_sample((l + r) * 0.5),
+                        [fl, fr, rest @ ..] => {
+                            *fl = T::from_sample(l);
+                            *fr = T::from_sample(r);
+                            rest.fill(T::EQUILIBRIUM);
+                        }
+                        [] => {}
                     }
                 }
             },
-            move |err| eprintln!("[voice] speaker stream error: {}", err),
+            move |err| {
+                eprintln!("[voice] speaker stream error: {}", err);
+                errors.output_failed.store(true, Ordering::Relaxed);
+            },
             None,
         )
+        .map_err(|e| eprintln!("[voice] speaker stream: {}", e))
         .ok()?;
     stream.play().ok()?;
-    Some((stream, queue))
+    Some(stream)
 }
 
-/// Songbird global event handler: mixes decoded voice and pushes to speaker queue.
+/// SSRC -> user ID of one call, learned from SpeakingStateUpdate by `SpeakingTracker`.
+pub type SsrcUsers = Arc<Mutex<HashMap<u32, String>>>;
+
+/// Songbird global event handler: mixes decoded voice at each user's gain and pushes it into the speaker ring.
+/// The producer lock is only contended by Songbird's event thread and speaker reopens, never by the output callback.
 #[derive(Clone)]
 pub struct VoicePlayback {
-    queue: Arc<Mutex<VecDeque<i16>>>,
+    producer: Arc<Mutex<Option<HeapProd<i16>>>>,
+    controls: Arc<VoiceControls>,
+    users: SsrcUsers,
 }
 
 impl VoicePlayback {
-    pub fn new(queue: Arc<Mutex<VecDeque<i16>>>) -> Self {
-        Self { queue }
+    /// Starts without a speaker; see `set_output`.
+    pub fn new(controls: Arc<VoiceControls>, users: SsrcUsers) -> Self {
+        Self {
+            producer: Arc::default(),
+            controls,
+            users,
+        }
+    }
+
+    /// Feed a (new) speaker ring from `create_speaker_output`, or none while no speaker is open.
+    pub fn set_output(&self, producer: Option<HeapProd<i16>>) {
+        *self.producer.lock().unwrap_or_else(|p| p.into_inner()) = producer;
+    }
+
+    /// Queue 48 kHz stereo for the speaker, if one is open.
+    /// A full ring drops the newest audio; the jitter buffer trims back to its target.
+    fn push(&self, samples: &[i16]) {
+        if let Ok(mut guard) = self.producer.lock() {
+            if let Some(producer) = guard.as_mut() {
+                producer.push_slice(samples);
+            }
+        }
+    }
+
+    /// Gain for an SSRC; unity until we know whose it is.
+    fn gain(&self, ssrc: u32) -> f32 {
+        let user = self.users.lock().ok().and_then(|u| u.get(&ssrc).cloned());
+        user.map(|id| self.controls.user_gain(&id)).unwrap_or(1.0)
     }
 }
 
@@ -227,12 +859,18 @@ impl EventHandler for VoicePlayback {
     async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
         use EventContext as Ctx;
         match ctx {
+            Ctx::VoiceTick(_) if self.controls.deafened.load(Ordering::Relaxed) => {}
             Ctx::VoiceTick(tick) => {
                 let n_speakers = tick.speaking.len();
                 // Mix all speakers into one PCM buffer (i16 stereo interleaved).
                 let mut mix: Option<Vec<i32>> = None;
-                for (_ssrc, data) in &tick.speaking {
+                for (ssrc, data) in &tick.speaking {
                     let Some(pcm) = data.decoded_voice.as_ref() else { continue };
+                    let gain = self.gain(*ssrc);
+                    if gain <= 0.0 {
+                        continue;
+                    }
+                    let scale = |s: i16| (s as f32 * gain) as i32;
                     let m = mix.get_or_insert_with(|| vec![0i32; pcm.len()]);
                     if m.len() != pcm.len() {
                         // Length mismatch: resize mix to max and mix what we can (e.g. mono vs stereo).
@@ -241,11 +879,11 @@ impl EventHandler for VoicePlayback {
                         }
                         let len = m.len().min(pcm.len());
                         for (i, &s) in pcm[..len].iter().enumerate() {
-                            m[i] += s as i32;
+                            m[i] += scale(s);
                         }
                     } else {
                         for (i, &s) in pcm.iter().enumerate() {
-                            m[i] += s as i32;
+                            m[i] += scale(s);
                         }
                     }
                 }
@@ -263,10 +901,105 @@ impl EventHandler for VoicePlayback {
                         let v = v.clamp(i16::MIN as i32, i16::MAX as i32);
                         out.push(v as i16);
                     }
-                    if let Ok(mut guard) = self.queue.lock() {
-                        guard.extend(out);
+                    self.push(&out);
+                } else {
+                    // Ticks keep coming while nobody speaks: queue their silence so the end of speech
+                    // does not look like an underrun to the jitter buffer.
+                    self.push(&[0i16; SAMPLE_RATE as usize / 50 * PLAYBACK_CHANNELS]);
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Decoded peaks below this (about -60 dBFS) count as silence; Discord sends a few silent frames after speech.
+const SPEAKING_MIN_AMPLITUDE: u16 = 33;
+/// Keep the indicator lit over short pauses between words.
+const SPEAKING_HOLD: Duration = Duration::from_millis(250);
+
+/// Songbird global event handler: maps SSRCs to users and reports who is speaking as `VoiceEvent`s.
+#[derive(Clone)]
+pub struct SpeakingTracker {
+    users: SsrcUsers,
+    /// Last tick with audible voice per SSRC; present while reported as speaking.
+    last_heard: Arc<Mutex<HashMap<u32, Instant>>>,
+    tx: mpsc::UnboundedSender<VoiceEvent>,
+}
+
+impl SpeakingTracker {
+    pub fn new(users: SsrcUsers, tx: mpsc::UnboundedSender<VoiceEvent>) -> Self {
+        Self {
+            users,
+            last_heard: Arc::new(Mutex::new(HashMap::new())),
+            tx,
+        }
+    }
+
+    fn send(&self, user_id: &str, speaking: bool) {
+        let _ = self.tx.send(VoiceEvent::Speaking {
+            user_id: user_id.to_string(),
+            speaking,
+        });
+    }
+}
+
+#[async_trait]
+impl EventHandler for SpeakingTracker {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        use EventContext as Ctx;
+        // Lock order: users, then last_heard.
+        let mut users = self.users.lock().unwrap_or_else(|p| p.into_inner());
+        let mut last_heard = self.last_heard.lock().unwrap_or_else(|p| p.into_inner());
+        match ctx {
+            Ctx::SpeakingStateUpdate(update) => {
+                if let Some(user) = update.user_id {
+                    let user = user.0.to_string();
+                    // Audio can arrive before the mapping; light up anyone already talking.
+                    if last_heard.contains_key(&update.ssrc) {
+                        self.send(&user, true);
+                    }
+                    users.insert(update.ssrc, user);
+                }
+            }
+            Ctx::VoiceTick(tick) => {
+                let now = Instant::now();
+                for (ssrc, data) in &tick.speaking {
+                    let audible = data
+                        .decoded_voice
+                        .as_ref()
+                        .is_some_and(|pcm| pcm.iter().any(|s| s.unsigned_abs() >= SPEAKING_MIN_AMPLITUDE));
+                    if !audible {
+                        continue;
+                    }
+                    if last_heard.insert(*ssrc, now).is_none() {
+                        if let Some(user) = users.get(ssrc) {
+                            self.send(user, true);
+                        }
                     }
                 }
+                last_heard.retain(|ssrc, heard| {
+                    let keep = now.duration_since(*heard) < SPEAKING_HOLD;
+                    if !keep {
+                        if let Some(user) = users.get(ssrc) {
+                            self.send(user, false);
+                        }
+                    }
+                    keep
+                });
+            }
+            Ctx::ClientDisconnect(disconnect) => {
+                let user = disconnect.user_id.0.to_string();
+                users.retain(|ssrc, u| {
+                    if *u != user {
+                        return true;
+                    }
+                    if last_heard.remove(ssrc).is_some() {
+                        self.send(u, false);
+                    }
+                    false
+                });
             }
             _ => {}
         }
@@ -0,0 +1,578 @@
+//! Microphone processing between capture and the input gate (voice feature).
+//! Echo cancellation against what the speaker plays, then noise suppression, then automatic gain
+//! control; each stage is switched by `VoiceControls` and works on 48 kHz mono f32.
+//!
+//! The echo reference is a ring the speaker output fills with the mono mix it plays; the mic side
+//! takes as many reference samples as it captured, so both stay in step. The reference is queued
+//! when the speaker buffer is filled, so its echo reaches the mic tens of milliseconds later (output
+//! buffering, the air path, input buffering); that bulk delay is estimated and the reference delayed
+//! by it, leaving the adaptive filter only the echo path itself to model.
+
+use realfft::num_complex::Complex;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use ringbuf::{traits::*, HeapCons, HeapProd, HeapRb};
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::voice_audio::VoiceControls;
+
+const SAMPLE_RATE: usize = 48_000;
+
+/// Far-end reference ring: 1 s of 48 kHz mono.
+pub fn echo_reference() -> (HeapProd<f32>, HeapCons<f32>) {
+    HeapRb::<f32>::new(SAMPLE_RATE).split()
+}
+
+/// Reference older than this is skipped (the mic started late or stalled) (samples, 100 ms).
+const ECHO_MAX_BACKLOG: usize = SAMPLE_RATE / 10;
+/// Echo tail the canceller models past the bulk delay (taps, ~21 ms at 48 kHz).
+const ECHO_TAPS: usize = 1024;
+/// Longest bulk delay searched for (samples, 250 ms).
+const ECHO_MAX_DELAY: usize = SAMPLE_RATE / 4;
+/// The filter starts this far before the estimated delay, so the estimate can wobble (samples, 4 ms).
+const ECHO_DELAY_MARGIN: usize = SAMPLE_RATE / 250;
+/// Far-end window energy below which there is no echo to cancel and the filter is skipped.
+const ECHO_SILENT_ENERGY: f32 = 1e-6;
+/// NLMS step size.
+const ECHO_STEP: f32 = 0.3;
+/// Geigel double-talk detector: near-end speech when the mic exceeds this fraction of the recent far-end peak.
+const DOUBLE_TALK_RATIO: f32 = 0.5;
+/// Keep adaptation frozen this long after double talk (samples, 30 ms).
+const DOUBLE_TALK_HOLD: usize = SAMPLE_RATE * 30 / 1000;
+
+/// Normalized LMS filter that predicts the echo of the far-end signal and subtracts it.
+struct EchoCanceller {
+    weights: Vec<f32>,
+    /// Far-end history, stored twice so the newest `ECHO_TAPS` are always one contiguous slice.
+    history: Vec<f32>,
+    pos: usize,
+    energy: f32,
+    far_peak: f32,
+    hold: usize,
+}
+
+impl EchoCanceller {
+    fn new() -> Self {
+        Self {
+            weights: vec![0.0; ECHO_TAPS],
+            history: vec![0.0; ECHO_TAPS * 2],
+            pos: 0,
+            energy: 0.0,
+            far_peak: 0.0,
+            hold: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.weights.fill(0.0);
+        self.history.fill(0.0);
+        self.energy = 0.0;
+        self.far_peak = 0.0;
+        self.hold = 0;
+    }
+
+    fn process(&mut self, mic: &mut [f32], far: &[f32]) {
+        for (d, &x) in mic.iter_mut().zip(far) {
+            // Newest sample first: history[pos..pos + ECHO_TAPS].
+            self.pos = self.pos.checked_sub(1).unwrap_or(ECHO_TAPS - 1);
+            let oldest = self.history[self.pos + ECHO_TAPS];
+            self.history[self.pos] = x;
+            self.history[self.pos + ECHO_TAPS] = x;
+            self.energy = (self.energy + x * x - oldest * oldest).max(0.0);
+            self.far_peak = x.abs().max(self.far_peak * 0.9995);
+            if self.energy <= ECHO_SILENT_ENERGY {
+                self.hold = self.hold.saturating_sub(1);
+                continue;
+            }
+
+            let window = &self.history[self.pos..self.pos + ECHO_TAPS];
+            let estimate: f32 = self.weights.iter().zip(window).map(|(w, x)| w * x).sum();
+            let error = *d - estimate;
+
+            if d.abs() > DOUBLE_TALK_RATIO * self.far_peak {
+                self.hold = DOUBLE_TALK_HOLD;
+            } else {
+                self.hold = self.hold.saturating_sub(1);
+            }
+            // Adapt only on far-end-only audio; near-end speech would pull the filter away from the echo path.
+            if self.hold == 0 {
+                let step = ECHO_STEP * error / (self.energy + 1e-3);
+                for (w, x) in self.weights.iter_mut().zip(window) {
+                    *w += step * x;
+                }
+            }
+            *d = error;
+        }
+    }
+}
+
+/// Delay search resolution: the signals are reduced to envelopes of 2 ms bins.
+const DELAY_BIN: usize = SAMPLE_RATE / 500;
+const DELAY_LAGS: usize = ECHO_MAX_DELAY / DELAY_BIN;
+/// Weight of each new bin in the smoothed cross-correlation (~0.5 s time constant).
+const DELAY_SMOOTHING: f32 = 0.004;
+/// Far-end bins quieter than this (mean magnitude) carry nothing to correlate.
+const DELAY_FAR_ACTIVE: f32 = 1e-3;
+/// A delay is taken once it has been the clear correlation peak for this many bins (200 ms).
+const DELAY_STABLE_BINS: usize = 100;
+/// The peak must stand this far above the average correlation magnitude.
+const DELAY_PEAK_RATIO: f32 = 3.0;
+
+/// Bulk delay from the reference to its echo in the mic: the lag that best correlates the onsets
+/// (first differences of 2 ms envelopes) of both; cheap enough to run in the mic callback.
+struct DelayEstimator {
+    far_sum: f32,
+    mic_sum: f32,
+    count: usize,
+    last_far: f32,
+    last_mic: f32,
+    /// Far-end onsets, newest first; index = lag in bins.
+    far_onsets: VecDeque<f32>,
+    corr: Vec<f32>,
+    candidate: usize,
+    candidate_bins: usize,
+    /// Estimated delay (samples), once found.
+    delay: Option<usize>,
+}
+
+impl DelayEstimator {
+    fn new() -> Self {
+        Self {
+            far_sum: 0.0,
+            mic_sum: 0.0,
+            count: 0,
+            last_far: 0.0,
+            last_mic: 0.0,
+            far_onsets: vec![0.0; DELAY_LAGS].into(),
+            corr: vec![0.0; DELAY_LAGS],
+            candidate: 0,
+            candidate_bins: 0,
+            delay: None,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.far_sum = 0.0;
+        self.mic_sum = 0.0;
+        self.count = 0;
+        self.last_far = 0.0;
+        self.last_mic = 0.0;
+        self.far_onsets.iter_mut().for_each(|f| *f = 0.0);
+        self.corr.fill(0.0);
+        self.candidate_bins = 0;
+        self.delay = None;
+    }
+
+    /// Feed one block of reference (as paired with the mic) and the mic before cancellation.
+    fn process(&mut self, far: &[f32], mic: &[f32]) {
+        for (&x, &d) in far.iter().zip(mic) {
+            self.far_sum += x.abs();
+            self.mic_sum += d.abs();
+            self.count += 1;
+            if self.count == DELAY_BIN {
+                self.update_bin();
+            }
+        }
+    }
+
+    fn update_bin(&mut self) {
+        let far = self.far_sum / DELAY_BIN as f32;
+        let mic = self.mic_sum / DELAY_BIN as f32;
+        self.far_sum = 0.0;
+        self.mic_sum = 0.0;
+        self.count = 0;
+        let far_onset = far - self.last_far;
+        let mic_onset = mic - self.last_mic;
+        self.last_far = far;
+        self.last_mic = mic;
+        self.far_onsets.pop_back();
+        self.far_onsets.push_front(far_onset);
+        if far < DELAY_FAR_ACTIVE {
+            return;
+        }
+        for (c, &f) in self.corr.iter_mut().zip(&self.far_onsets) {
+            *c += DELAY_SMOOTHING * (mic_onset * f - *c);
+        }
+        let (best, peak) = self
+            .corr
+            .iter()
+            .enumerate()
+            .fold((0, f32::MIN), |best, (lag, &c)| if c > best.1 { (lag, c) } else { best });
+        let average = self.corr.iter().map(|c| c.abs()).sum::<f32>() / DELAY_LAGS as f32;
+        if peak <= 0.0 || peak < DELAY_PEAK_RATIO * average {
+            self.candidate_bins = 0;
+            return;
+        }
+        if best == self.candidate {
+            self.candidate_bins += 1;
+        } else {
+            self.candidate = best;
+            self.candidate_bins = 1;
+        }
+        if self.candidate_bins >= DELAY_STABLE_BINS {
+            self.delay = Some(best * DELAY_BIN);
+        }
+    }
+}
+
+/// STFT hop (10 ms); frames are two hops long with a square-root Hann window on analysis and synthesis.
+const NS_HOP: usize = SAMPLE_RATE / 100;
+const NS_FRAME: usize = NS_HOP * 2;
+/// Noise is over-subtracted by this factor; the gain never drops below the floor (-20 dB).
+const NS_OVERSUBTRACT: f32 = 2.0;
+const NS_GAIN_FLOOR: f32 = 0.1;
+/// Per-frame rise of the noise estimate while the signal stays above it (~+2 dB/s).
+const NS_NOISE_RISE: f32 = 1.005;
+
+/// Spectral subtraction with a minimum-tracking noise estimate; adds one hop of latency.
+struct NoiseSuppressor {
+    fft: Arc<dyn RealToComplex<f32>>,
+    ifft: Arc<dyn ComplexToReal<f32>>,
+    window: Vec<f32>,
+    /// Last `NS_FRAME` input samples.
+    frame: Vec<f32>,
+    input: VecDeque<f32>,
+    output: VecDeque<f32>,
+    overlap: Vec<f32>,
+    time: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    noise: Vec<f32>,
+    gains: Vec<f32>,
+    primed: bool,
+}
+
+impl NoiseSuppressor {
+    fn new() -> Self {
+        let mut planner = RealFftPlanner::<f32>::new();
+        let fft = planner.plan_fft_forward(NS_FRAME);
+        let ifft = planner.plan_fft_inverse(NS_FRAME);
+        let spectrum = fft.make_output_vec();
+        let scratch_len = fft.get_scratch_len().max(ifft.get_scratch_len());
+        let bins = spectrum.len();
+        let window = (0..NS_FRAME)
+            .map(|n| (std::f32::consts::PI * n as f32 / NS_FRAME as f32).sin())
+            .collect();
+        Self {
+            fft,
+            ifft,
+            window,
+            frame: vec![0.0; NS_FRAME],
+            input: VecDeque::with_capacity(NS_HOP * 2),
+            output: vec![0.0; NS_HOP].into(),
+            overlap: vec![0.0; NS_HOP],
+            time: vec![0.0; NS_FRAME],
+            spectrum,
+            scratch: vec![Complex::default(); scratch_len],
+            noise: vec![0.0; bins],
+            gains: vec![1.0; bins],
+            primed: false,
+        }
+    }
+
+    /// Forget the signal and noise estimate without replanning the transforms.
+    fn reset(&mut self) {
+        self.frame.fill(0.0);
+        self.input.clear();
+        self.output.clear();
+        self.output.resize(NS_HOP, 0.0);
+        self.overlap.fill(0.0);
+        self.gains.fill(1.0);
+        self.primed = false;
+    }
+
+    fn process(&mut self, block: &mut [f32]) {
+        for &s in block.iter() {
+            self.input.push_back(s);
+            if self.input.len() == NS_HOP {
+                self.process_hop();
+            }
+        }
+        for s in block.iter_mut() {
+            *s = self.output.pop_front().unwrap_or(0.0);
+        }
+    }
+
+    fn process_hop(&mut self) {
+        self.frame.copy_within(NS_HOP.., 0);
+        for (dst, src) in self.frame[NS_HOP..].iter_mut().zip(self.input.drain(..)) {
+            *dst = src;
+        }
+        for ((t, &s), &w) in self.time.iter_mut().zip(&self.frame).zip(&self.window) {
+            *t = s * w;
+        }
+        if self
+            .fft
+            .process_with_scratch(&mut self.time, &mut self.spectrum, &mut self.scratch)
+            .is_err()
+        {
+            return;
+        }
+        for ((bin, noise), gain) in self.spectrum.iter_mut().zip(&mut self.noise).zip(&mut self.gains) {
+            let power = bin.norm_sqr();
+            if !self.primed || power < *noise {
+                *noise = if self.primed { 0.7 * *noise + 0.3 * power } else { power };
+            } else {
+                *noise = (*noise * NS_NOISE_RISE).min(power);
+            }
+            let target = ((power - NS_OVERSUBTRACT * *noise) / power.max(1e-12))
+                .max(NS_GAIN_FLOOR * NS_GAIN_FLOOR)
+                .sqrt();
+            // Open instantly for speech onsets, close gradually to avoid musical noise.
+            *gain = if target > *gain { target } else { 0.5 * *gain + 0.5 * target };
+            *bin *= *gain;
+        }
+        self.primed = true;
+        // The inverse transform needs purely real DC and Nyquist bins.
+        let last = self.spectrum.len() - 1;
+        self.spectrum[0].im = 0.0;
+        self.spectrum[last].im = 0.0;
+        if self
+            .ifft
+            .process_with_scratch(&mut self.spectrum, &mut self.time, &mut self.scratch)
+            .is_err()
+        {
+            return;
+        }
+        let scale = 1.0 / NS_FRAME as f32;
+        for (i, (t, &w)) in self.time.iter_mut().zip(&self.window).enumerate() {
+            *t *= w * scale;
+            if i < NS_HOP {
+                self.output.push_back(*t + self.overlap[i]);
+            }
+        }
+        self.overlap.copy_from_slice(&self.time[NS_HOP..]);
+    }
+}
+
+/// Speech level the gain control aims for (dBFS RMS) and the range of gain it may apply (dB).
+const AGC_TARGET_DB: f32 = -22.0;
+const AGC_MIN_GAIN_DB: f32 = -12.0;
+const AGC_MAX_GAIN_DB: f32 = 12.0;
+/// Blocks quieter than this are not speech and leave the gain alone.
+const AGC_GATE_DB: f32 = -55.0;
+/// Gain change rates (dB per ms): loud input is pulled down fast, quiet input raised slowly.
+const AGC_ATTACK_DB_PER_MS: f32 = 0.1;
+const AGC_RELEASE_DB_PER_MS: f32 = 0.005;
+/// Peak ceiling after gain.
+const AGC_LIMIT: f32 = 0.9;
+
+/// Slow level normalization with a peak limiter.
+struct AutoGain {
+    gain_db: f32,
+}
+
+impl AutoGain {
+    fn process(&mut self, block: &mut [f32]) {
+        let mean_square = block.iter().map(|s| s * s).sum::<f32>() / block.len() as f32;
+        let level = 10.0 * mean_square.max(1e-10).log10();
+        let start = db_to_gain(self.gain_db);
+        if level > AGC_GATE_DB {
+            let wanted = (AGC_TARGET_DB - level).clamp(AGC_MIN_GAIN_DB, AGC_MAX_GAIN_DB);
+            let block_ms = block.len() as f32 * 1000.0 / SAMPLE_RATE as f32;
+            let max_step = if wanted < self.gain_db {
+                AGC_ATTACK_DB_PER_MS * block_ms
+            } else {
+                AGC_RELEASE_DB_PER_MS * block_ms
+            };
+            self.gain_db += (wanted - self.gain_db).clamp(-max_step, max_step);
+        }
+        let peak = block.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        let mut end = db_to_gain(self.gain_db);
+        if peak * end > AGC_LIMIT {
+            end = AGC_LIMIT / peak;
+            self.gain_db = 20.0 * end.log10();
+        }
+        // Ramp across the block so gain changes do not click.
+        let n = block.len() as f32;
+        for (i, s) in block.iter_mut().enumerate() {
+            let g = start + (end - start) * (i + 1) as f32 / n;
+            *s = (*s * g).clamp(-1.0, 1.0);
+        }
+    }
+}
+
+fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// The capture-side chain; runs in the mic callback, stages enabled per `VoiceControls`.
+pub struct MicDsp {
+    controls: Arc<VoiceControls>,
+    reference: Option<HeapCons<f32>>,
+    /// Set once the startup backlog of the reference has been dropped.
+    aligned: bool,
+    far: Vec<f32>,
+    /// The last `ECHO_MAX_DELAY` reference samples before this block, then the block's own.
+    far_line: VecDeque<f32>,
+    delayed: Vec<f32>,
+    delay: DelayEstimator,
+    /// Delay the filter currently works at (samples); None until the estimator has one.
+    aec_delay: Option<usize>,
+    aec: EchoCanceller,
+    ns: NoiseSuppressor,
+    agc: AutoGain,
+    aec_on: bool,
+    ns_on: bool,
+}
+
+impl MicDsp {
+    /// `reference` is the consumer of `echo_reference()` whose producer feeds the speaker; None disables echo cancellation.
+    pub fn new(controls: Arc<VoiceControls>, reference: Option<HeapCons<f32>>) -> Self {
+        Self {
+            controls,
+            reference,
+            aligned: false,
+            far: Vec::new(),
+            far_line: vec![0.0; ECHO_MAX_DELAY].into(),
+            delayed: Vec::new(),
+            delay: DelayEstimator::new(),
+            aec_delay: None,
+            aec: EchoCanceller::new(),
+            ns: NoiseSuppressor::new(),
+            agc: AutoGain { gain_db: 0.0 },
+            aec_on: false,
+            ns_on: false,
+        }
+    }
+
+    /// Process one captured block in place.
+    pub fn process(&mut self, block: &mut [f32]) {
+        // Always drain the reference so it stays aligned while echo cancellation is off.
+        self.far.clear();
+        if let Some(reference) = self.reference.as_mut() {
+            // Whatever queued up before the mic started would only add to the delay; after that the
+            // backlog grows only when the mic stalls, and the delay has to be found again.
+            let backlog = reference.occupied_len();
+            if backlog > block.len() && (!self.aligned || backlog > block.len() + ECHO_MAX_BACKLOG) {
+                reference.skip(backlog - block.len());
+                if self.aligned {
+                    self.delay.reset();
+                    self.aec_delay = None;
+                }
+                self.aligned = true;
+            }
+            self.far.extend(reference.pop_iter().take(block.len()));
+        }
+        self.far.resize(block.len(), 0.0);
+        self.far_line.extend(&self.far);
+        let excess = self.far_line.len() - ECHO_MAX_DELAY - block.len();
+        self.far_line.drain(..excess);
+
+        let aec_on = self.reference.is_some() && self.controls.echo_cancellation.load(Ordering::Relaxed);
+        if aec_on != self.aec_on {
+            self.aec.reset();
+            self.delay.reset();
+            self.aec_delay = None;
+            self.aec_on = aec_on;
+        }
+        if aec_on {
+            self.delay.process(&self.far, block);
+            self.follow_delay();
+            if let Some(delay) = self.aec_delay {
+                // The reference `delay` samples before the one paired with each mic sample.
+                let start = ECHO_MAX_DELAY - delay;
+                self.delayed.clear();
+                self.delayed.extend(self.far_line.range(start..start + block.len()));
+                self.aec.process(block, &self.delayed);
+            }
+        }
+
+        let ns_on = self.controls.noise_suppression.load(Ordering::Relaxed);
+        if ns_on != self.ns_on {
+            self.ns.reset();
+            self.ns_on = ns_on;
+        }
+        if ns_on {
+            self.ns.process(block);
+        }
+
+        if self.controls.auto_gain.load(Ordering::Relaxed) {
+            self.agc.process(block);
+        } else {
+            self.agc.gain_db = 0.0;
+        }
+    }
+
+    /// Move the filter when the estimated delay leaves the first half of its window; it has to relearn there.
+    fn follow_delay(&mut self) {
+        let Some(estimate) = self.delay.delay else {
+            return;
+        };
+        let in_window = self
+            .aec_delay
+            .is_some_and(|d| estimate >= d && estimate < d + ECHO_TAPS / 2);
+        if !in_window {
+            self.aec_delay = Some(estimate.saturating_sub(ECHO_DELAY_MARGIN));
+            self.aec.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Speech-like far end: noise shaped into bursts at a syllable-like rate.
+    fn far_signal(len: usize) -> Vec<f32> {
+        let mut seed = 1u32;
+        (0..len)
+            .map(|n| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let noise = (seed >> 8) as f32 / (1u32 << 23) as f32 - 1.0;
+                let t = n as f32 / SAMPLE_RATE as f32;
+                let envelope = (std::f32::consts::TAU * 4.0 * t).sin().max(0.0) * (0.6 + 0.4 * (std::f32::consts::TAU * 0.7 * t).sin());
+                0.3 * noise * envelope
+            })
+            .collect()
+    }
+
+    fn energy(samples: &[f32]) -> f32 {
+        samples.iter().map(|s| s * s).sum()
+    }
+
+    /// Echo 40 ms behind the reference (well past the filter's own 21 ms) is found and cancelled.
+    #[test]
+    fn cancels_delayed_echo() {
+        const BLOCK: usize = SAMPLE_RATE / 100;
+        const DELAY: usize = SAMPLE_RATE * 40 / 1000;
+        let controls = Arc::new(VoiceControls::default());
+        controls.echo_cancellation.store(true, Ordering::Relaxed);
+        controls.noise_suppression.store(false, Ordering::Relaxed);
+        controls.auto_gain.store(false, Ordering::Relaxed);
+        let (mut producer, consumer) = echo_reference();
+        let mut dsp = MicDsp::new(controls, Some(consumer));
+
+        let far = far_signal(SAMPLE_RATE * 8);
+        // Direct path plus one reflection.
+        let mic: Vec<f32> = (0..far.len())
+            .map(|n| {
+                let direct = n.checked_sub(DELAY).map_or(0.0, |i| 0.5 * far[i]);
+                let reflection = n.checked_sub(DELAY + 150).map_or(0.0, |i| -0.2 * far[i]);
+                direct + reflection
+            })
+            .collect();
+
+        // The speaker is filled a few blocks ahead before the mic starts.
+        producer.push_slice(&far[..BLOCK * 3]);
+        let mut out = Vec::with_capacity(mic.len());
+        for (n, chunk) in mic.chunks(BLOCK).enumerate() {
+            let ahead = (n + 4) * BLOCK;
+            if ahead <= far.len() {
+                producer.push_slice(&far[ahead - BLOCK..ahead]);
+            }
+            let mut block = chunk.to_vec();
+            dsp.process(&mut block);
+            out.extend(block);
+        }
+
+        // The backlog is cut to one block, so each mic sample is paired with the reference three blocks
+        // after the one it was captured with; the echo trails that by the air path and those blocks.
+        let estimate = dsp.delay.delay.expect("no delay estimate");
+        assert!(estimate.abs_diff(DELAY + 3 * BLOCK) <= 2 * DELAY_BIN, "delay estimate {estimate}");
+        let tail = mic.len() - 2 * SAMPLE_RATE;
+        let erle_db = 10.0 * (energy(&mic[tail..]) / energy(&out[tail..]).max(1e-12)).log10();
+        assert!(erle_db > 20.0, "echo reduced by only {erle_db:.1} dB");
+    }
+}
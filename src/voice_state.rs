@@ -0,0 +1,91 @@
+//! Who is in which voice channel, from READY `guilds[].voice_states` and VOICE_STATE_UPDATE.
+//! A user has at most one voice state per guild; `channel_id` None means they left.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::gateway::{deserialize_snowflake_string, deserialize_snowflake_string_opt};
+use crate::http::GuildMember;
+
+/// One user's voice state. READY omits `guild_id` (it is filled in from the guild) and `member`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct VoiceState {
+    #[serde(default, deserialize_with = "deserialize_snowflake_string_opt")]
+    pub guild_id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_snowflake_string_opt")]
+    pub channel_id: Option<String>,
+    #[serde(deserialize_with = "deserialize_snowflake_string")]
+    pub user_id: String,
+    #[serde(default)]
+    pub session_id: String,
+    /// Server mute / deafen (by a moderator).
+    #[serde(default)]
+    pub mute: bool,
+    #[serde(default)]
+    pub deaf: bool,
+    #[serde(default)]
+    pub self_mute: bool,
+    #[serde(default)]
+    pub self_deaf: bool,
+    /// Go Live stream.
+    #[serde(default)]
+    pub self_stream: bool,
+    #[serde(default)]
+    pub self_video: bool,
+    #[serde(default)]
+    pub member: Option<GuildMember>,
+}
+
+impl VoiceState {
+    pub fn is_muted(&self) -> bool {
+        self.mute || self.self_mute
+    }
+
+    pub fn is_deafened(&self) -> bool {
+        self.deaf || self.self_deaf
+    }
+}
+
+/// Voice states of all guilds we know about, keyed by (guild ID, user ID).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VoiceStates {
+    states: HashMap<(Option<String>, String), VoiceState>,
+}
+
+impl VoiceStates {
+    /// Replace everything (READY).
+    pub fn reset(&mut self, states: Vec<VoiceState>) {
+        self.states.clear();
+        for vs in states {
+            self.apply(vs);
+        }
+    }
+
+    /// Insert or move a user, or drop them when they left the channel.
+    pub fn apply(&mut self, vs: VoiceState) {
+        let key = (vs.guild_id.clone(), vs.user_id.clone());
+        if vs.channel_id.is_some() {
+            self.states.insert(key, vs);
+        } else {
+            self.states.remove(&key);
+        }
+    }
+
+    /// Participants of one channel, sorted by user ID.
+    pub fn in_channel(&self, channel_id: &str) -> Vec<&VoiceState> {
+        let mut list: Vec<&VoiceState> = self
+            .states
+            .values()
+            .filter(|vs| vs.channel_id.as_deref() == Some(channel_id))
+            .collect();
+        list.sort_by(|a, b| a.user_id.cmp(&b.user_id));
+        list
+    }
+
+    /// Everyone in a voice channel of `guild_id`.
+    pub fn in_guild<'a>(&'a self, guild_id: &'a str) -> impl Iterator<Item = &'a VoiceState> + 'a {
+        self.states
+            .values()
+            .filter(move |vs| vs.guild_id.as_deref() == Some(guild_id))
+    }
+}